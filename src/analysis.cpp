@@ -1,10 +1,18 @@
+#include <algorithm>
 #include <boost/archive/text_iarchive.hpp>
 #include <boost/archive/text_oarchive.hpp>
+#include <filesystem>
 #include <fstream>
+#include <queue>
+#include <set>
+#include <sstream>
 
 #include "analysis.hpp"
 
 #include "cpu.hpp"
+#include "hangpoint.hpp"
+#include "preservation.hpp"
+#include "regionclear.hpp"
 #include "utils.hpp"
 
 using namespace std;
@@ -36,6 +44,15 @@ size_t hash_value(const Reference& reference) {
   return seed;
 }
 
+/**************
+ *  CallSite  *
+ **************/
+
+bool CallSite::operator==(const CallSite& other) const {
+  return caller == other.caller && callee == other.callee && pc == other.pc &&
+         indirect == other.indirect;
+}
+
 /**************
  *  Analysis  *
  **************/
@@ -56,6 +73,16 @@ void Analysis::clear() {
   instructions.clear();
   subroutines.clear();
   references.clear();
+  referrers.clear();
+
+  // Tables resolved by emulation are resolved again by the next run.
+  for (auto it = jumpTables.begin(); it != jumpTables.end();) {
+    if (it->second.status == JumpTableStatus::Emulated) {
+      it = jumpTables.erase(it);
+    } else {
+      ++it;
+    }
+  }
 }
 
 // Reset the analysis (start from scratch).
@@ -64,19 +91,62 @@ void Analysis::reset() {
 
   comments.clear();
   customLabels.clear();
+  ramLabels.clear();
+  bookmarks.clear();
+  watches.clear();
+  reviewFlags.clear();
+  interruptHandlers.clear();
   assertions.clear();
   jumpTables.clear();
 
-  entryPoints = {
-      {"reset", rom.resetVector(), State()},
-      {"nmi", rom.nmiVector(), State()},
+  sweepCursor = 0;
+  sweepQuarantine.clear();
+  sweepRejected.clear();
+  dataBlocks.clear();
+  savedChecksum = nullopt;
+
+  const vector<pair<string, SubroutinePC>> vectors = {
+      {"reset", rom.resetVector()},
+      {"nmi", rom.nmiVector()},
   };
+  // Vectors pointing to RAM, or left blank, are not used.
+  entryPoints.clear();
+  for (auto& [label, pc] : vectors) {
+    if (!ROM::isRAM(pc) && pc != 0xFFFF) {
+      addEntryPoint(label, pc);
+    }
+  }
 }
 
-// Try to load the analysis from a saved state.
-bool Analysis::load() {
+// First word of the line describing a save file, before the analysis.
+static const string SAVE_HEADER = "gilgamesh";
+
+// Read the line describing a save file, if any. Files saved by older
+// versions have no such line: they are rewound to the start.
+static SavedAnalysis readSaveHeader(istream& file) {
+  SavedAnalysis save;
+  string line;
+  getline(file, line);
+  istringstream header(line);
+  string word;
+  time_t timestamp;
+  size_t subroutines;
+  if (header >> word && word == SAVE_HEADER && header >> timestamp >>
+                                                   subroutines) {
+    save.timestamp = timestamp;
+    save.subroutines = subroutines;
+  } else {
+    file.clear();
+    file.seekg(0);
+  }
+  return save;
+}
+
+// Try to load the analysis from a saved state, or a named one.
+bool Analysis::load(const string& name) {
   try {
-    ifstream file(rom.savePath());
+    ifstream file(rom.savePath(name));
+    readSaveHeader(file);
     boost::archive::text_iarchive archive(file);
     clear();
     archive >> *this;
@@ -86,29 +156,282 @@ bool Analysis::load() {
   }
 }
 
-// Save the results of the analysis.
-void Analysis::save() {
-  ofstream file(rom.savePath());
+// Save the results of the analysis, optionally under a name.
+void Analysis::save(const string& name) {
+  savedChecksum = rom.checksum();
+  ofstream file(rom.savePath(name));
+  file << SAVE_HEADER << " " << time(nullptr) << " " << subroutines.size()
+       << endl;
   boost::archive::text_oarchive archive(file);
   archive << *this;
 }
 
+// Return the saved analyses of the ROM, ordered by name.
+vector<SavedAnalysis> Analysis::savedAnalyses() const {
+  auto romPath = filesystem::absolute(rom.path);
+  auto prefix = romPath.stem().string() + ".";
+
+  vector<SavedAnalysis> saves;
+  error_code error;
+  for (auto& entry :
+       filesystem::directory_iterator(romPath.parent_path(), error)) {
+    auto path = entry.path();
+    auto stem = path.stem().string();
+    if (path.extension() != ".glm" ||
+        (stem + "." != prefix && stem.rfind(prefix, 0) != 0)) {
+      continue;
+    }
+    ifstream file(path);
+    auto save = readSaveHeader(file);
+    save.name = stem + "." == prefix ? "" : stem.substr(prefix.size());
+    saves.push_back(save);
+  }
+  sort(saves.begin(), saves.end(),
+       [](auto& a, auto& b) { return a.name < b.name; });
+  return saves;
+}
+
+// Return the user's annotations and settings, serialized.
+string Analysis::saveState() const {
+  ostringstream stream;
+  boost::archive::text_oarchive archive(stream);
+  archive << *this;
+  return stream.str();
+}
+
+// Restore the annotations and settings saved by saveState().
+void Analysis::restoreState(const string& state) {
+  istringstream stream(state);
+  boost::archive::text_iarchive archive(stream);
+  clear();
+  archive >> *this;
+}
+
+// Whether the loaded analysis was saved for a different version of the ROM
+// (e.g. before patching it), so its addresses might not match anymore.
+bool Analysis::romChanged() const {
+  return savedChecksum.has_value() && *savedChecksum != rom.checksum();
+}
+
 // Analyze the ROM.
 void Analysis::run() {
-  clear();
+  // A subroutine might be run into from the one before it, before being
+  // called: repeat the analysis until all such subroutines are known.
+  knownSubroutines.clear();
+  passes = 0;
+  do {
+    clear();
+    runEntryPoints();
+    passes++;
+  } while (learnSubroutineEntries());
+
+  generateLocalLabels();
+  PreservationAnalysis(this).run();
+  RegionClearAnalysis(this).run();
+  HangPointAnalysis(this).run();
+}
 
+// Run the CPU from each entry point.
+void Analysis::runEntryPoints() {
   for (auto& e : entryPoints) {
     addSubroutine(e.pc, e.label, true);
-    CPU cpu(this, e.pc, e.pc, e.state);
-    cpu.run();
+    for (auto state : entryStates(e)) {
+      entryPointCPU(e, state).run();
+    }
   }
+}
 
-  generateLocalLabels();
+// Return a CPU ready to run an entry point with the given state.
+CPU Analysis::entryPointCPU(const EntryPoint& entryPoint, State state) const {
+  // The CPU changes the analysis only when it's not on a dry run.
+  CPU cpu(const_cast<Analysis*>(this), entryPoint.pc, entryPoint.pc, state);
+  // Only the reset handler starts with the power-on D and DBR.
+  if (entryPoint.pc != rom.resetVector()) {
+    cpu.directPage = DirectPageStatus::Unknown;
+    cpu.dataBank = nullopt;
+  } else if (settings.emulationReset) {
+    // The CPU powers on in emulation mode, with 8-bits A and X.
+    cpu.state.e = true;
+    cpu.state.set(0x30);
+  }
+  // Values asserted by the user take precedence.
+  if (entryPoint.dataBank.has_value()) {
+    cpu.dataBank = entryPoint.dataBank;
+  }
+  if (entryPoint.directPage.has_value()) {
+    cpu.directPage = DirectPageStatus::Known;
+  }
+  return cpu;
+}
+
+// Return the states an entry point is run with. The NMI handler can be run
+// with every size of A and X: the state of its entry point comes last, so
+// that it takes precedence where the code differs.
+vector<State> Analysis::entryStates(const EntryPoint& entryPoint) const {
+  if (!settings.nmiAllStates || entryPoint.pc != rom.nmiVector()) {
+    return {entryPoint.state};
+  }
+  vector<State> states;
+  for (u8 sizes : {0x00, 0x10, 0x20, 0x30}) {
+    State state = entryPoint.state;
+    state.reset(0x30);
+    state.set(sizes);
+    if (!(state == entryPoint.state)) {
+      states.push_back(state);
+    }
+  }
+  states.push_back(entryPoint.state);
+  return states;
+}
+
+// Whether a subroutine starts at the given address, as far as we know.
+bool Analysis::isSubroutineEntry(InstructionPC pc) const {
+  return subroutines.count(pc) || knownSubroutines.count(pc);
+}
+
+// Remember the subroutines found so far. Return true if the code of one
+// of the new ones was analyzed as part of another subroutine.
+bool Analysis::learnSubroutineEntries() {
+  auto overlapped = false;
+  for (auto& [pc, subroutine] : subroutines) {
+    if (!knownSubroutines.insert(pc).second) {
+      continue;
+    }
+    auto search = instructions.find(pc);
+    if (search == instructions.end()) {
+      continue;
+    }
+    for (auto& instruction : search->second) {
+      if (instruction.subroutinePC != pc) {
+        overlapped = true;
+      }
+    }
+  }
+  return overlapped;
 }
 
 // Add an entry point to the analysis.
-void Analysis::addEntryPoint(string label, SubroutinePC pc, State state) {
-  entryPoints.emplace(EntryPoint{label, pc, state});
+void Analysis::addEntryPoint(string label,
+                             SubroutinePC pc,
+                             State state,
+                             optional<u8> dataBank,
+                             optional<u16> directPage) {
+  entryPoints.emplace(EntryPoint{label, pc, state, dataBank, directPage});
+}
+
+// Return why an address can't be an entry point (in RAM, or not mapped to
+// the ROM), if it can't.
+optional<string> Analysis::entryPointError(SubroutinePC pc) const {
+  if (ROM::isRAM(pc)) {
+    return format("$%06X is in RAM", pc);
+  }
+  if (!rom.isROM(pc)) {
+    return format("$%06X is not mapped to the ROM", pc);
+  }
+  return nullopt;
+}
+
+// Add the entry points listed in a file, one `ADDRESS NAME [STATE]` per line
+// (e.g. `$8000 reset m=1,x=1,db=7E`). Return the lines that couldn't be
+// added.
+vector<EntryPointIssue> Analysis::loadEntryPoints(const string& path) {
+  ifstream file(path);
+  if (!file) {
+    return {{0, "could not open the file"}};
+  }
+
+  vector<EntryPointIssue> issues;
+  string line;
+  for (size_t lineNumber = 1; getline(file, line); lineNumber++) {
+    istringstream fields(line.substr(0, line.find('#')));
+    string address, label, expression, extra;
+    if (!(fields >> address)) {
+      continue;  // Blank line or comment.
+    }
+    fields >> label >> expression >> extra;
+
+    // Addresses are in hexadecimal, with an optional `$` prefix.
+    if (address[0] == '$') {
+      address.erase(0, 1);
+    }
+    size_t parsed = 0;
+    SubroutinePC pc = 0;
+    try {
+      pc = stoul(address, &parsed, 16);
+    } catch (...) {
+    }
+    if (parsed == 0 || parsed != address.size() || pc > 0xFFFFFF) {
+      issues.push_back({lineNumber, "invalid address"});
+      continue;
+    }
+    if (label.empty() || !extra.empty()) {
+      issues.push_back({lineNumber, "expected ADDRESS NAME [STATE]"});
+      continue;
+    }
+    if (auto error = entryPointError(pc)) {
+      issues.push_back({lineNumber, *error});
+      continue;
+    }
+    auto entryState =
+        expression.empty() ? EntryPoint() : parseEntryState(expression);
+    if (!entryState.has_value()) {
+      issues.push_back({lineNumber, "invalid state"});
+      continue;
+    }
+
+    entryState->label = label;
+    entryState->pc = pc;
+    if (subroutines.count(pc) || instructions.count(pc) ||
+        entryPoints.count(*entryState)) {
+      issues.push_back({lineNumber, format("$%06X already analyzed", pc)});
+      continue;
+    }
+    addEntryPoint(label, pc, entryState->state, entryState->dataBank,
+                  entryState->directPage);
+  }
+  return issues;
+}
+
+// Parse an entry state expression (e.g. `m=1,x=0,db=7E,dp=0000`), if valid.
+// Only the state and the registers of the returned entry point are set.
+optional<EntryPoint> Analysis::parseEntryState(const string& expression) {
+  EntryPoint entryPoint{};
+  istringstream assignments(expression);
+  string assignment;
+  while (getline(assignments, assignment, ',')) {
+    auto equals = assignment.find('=');
+    if (equals == string::npos) {
+      return nullopt;
+    }
+    auto name = assignment.substr(0, equals);
+    auto value = assignment.substr(equals + 1);
+    transform(name.begin(), name.end(), name.begin(), ::tolower);
+
+    if (name == "m" || name == "x") {
+      if (value != "0" && value != "1") {
+        return nullopt;
+      }
+      (name == "m" ? entryPoint.state.m : entryPoint.state.x) = value == "1";
+      continue;
+    }
+
+    // Registers are in hexadecimal, with an optional `$` prefix.
+    if (!value.empty() && value[0] == '$') {
+      value.erase(0, 1);
+    }
+    size_t maxDigits = name == "db" ? 2 : name == "dp" ? 4 : 0;
+    if (value.empty() || value.size() > maxDigits ||
+        !all_of(value.begin(), value.end(), ::isxdigit)) {
+      return nullopt;
+    }
+    auto number = stoul(value, nullptr, 16);
+    if (name == "db") {
+      entryPoint.dataBank = number;
+    } else {
+      entryPoint.directPage = number;
+    }
+  }
+  return entryPoint;
 }
 
 // Add an instruction to the analysis.
@@ -131,6 +454,7 @@ Instruction* Analysis::addInstruction(InstructionPC pc,
   auto& subroutine = subroutines.at(subroutinePC);
   auto instructionPtr = (Instruction*)&(*instructionIter);
   subroutine.addInstruction(instructionPtr);
+  notify(InstructionAdded{pc, subroutinePC});
   // Return a pointer to the new instruction.
   return instructionPtr;
 }
@@ -141,12 +465,14 @@ void Analysis::addReference(InstructionPC source,
                             SubroutinePC subroutinePC) {
   auto& referenceSet = references.try_emplace(source).first->second;
   referenceSet.insert({target, subroutinePC});
+  referrers[target].insert({source, subroutinePC});
 }
 
 // Add a subroutine to the analysis.
 void Analysis::addSubroutine(SubroutinePC pc,
                              optional<string> label,
-                             bool isEntryPoint) {
+                             bool isEntryPoint,
+                             const StackTrace& stackTrace) {
   string labelValue;
 
   auto customLabel = customLabels.find({pc, pc});
@@ -155,10 +481,39 @@ void Analysis::addSubroutine(SubroutinePC pc,
   } else if (label.has_value()) {
     labelValue = *label;
   } else {
-    labelValue = format("sub_%06X", pc);
+    labelValue = format("%s%06X", settings.subroutinePrefix.c_str(), pc);
   }
 
-  subroutines.try_emplace(pc, pc, labelValue, isEntryPoint);
+  auto [subroutine, inserted] =
+      subroutines.try_emplace(pc, pc, labelValue, isEntryPoint);
+  if (inserted) {
+    notify(SubroutineDiscovered{pc, labelValue});
+  }
+  // The subroutine might have been reached before through a different path.
+  if (subroutine->second.addStackTrace(stackTrace)) {
+    propagateStackTrace(pc, stackTrace);
+  }
+}
+
+// Extend a new stack trace to the subroutines called by a subroutine.
+void Analysis::propagateStackTrace(SubroutinePC pc,
+                                   const StackTrace& stackTrace) {
+  for (auto& [instructionPC, instruction] : subroutines.at(pc).instructions) {
+    // Recursive calls would make the trace grow forever.
+    if (instruction->type() != InstructionType::Call ||
+        find(stackTrace.begin(), stackTrace.end(), instructionPC) !=
+            stackTrace.end()) {
+      continue;
+    }
+
+    auto calleeStackTrace = stackTrace;
+    calleeStackTrace.push_back(instructionPC);
+    for (auto& [target, callee] : followTargets(instructionPC, pc)) {
+      if (subroutines.at(callee).addStackTrace(calleeStackTrace)) {
+        propagateStackTrace(callee, calleeStackTrace);
+      }
+    }
+  }
 }
 
 // Get an assertion for an instruction, if any.
@@ -184,6 +539,39 @@ void Analysis::removeAssertion(InstructionPC pc, SubroutinePC subroutinePC) {
   assertions.erase({pc, subroutinePC});
 }
 
+// Change the confidence of an existing assertion.
+bool Analysis::setAssertionConfidence(InstructionPC pc,
+                                      SubroutinePC subroutinePC,
+                                      AssertionConfidence confidence) {
+  auto search = assertions.find({pc, subroutinePC});
+  if (search == assertions.end()) {
+    return false;
+  }
+  search->second.confidence = confidence;
+  return true;
+}
+
+// Return the coordinates of the assertions, optionally of a given
+// confidence, ordered by address.
+vector<PCPair> Analysis::assertionPCs(
+    optional<AssertionConfidence> confidence) const {
+  vector<PCPair> pcs;
+  for (auto& [pc, assertion] : assertions) {
+    if (!confidence.has_value() || assertion.confidence == *confidence) {
+      pcs.push_back(pc);
+    }
+  }
+  sort(pcs.begin(), pcs.end());
+  return pcs;
+}
+
+// Whether an assertion is on an instruction the analysis hasn't reached.
+bool Analysis::isDanglingAssertion(PCPair pc) const {
+  auto search = subroutines.find(pc.second);
+  return search == subroutines.end() ||
+         search->second.instructions.count(pc.first) == 0;
+}
+
 // Define a jump table: caller spans a jumptable going from x to y (included).
 void Analysis::defineJumpTable(InstructionPC callerPC,
                                pair<u16, u16> range,
@@ -201,6 +589,7 @@ void Analysis::defineJumpTable(InstructionPC callerPC,
     jumpTable.targets.insert_or_assign(x, target);
   }
   jumpTable.status = status;
+  jumpTable.guessed = false;
 }
 
 // Undefine a jump table.
@@ -208,10 +597,84 @@ void Analysis::undefineJumpTable(InstructionPC callerPC) {
   auto& jumpTable = jumpTables.at(callerPC);
   jumpTable.targets.clear();
   jumpTable.status = JumpTableStatus::Unknown;
+  jumpTable.guessed = false;
+}
+
+// Guess the tables of the unresolved jumps and calls indexed by X, from the
+// words following their address. Return the jumps and calls whose table
+// was guessed, ordered by address.
+vector<InstructionPC> Analysis::guessJumpTables() {
+  // Addresses covered by code, which a table can't overlap.
+  set<u24> code;
+  for (auto& [pc, instructionSet] : instructions) {
+    for (auto& instruction : instructionSet) {
+      for (size_t i = 0; i < instruction.size(); i++) {
+        code.insert(pc + i);
+      }
+    }
+  }
+
+  // Addresses named by a label, which start something other than the table.
+  set<u24> labels;
+  for (auto& [pc, subroutine] : subroutines) {
+    labels.insert(pc);
+  }
+  for (auto& [pcPair, label] : customLabels) {
+    labels.insert(pcPair.first);
+  }
+
+  // A table stops at known code, data blocks and the next label.
+  auto isTable = [&](u24 entry, bool first) {
+    for (auto address : {entry, entry + 1}) {
+      if (!rom.isROM(address) || code.count(address) ||
+          dataBlockAt(address) != nullptr ||
+          (labels.count(address) && !(first && address == entry))) {
+        return false;
+      }
+    }
+    return true;
+  };
+  // An entry must point to code in the same bank that doesn't start in the
+  // middle of known code or data. In strict mode, it also can't start with
+  // an opcode that never appears in code.
+  auto isPlausibleTarget = [&](u24 entry) {
+    auto target = (entry & 0xFF0000) | rom.readWord(entry);
+    return rom.isROM(target) && dataBlockAt(target) == nullptr &&
+           (!code.count(target) || instructions.count(target)) &&
+           (!settings.strictMode ||
+            !settings.dataOpcodes.count(rom.readByte(target)));
+  };
+
+  vector<InstructionPC> guessed;
+  for (auto& [pc, jumpTable] : jumpTables) {
+    auto caller = anyInstruction(pc);
+    if (jumpTable.status != JumpTableStatus::Unknown || caller == nullptr ||
+        caller->addressMode() != AddressMode::AbsoluteIndexedIndirect) {
+      continue;
+    }
+
+    // The table lives in the program bank, and can't cross its end.
+    auto bank = pc & 0xFF0000;
+    u32 table = *caller->argument();
+    size_t entries = 0;
+    while (entries < JumpTable::MAX_GUESSED_ENTRIES &&
+           table + entries * 2 + 1 <= 0xFFFF &&
+           isTable(bank | (table + entries * 2), entries == 0) &&
+           isPlausibleTarget(bank | (table + entries * 2))) {
+      entries++;
+    }
+    if (entries > 0) {
+      defineJumpTable(pc, {0, (entries - 1) * 2});
+      jumpTable.guessed = true;
+      guessed.push_back(pc);
+    }
+  }
+  sort(guessed.begin(), guessed.end());
+  return guessed;
 }
 
 // Return any of the instructions at address PC.
-const Instruction* Analysis::anyInstruction(InstructionPC pc) {
+const Instruction* Analysis::anyInstruction(InstructionPC pc) const {
   auto search = instructions.find(pc);
   if (search == instructions.end()) {
     return nullptr;
@@ -220,6 +683,526 @@ const Instruction* Analysis::anyInstruction(InstructionPC pc) {
   }
 }
 
+// Return the indirect jumps or calls, ordered by address.
+vector<InstructionPC> Analysis::indirectJumps(InstructionType type) const {
+  vector<InstructionPC> pcs;
+  for (auto& [pc, jumpTable] : jumpTables) {
+    auto instruction = anyInstruction(pc);
+    if (instruction != nullptr && instruction->type() == type) {
+      pcs.push_back(pc);
+    }
+  }
+  sort(pcs.begin(), pcs.end());
+  return pcs;
+}
+
+// Return the returns jumping to an address pushed on the stack, with their
+// target, ordered by address.
+vector<pair<PCPair, InstructionPC>> Analysis::returnJumps() const {
+  vector<pair<PCPair, InstructionPC>> jumps;
+  for (auto& [subroutinePC, subroutine] : subroutines) {
+    for (auto& [pc, target] : subroutine.returnJumps) {
+      jumps.push_back({{pc, subroutinePC}, target});
+    }
+  }
+  sort(jumps.begin(), jumps.end());
+  return jumps;
+}
+
+// Return the locations an instruction transfers control to.
+vector<PCPair> Analysis::followTargets(InstructionPC pc,
+                                       SubroutinePC subroutinePC) const {
+  vector<PCPair> targets;
+  auto search = references.find(pc);
+  if (search == references.end()) {
+    return targets;
+  }
+
+  for (auto& reference : search->second) {
+    if (reference.subroutinePC != subroutinePC) {
+      continue;
+    }
+    // Calls lead to the beginning of a subroutine,
+    // everything else stays inside the current one.
+    if (subroutines.count(reference.target)) {
+      targets.push_back({reference.target, reference.target});
+    } else {
+      targets.push_back({reference.target, subroutinePC});
+    }
+  }
+  sort(targets.begin(), targets.end());
+  return targets;
+}
+
+// Return the instructions that transfer control to an address.
+vector<PCPair> Analysis::referencesTo(InstructionPC target) const {
+  auto search = referrers.find(target);
+  if (search == referrers.end()) {
+    return {};
+  }
+  return {search->second.begin(), search->second.end()};
+}
+
+// Return the shortest chain of calls from a subroutine to another.
+CallPath Analysis::shortestPath(SubroutinePC from, SubroutinePC to) const {
+  CallPath path;
+  if (!subroutines.count(from) || !subroutines.count(to)) {
+    return path;
+  }
+
+  // Breadth-first search over the call graph, remembering where we came from.
+  unordered_map<SubroutinePC, SubroutinePC> previous = {{from, from}};
+  queue<SubroutinePC> frontier;
+  frontier.push(from);
+
+  while (!frontier.empty() && !previous.count(to)) {
+    auto pc = frontier.front();
+    frontier.pop();
+
+    for (auto& [instructionPC, instruction] : subroutines.at(pc).instructions) {
+      auto type = instruction->type();
+      if (type != InstructionType::Call && type != InstructionType::Jump) {
+        continue;
+      }
+
+      // Targets we don't know about could lead anywhere.
+      auto jumpTable = jumpTables.find(instructionPC);
+      if (jumpTable != jumpTables.end() &&
+          jumpTable->second.status != JumpTableStatus::Complete) {
+        path.gaps.push_back(instructionPC);
+      }
+
+      for (auto& [target, callee] : followTargets(instructionPC, pc)) {
+        if (callee != pc && previous.try_emplace(callee, pc).second) {
+          frontier.push(callee);
+        }
+      }
+    }
+  }
+
+  if (previous.count(to)) {
+    for (auto pc = to; pc != from; pc = previous.at(pc)) {
+      path.subroutines.push_back(pc);
+    }
+    path.subroutines.push_back(from);
+    reverse(path.subroutines.begin(), path.subroutines.end());
+  }
+  sort(path.gaps.begin(), path.gaps.end());
+  return path;
+}
+
+// Return the instructions accessing WRAM or hardware registers, or jumping
+// into WRAM, grouped by the address they target.
+map<u24, vector<PCPair>> Analysis::ramReferences() const {
+  map<u24, vector<PCPair>> references;
+  for (auto& [pc, subroutine] : subroutines) {
+    for (auto& [instructionPC, instruction] : subroutine.instructions) {
+      optional<u24> address = instruction->ramAddress();
+      if (!address.has_value()) {
+        address = instruction->hardwareRegister();
+      }
+      auto target = instruction->absoluteArgument();
+      if (!address.has_value() && instruction->isControl() &&
+          target.has_value()) {
+        address = ROM::wramAddress(*target);
+      }
+      if (address.has_value()) {
+        references[*address].push_back(instruction->pcPair());
+      }
+    }
+  }
+  for (auto& [address, sources] : references) {
+    sort(sources.begin(), sources.end());
+  }
+  return references;
+}
+
+// Return the instructions reading or writing WRAM, hardware registers or
+// data in the ROM, grouped by the address they access.
+map<u24, vector<PCPair>> Analysis::dataReferences() const {
+  map<u24, vector<PCPair>> references;
+  for (auto& [pc, subroutine] : subroutines) {
+    for (auto& [instructionPC, instruction] : subroutine.instructions) {
+      if (instruction->isControl()) {
+        continue;
+      }
+      optional<u24> address = instruction->ramAddress();
+      if (!address.has_value()) {
+        address = instruction->hardwareRegister();
+      }
+      if (!address.has_value()) {
+        auto target = instruction->dataAddress();
+        if (target.has_value() && rom.isROM(*target)) {
+          address = target;
+        }
+      }
+      if (address.has_value()) {
+        references[*address].push_back(instruction->pcPair());
+      }
+    }
+  }
+  for (auto& [address, sources] : references) {
+    sort(sources.begin(), sources.end());
+  }
+  return references;
+}
+
+// Return the pairs of caller and callee subroutines, ordered by address.
+vector<pair<SubroutinePC, SubroutinePC>> Analysis::callEdges() const {
+  set<pair<SubroutinePC, SubroutinePC>> edges;
+  for (auto& [pc, subroutine] : subroutines) {
+    for (auto& [instructionPC, instruction] : subroutine.instructions) {
+      if (instruction->type() != InstructionType::Call) {
+        continue;
+      }
+      for (auto& [target, callee] : followTargets(instructionPC, pc)) {
+        edges.insert({pc, callee});
+      }
+    }
+  }
+  return {edges.begin(), edges.end()};
+}
+
+// Return the calls, and the jumps into other subroutines, ordered by caller
+// and by address.
+vector<CallSite> Analysis::callSites() const {
+  vector<CallSite> sites;
+  for (auto& [pc, subroutine] : subroutines) {
+    for (auto& [instructionPC, instruction] : subroutine.instructions) {
+      auto type = instruction->type();
+      if (type != InstructionType::Call && type != InstructionType::Jump) {
+        continue;
+      }
+      bool indirect = jumpTables.count(instructionPC) > 0;
+      for (auto& [target, callee] : followTargets(instructionPC, pc)) {
+        // Jumps inside the subroutine itself are not calls.
+        if (type == InstructionType::Jump && callee == pc) {
+          continue;
+        }
+        sites.push_back({pc, callee, instructionPC, indirect});
+      }
+    }
+  }
+  return sites;
+}
+
+// Return the pairs of instructions decoded from overlapping bytes, i.e. the
+// second one starts inside the first one, or at the same address but with a
+// different size.
+vector<pair<InstructionPC, InstructionPC>>
+Analysis::overlappingInstructions() const {
+  // Sizes of the instructions decoded at each address.
+  map<InstructionPC, set<size_t>> sizes;
+  for (auto& [pc, instructionSet] : instructions) {
+    for (auto& instruction : instructionSet) {
+      sizes[pc].insert(instruction.size());
+    }
+  }
+
+  vector<pair<InstructionPC, InstructionPC>> overlaps;
+  for (auto& [pc, pcSizes] : sizes) {
+    if (pcSizes.size() > 1) {
+      overlaps.push_back({pc, pc});
+    }
+    auto end = pc + *pcSizes.rbegin();
+    for (auto other = pc + 1; other < end; other++) {
+      if (sizes.count(other)) {
+        overlaps.push_back({pc, other});
+      }
+    }
+  }
+  return overlaps;
+}
+
+// Return what the emulator knows about the CPU when it reaches an
+// instruction, by replaying its subroutine.
+optional<CPUSnapshot> Analysis::cpuSnapshot(InstructionPC pc,
+                                            SubroutinePC subroutinePC) const {
+  auto entryInstructions = instructions.find(subroutinePC);
+  if (entryInstructions == instructions.end()) {
+    return nullopt;
+  }
+  // Subroutines other than entry points start with nothing known about
+  // the registers.
+  EntryPoint entryPoint{"", subroutinePC, State(), nullopt, nullopt};
+  for (auto& e : entryPoints) {
+    if (e.pc == subroutinePC) {
+      entryPoint = e;
+    }
+  }
+
+  // The subroutine is replayed from each state it was entered with, taking
+  // the state changes of the subroutines it calls from the analysis.
+  DryRun dryRun;
+  dryRun.followCalls = false;
+  dryRun.snapshotPC = {pc, subroutinePC};
+  for (auto& entry : entryInstructions->second) {
+    if (entry.subroutinePC != subroutinePC) {
+      continue;
+    }
+    auto cpu = entryPointCPU(entryPoint, entry.state);
+    cpu.state = entry.state;
+    cpu.dryRun = &dryRun;
+    cpu.run();
+    if (dryRun.snapshot.has_value()) {
+      break;
+    }
+  }
+  return dryRun.snapshot;
+}
+
+// Emulate up to a number of instructions from an entry point, without
+// changing the analysis. Return the instructions emulated, with the state
+// before and after them, and the points where the emulation stopped.
+vector<TraceStep> Analysis::trace(const EntryPoint& entryPoint,
+                                  size_t maxInstructions) const {
+  DryRun dryRun;
+  dryRun.budget = maxInstructions;
+  for (auto state : entryStates(entryPoint)) {
+    auto cpu = entryPointCPU(entryPoint, state);
+    cpu.dryRun = &dryRun;
+    cpu.run();
+  }
+  return dryRun.steps;
+}
+
+// Return the instructions performing an operation (on a given argument,
+// if any), ordered by address.
+vector<PCPair> Analysis::findInstructions(Op operation,
+                                          optional<u24> argument) const {
+  vector<PCPair> matches;
+  for (auto& [pc, instructionSet] : instructions) {
+    for (auto& instruction : instructionSet) {
+      if (instruction.operation() == operation &&
+          (!argument.has_value() || instruction.argument() == argument)) {
+        matches.push_back(instruction.pcPair());
+      }
+    }
+  }
+  sort(matches.begin(), matches.end());
+  return matches;
+}
+
+// Return the instructions whose bytes span an address.
+vector<PCPair> Analysis::instructionsAt(u24 address) const {
+  // Instructions are at most 4 bytes long.
+  vector<PCPair> matches;
+  for (u24 pc = address >= 3 ? address - 3 : 0; pc <= address; pc++) {
+    auto search = instructions.find(pc);
+    if (search == instructions.end()) {
+      continue;
+    }
+    for (auto& instruction : search->second) {
+      if (address < pc + instruction.size()) {
+        matches.push_back(instruction.pcPair());
+      }
+    }
+  }
+  sort(matches.begin(), matches.end());
+  return matches;
+}
+
+// Return the instruction to show for an address: the start of the
+// subroutine at that address if any, otherwise the instruction spanning it
+// in the subroutine with the lowest address.
+optional<PCPair> Analysis::locate(u24 address) const {
+  if (subroutines.count(address)) {
+    return PCPair{address, address};
+  }
+
+  auto matches = instructionsAt(address);
+  if (matches.empty()) {
+    return nullopt;
+  }
+  return *min_element(matches.begin(), matches.end(),
+                      [](const PCPair& a, const PCPair& b) {
+                        return make_pair(a.second, a.first) <
+                               make_pair(b.second, b.first);
+                      });
+}
+
+// Return the number of places where the analysis couldn't proceed.
+size_t Analysis::unresolvedCount() const {
+  unordered_set<InstructionPC> unresolved;
+  for (auto& [pc, subroutine] : subroutines) {
+    for (auto& [instructionPC, stateChange] : subroutine.unknownStateChanges) {
+      unresolved.insert(instructionPC);
+    }
+  }
+  // Jump tables of code that is no longer reached don't count.
+  for (auto& [pc, jumpTable] : jumpTables) {
+    if (jumpTable.status == JumpTableStatus::Unknown &&
+        instructions.count(pc)) {
+      unresolved.insert(pc);
+    }
+  }
+  return unresolved.size();
+}
+
+// Whether the control flow has been fully resolved.
+bool Analysis::isComplete() const {
+  return unresolvedCount() == 0;
+}
+
+// Return the regions of the ROM that are neither code nor declared data,
+// ordered by address. The header is left out.
+vector<UnexploredRange> Analysis::unexploredRanges() const {
+  vector<bool> covered(rom.realSize());
+  auto cover = [&](u24 address) {
+    if (rom.isROM(address)) {
+      covered[rom.translate(address)] = true;
+    }
+  };
+  for (auto& [pc, instructionSet] : instructions) {
+    for (auto& instruction : instructionSet) {
+      for (size_t i = 0; i < instruction.size(); i++) {
+        cover(pc + i);
+      }
+    }
+  }
+  for (auto& [start, block] : dataBlocks) {
+    for (auto address = start; address < block.end; address++) {
+      cover(address);
+    }
+  }
+
+  vector<UnexploredRange> ranges;
+  for (u24 offset = 0; offset < rom.realSize(); offset++) {
+    auto address = rom.address(offset);
+    if (covered[offset] || rom.isHeader(address)) {
+      continue;
+    }
+    // Extend the previous range if the addresses are contiguous.
+    if (!ranges.empty() && ranges.back().end + 1 == address) {
+      ranges.back().end = address;
+      ranges.back().size++;
+    } else {
+      ranges.push_back({address, address, 1});
+    }
+  }
+
+  sort(ranges.begin(), ranges.end(),
+       [](auto& a, auto& b) { return a.start < b.start; });
+  return ranges;
+}
+
+// Return a summary of how much of the ROM the analysis has covered.
+AnalysisStatistics Analysis::statistics() const {
+  AnalysisStatistics statistics;
+  statistics.romSize = rom.realSize();
+  statistics.subroutines = subroutines.size();
+
+  // Bytes shared by overlapping instructions are only counted once.
+  vector<bool> covered(rom.realSize());
+  for (auto& [pc, instructionSet] : instructions) {
+    for (auto& instruction : instructionSet) {
+      for (size_t i = 0; i < instruction.size(); i++) {
+        if (rom.isROM(pc + i) && !covered[rom.translate(pc + i)]) {
+          covered[rom.translate(pc + i)] = true;
+          statistics.codeBytes++;
+        }
+      }
+    }
+  }
+
+  for (auto& [pc, subroutine] : subroutines) {
+    if (subroutine.unknownStateChanges.empty()) {
+      continue;
+    }
+    statistics.unknownSubroutines++;
+    set<UnknownReason> reasons;
+    for (auto& [instructionPC, stateChange] : subroutine.unknownStateChanges) {
+      reasons.insert(stateChange.unknownReason);
+    }
+    for (auto reason : reasons) {
+      statistics.unknownReasons[reason]++;
+    }
+  }
+
+  // Jump tables of code that is no longer reached don't count.
+  for (auto& [pc, jumpTable] : jumpTables) {
+    if (jumpTable.status == JumpTableStatus::Unknown &&
+        instructions.count(pc)) {
+      statistics.unresolvedJumps++;
+    }
+  }
+  return statistics;
+}
+
+// Percentage of the ROM decoded as code.
+double AnalysisStatistics::coverage() const {
+  return romSize == 0 ? 0.0 : 100.0 * codeBytes / romSize;
+}
+
+// Return a textual report of the statistics.
+string AnalysisStatistics::report() const {
+  auto text = format("Code: %zu bytes, %.1f%% of the ROM (%zu bytes)\n",
+                     codeBytes, coverage(), romSize);
+  text += format("Subroutines: %zu\n", subroutines);
+  text += format("Subroutines with unknown state changes: %zu\n",
+                 unknownSubroutines);
+  for (auto& [reason, count] : unknownReasons) {
+    text += format("  %s: %zu\n", unknownReasonName(reason).c_str(), count);
+  }
+  text += format("Unresolved indirect jumps: %zu\n", unresolvedJumps);
+  return text;
+}
+
+// Accept code found by the sweep, turning it into an entry point.
+bool Analysis::acceptSweepCandidate(SubroutinePC pc) {
+  if (sweepQuarantine.erase(pc) == 0) {
+    return false;
+  }
+  addEntryPoint(format("sweep_%06X", pc), pc);
+  return true;
+}
+
+// Reject code found by the sweep, so that it isn't proposed again.
+bool Analysis::rejectSweepCandidate(SubroutinePC pc) {
+  auto search = sweepQuarantine.find(pc);
+  if (search == sweepQuarantine.end()) {
+    return false;
+  }
+  sweepRejected.insert(*search);
+  sweepQuarantine.erase(search);
+  return true;
+}
+
+// Declare a region of the ROM as data. Return false if the region is empty,
+// outside the ROM, or overlaps an existing data block.
+bool Analysis::addDataBlock(u24 start, u24 end, DataType type) {
+  if (start >= end || !rom.isROM(start) || !rom.isROM(end - 1)) {
+    return false;
+  }
+
+  // The first block starting at or after this one, and the one before it.
+  auto next = dataBlocks.lower_bound(start);
+  if (next != dataBlocks.end() && next->second.start < end) {
+    return false;
+  }
+  if (next != dataBlocks.begin() && prev(next)->second.end > start) {
+    return false;
+  }
+
+  dataBlocks[start] = {start, end, type};
+  return true;
+}
+
+// Remove the data block starting at the given address.
+bool Analysis::removeDataBlock(u24 start) {
+  return dataBlocks.erase(start) > 0;
+}
+
+// Return the data block containing an address, if any.
+const DataBlock* Analysis::dataBlockAt(u24 address) const {
+  auto next = dataBlocks.upper_bound(address);
+  if (next == dataBlocks.begin()) {
+    return nullptr;
+  }
+  auto& block = prev(next)->second;
+  return address < block.end ? &block : nullptr;
+}
+
 // Return the label associated with an address, if any.
 optional<Label> Analysis::getLabel(InstructionPC pc,
                                    optional<SubroutinePC> subroutinePC) const {
@@ -230,36 +1213,172 @@ optional<Label> Analysis::getLabel(InstructionPC pc,
   }
 
   // Try to find an instruction label.
-  if (!subroutinePC.has_value()) {
+  if (!subroutinePC.has_value() || !subroutines.count(*subroutinePC)) {
     return nullopt;
   }
   auto& subroutine = subroutines.at(*subroutinePC);
-  auto* instruction = subroutine.instructions.at(pc);
-  return instruction->label;
+  auto instructionSearch = subroutine.instructions.find(pc);
+  if (instructionSearch == subroutine.instructions.end()) {
+    return nullopt;
+  }
+  return instructionSearch->second->label;
+}
+
+// Return the address named by a label, or written in hexadecimal with an
+// optional `$` prefix, if any. Labels take precedence.
+optional<u24> Analysis::resolveAddress(const string& text) const {
+  if (auto pc = labelPC(text)) {
+    return pc->first;
+  }
+
+  auto digits = (!text.empty() && text[0] == '$') ? text.substr(1) : text;
+  if (digits.empty() || digits.size() > 6 ||
+      !all_of(digits.begin(), digits.end(), ::isxdigit)) {
+    return nullopt;
+  }
+  return stoul(digits, nullptr, 16);
+}
+
+// Bookmark an address, with an optional note.
+void Analysis::addBookmark(u24 address, string note) {
+  bookmarks[address] = note;
+}
+
+// Remove a bookmark. Return false if the address wasn't bookmarked.
+bool Analysis::removeBookmark(u24 address) {
+  return bookmarks.erase(address) > 0;
+}
+
+// Watch a variable in WRAM, with an optional note. Return false if the
+// address is not in WRAM.
+bool Analysis::addWatch(u24 address, string note) {
+  auto ramAddress = ROM::wramAddress(address);
+  if (!ramAddress.has_value()) {
+    return false;
+  }
+  watches[*ramAddress] = note;
+  return true;
+}
+
+// Stop watching a variable. Return false if the address wasn't watched.
+bool Analysis::removeWatch(u24 address) {
+  auto ramAddress = ROM::wramAddress(address);
+  return ramAddress.has_value() && watches.erase(*ramAddress) > 0;
+}
+
+// Emulate BRK or COP as a call to a handler, or stop at them again if no
+// handler is given.
+void Analysis::setInterruptHandler(Op op, optional<SubroutinePC> handler) {
+  if (handler.has_value()) {
+    interruptHandlers[op] = *handler;
+  } else {
+    interruptHandlers.erase(op);
+  }
+}
+
+// Flag an instruction for review, or clear its flag.
+void Analysis::setReviewFlag(InstructionPC pc, bool flagged) {
+  if (flagged) {
+    reviewFlags.insert(pc);
+  } else {
+    reviewFlags.erase(pc);
+  }
+}
+
+// Name a variable in WRAM, or remove its name if the label is empty.
+// Return false if the label is invalid or names another variable, or the
+// address is not in WRAM.
+bool Analysis::labelRAM(const string& label, u24 address) {
+  auto ramAddress = ROM::wramAddress(address);
+  if (!ramAddress.has_value()) {
+    return false;
+  }
+  if (label.empty()) {
+    ramLabels.erase(*ramAddress);
+    return true;
+  }
+
+  if (!isValidLabel(label)) {
+    return false;
+  }
+  for (auto& [otherAddress, otherLabel] : ramLabels) {
+    if (otherLabel == label && otherAddress != *ramAddress) {
+      return false;
+    }
+  }
+  ramLabels[*ramAddress] = label;
+  return true;
+}
+
+// Whether a label is a valid identifier for asar.
+bool Analysis::isValidLabel(const string& label) {
+  if (label.empty() || isdigit(label[0])) {
+    return false;
+  }
+  return all_of(label.begin(), label.end(),
+                [](char c) { return isalnum(c) || c == '_'; });
+}
+
+// Return the instruction and subroutine a subroutine label or a combined
+// local label (`sub.loc`) refers to, if any.
+optional<PCPair> Analysis::labelPC(const string& label) const {
+  Label parsed(label);
+  for (auto& [pc, subroutine] : subroutines) {
+    if (subroutine.label != parsed.subroutineLabel) {
+      continue;
+    }
+    if (parsed.localLabel.empty()) {
+      return PCPair{pc, pc};
+    }
+    for (auto& [instructionPC, instruction] : subroutine.instructions) {
+      if (instruction->label.has_value() &&
+          instruction->label->localLabel == parsed.localLabel) {
+        return PCPair{instructionPC, pc};
+      }
+    }
+  }
+  return nullopt;
 }
 
-// Rename a subroutine or local label.
-void Analysis::renameLabel(string newLabel,
+// Rename a subroutine or local label. Return false if the new label is
+// invalid, or no subroutine or instruction is at the given address.
+bool Analysis::renameLabel(string newLabel,
                            InstructionPC pc,
                            optional<SubroutinePC> subroutinePC) {
-  customLabels[{pc, subroutinePC.value_or(pc)}] = newLabel;
+  auto subroutine = subroutines.find(subroutinePC.value_or(pc));
+  if (!isValidLabel(newLabel) || subroutine == subroutines.end() ||
+      subroutine->second.instructions.count(pc) == 0) {
+    return false;
+  }
+  customLabels[{pc, subroutine->first}] = newLabel;
+  return true;
+}
+
+// Send an event to the event sink, if any.
+void Analysis::notify(const AnalysisEvent& event) const {
+  if (eventSink) {
+    eventSink(event);
+  }
 }
 
 // Generate local label names.
 void Analysis::generateLocalLabels() {
   for (auto& [source, referenceSet] : references) {
     for (auto& [target, subroutinePC] : referenceSet) {
-      if (subroutines.count(target) == 0) {
+      // Targets the analysis couldn't execute (e.g. data) get no label.
+      auto& subroutine = subroutines.at(subroutinePC);
+      if (subroutines.count(target) == 0 &&
+          subroutine.instructions.count(target)) {
         string label;
 
         auto customLabel = customLabels.find({target, subroutinePC});
         if (customLabel != customLabels.end()) {
           label = customLabel->second;
         } else {
-          label = format("loc_%06X", target);
+          label = format("%s%06X", settings.localLabelPrefix.c_str(),
+                         target);
         }
 
-        auto& subroutine = subroutines.at(subroutinePC);
         auto& instruction = subroutine.instructions.at(target);
         instruction->label = Label(subroutine.label, label);
       }
@@ -1,42 +1,59 @@
 #pragma once
 
 #include <boost/container_hash/hash.hpp>
+#include <boost/serialization/map.hpp>
 #include <boost/serialization/unordered_map.hpp>
 #include <boost/serialization/unordered_set.hpp>
+#include <boost/serialization/version.hpp>
+#include <ctime>
 #include <map>
 #include <optional>
+#include <set>
 #include <string>
 #include <unordered_map>
 #include <unordered_set>
 #include <utility>
+#include <vector>
 
 #include "assertion.hpp"
+#include "cpu.hpp"
+#include "datablock.hpp"
+#include "events.hpp"
 #include "instruction.hpp"
 #include "jumptable.hpp"
 #include "rom.hpp"
+#include "settings.hpp"
 #include "state.hpp"
 #include "subroutine.hpp"
+#include "sweep.hpp"
 #include "types.hpp"
 
 /**
  * ROM's entry point.
  */
 struct EntryPoint {
-  std::string label;  // Subroutine's label.
-  SubroutinePC pc;    // Subroutine's PC.
-  State state;        // CPU's state.
+  std::string label;              // Subroutine's label.
+  SubroutinePC pc;                // Subroutine's PC.
+  State state;                    // CPU's state.
+  std::optional<u8> dataBank;     // Value of DBR on entry, if known.
+  std::optional<u16> directPage;  // Value of D on entry, if known.
 
   // Hash table utils.
   bool operator==(const EntryPoint& other) const;
   friend std::size_t hash_value(const EntryPoint& entryPoint);
 
   template <class Archive>
-  void serialize(Archive& ar, const unsigned int) {
+  void serialize(Archive& ar, const unsigned int version) {
     ar& label;
     ar& pc;
     ar& state;
+    if (version >= 1) {
+      ar& dataBank;
+      ar& directPage;
+    }
   }
 };
+BOOST_CLASS_VERSION(EntryPoint, 1)
 // Set of EntryPoints.
 typedef std::unordered_set<EntryPoint, boost::hash<EntryPoint>> EntryPointSet;
 
@@ -54,6 +71,74 @@ struct Reference {
 // Set of References.
 typedef std::unordered_set<Reference, boost::hash<Reference>> ReferenceSet;
 
+/**
+ * Problem found while loading entry points from a file.
+ */
+struct EntryPointIssue {
+  std::size_t line;     // Line of the file (starting from 1).
+  std::string message;  // What went wrong.
+};
+
+/**
+ * Saved analysis of a ROM.
+ */
+struct SavedAnalysis {
+  std::string name;  // Name of the save (empty for the default one).
+  // When the analysis was saved, and how many subroutines it had (unknown
+  // for files saved by older versions).
+  std::optional<std::time_t> timestamp;
+  std::optional<std::size_t> subroutines;
+};
+
+/**
+ * Contiguous region of the ROM not covered by the analysis.
+ */
+struct UnexploredRange {
+  u24 start;         // First address of the region.
+  u24 end;           // Last address of the region (included).
+  std::size_t size;  // Size of the region in bytes.
+};
+
+/**
+ * Summary of how much of the ROM the analysis has covered.
+ */
+struct AnalysisStatistics {
+  std::size_t codeBytes = 0;    // Bytes of the ROM decoded as code.
+  std::size_t romSize = 0;      // Size of the ROM in bytes.
+  std::size_t subroutines = 0;  // Number of subroutines.
+  // Number of subroutines with unknown state changes, in total and by reason.
+  std::size_t unknownSubroutines = 0;
+  std::map<UnknownReason, std::size_t> unknownReasons;
+  std::size_t unresolvedJumps = 0;  // Indirect jumps with unknown targets.
+
+  // Percentage of the ROM decoded as code.
+  double coverage() const;
+  // Return a textual report of the statistics.
+  std::string report() const;
+};
+
+/**
+ * Chain of calls between two subroutines.
+ */
+struct CallPath {
+  // Subroutines along the path (empty if no path was found).
+  std::vector<SubroutinePC> subroutines;
+  // Unresolved indirect jumps and calls that might hide other paths.
+  std::vector<InstructionPC> gaps;
+};
+
+/**
+ * Call or jump from a subroutine to another.
+ */
+struct CallSite {
+  SubroutinePC caller;  // Subroutine performing the call.
+  SubroutinePC callee;  // Subroutine being called.
+  InstructionPC pc;     // Instruction performing the call.
+  bool indirect;        // Whether the target comes from a jump table.
+
+  bool operator==(const CallSite& other) const;
+};
+
 /**
  * Class holding the state of the ROM's analysis.
  */
@@ -69,11 +154,37 @@ class Analysis {
   // Analyze the ROM.
   void run();
 
-  bool load();  // Try to load the analysis from a saved state.
-  void save();  // Save the results of the analysis.
+  // Try to load the analysis from a saved state, or a named one.
+  bool load(const std::string& name = "");
+  // Save the results of the analysis, optionally under a name.
+  void save(const std::string& name = "");
+  // Return the saved analyses of the ROM, ordered by name.
+  std::vector<SavedAnalysis> savedAnalyses() const;
+  // Return the user's annotations and settings, serialized.
+  std::string saveState() const;
+  // Restore the annotations and settings saved by saveState().
+  void restoreState(const std::string& state);
+  // Whether the loaded analysis was saved for a different version of the ROM
+  // (e.g. before patching it), so its addresses might not match anymore.
+  bool romChanged() const;
 
   // Add an entry point to the analysis.
-  void addEntryPoint(std::string label, SubroutinePC pc, State state = State());
+  void addEntryPoint(std::string label,
+                     SubroutinePC pc,
+                     State state = State(),
+                     std::optional<u8> dataBank = std::nullopt,
+                     std::optional<u16> directPage = std::nullopt);
+  // Return why an address can't be an entry point (in RAM, or not mapped to
+  // the ROM), if it can't.
+  std::optional<std::string> entryPointError(SubroutinePC pc) const;
+  // Add the entry points listed in a file, one `ADDRESS NAME [STATE]` per line
+  // (e.g. `$8000 reset m=1,x=1,db=7E`). Return the lines that couldn't be
+  // added.
+  std::vector<EntryPointIssue> loadEntryPoints(const std::string& path);
+  // Parse an entry state expression (e.g. `m=1,x=0,db=7E,dp=0000`), if valid.
+  // Only the state and the registers of the returned entry point are set.
+  static std::optional<EntryPoint> parseEntryState(
+      const std::string& expression);
 
   // Add an instruction to the analysis.
   Instruction* addInstruction(InstructionPC pc,
@@ -90,7 +201,8 @@ class Analysis {
   // Add a subroutine to the analysis.
   void addSubroutine(SubroutinePC pc,
                      std::optional<std::string> label = std::nullopt,
-                     bool isEntryPoint = false);
+                     bool isEntryPoint = false,
+                     const StackTrace& stackTrace = {});
 
   // Define a jump table: caller spans a jumptable going from x to y (included).
   void defineJumpTable(InstructionPC callerPC,
@@ -98,9 +210,72 @@ class Analysis {
                        JumpTableStatus status = JumpTableStatus::Partial);
   // Undefine a jump table.
   void undefineJumpTable(InstructionPC callerPC);
+  // Guess the tables of the unresolved jumps and calls indexed by X, from the
+  // words following their address. Return the jumps and calls whose table
+  // was guessed, ordered by address.
+  std::vector<InstructionPC> guessJumpTables();
 
   // Return any of the instructions at address PC.
-  const Instruction* anyInstruction(InstructionPC pc);
+  const Instruction* anyInstruction(InstructionPC pc) const;
+  // Return the indirect jumps or calls, ordered by address.
+  std::vector<InstructionPC> indirectJumps(InstructionType type) const;
+  // Return the returns jumping to an address pushed on the stack, with their
+  // target, ordered by address.
+  std::vector<std::pair<PCPair, InstructionPC>> returnJumps() const;
+  // Return the locations an instruction transfers control to.
+  std::vector<PCPair> followTargets(InstructionPC pc,
+                                    SubroutinePC subroutinePC) const;
+  // Return the instructions that transfer control to an address.
+  std::vector<PCPair> referencesTo(InstructionPC target) const;
+  // Return the instructions performing an operation (on a given argument,
+  // if any), ordered by address.
+  std::vector<PCPair> findInstructions(Op operation,
+                                       std::optional<u24> argument) const;
+  // Return the instructions whose bytes span an address.
+  std::vector<PCPair> instructionsAt(u24 address) const;
+  // Return the instruction to show for an address: the start of the
+  // subroutine at that address if any, otherwise the instruction spanning it
+  // in the subroutine with the lowest address.
+  std::optional<PCPair> locate(u24 address) const;
+  // Return the shortest chain of calls from a subroutine to another.
+  CallPath shortestPath(SubroutinePC from, SubroutinePC to) const;
+  // Return the instructions accessing WRAM or hardware registers, or jumping
+  // into WRAM, grouped by the address they target.
+  std::map<u24, std::vector<PCPair>> ramReferences() const;
+  // Return the instructions reading or writing WRAM, hardware registers or
+  // data in the ROM, grouped by the address they access.
+  std::map<u24, std::vector<PCPair>> dataReferences() const;
+  // Return the pairs of caller and callee subroutines, ordered by address.
+  std::vector<std::pair<SubroutinePC, SubroutinePC>> callEdges() const;
+  // Return the calls, and the jumps into other subroutines, ordered by caller
+  // and by address.
+  std::vector<CallSite> callSites() const;
+
+  // Return the pairs of instructions decoded from overlapping bytes, i.e. the
+  // second one starts inside the first one, or at the same address but with a
+  // different size.
+  std::vector<std::pair<InstructionPC, InstructionPC>> overlappingInstructions()
+      const;
+
+  // Return what the emulator knows about the CPU when it reaches an
+  // instruction, by replaying its subroutine.
+  std::optional<CPUSnapshot> cpuSnapshot(InstructionPC pc,
+                                         SubroutinePC subroutinePC) const;
+  // Emulate up to a number of instructions from an entry point, without
+  // changing the analysis. Return the instructions emulated, with the state
+  // before and after them, and the points where the emulation stopped.
+  std::vector<TraceStep> trace(const EntryPoint& entryPoint,
+                               std::size_t maxInstructions) const;
+
+  // Return the number of places where the analysis couldn't proceed.
+  std::size_t unresolvedCount() const;
+  // Whether the control flow has been fully resolved.
+  bool isComplete() const;
+  // Return the regions of the ROM that are neither code nor declared data,
+  // ordered by address. The header is left out.
+  std::vector<UnexploredRange> unexploredRanges() const;
+  // Return a summary of how much of the ROM the analysis has covered.
+  AnalysisStatistics statistics() const;
 
   // Get an assertion for the current instruction, if any.
   std::optional<Assertion> getAssertion(InstructionPC pc,
@@ -111,25 +286,87 @@ class Analysis {
                     SubroutinePC subroutinePC);
   // Remove a state change assertion from the analysis.
   void removeAssertion(InstructionPC pc, SubroutinePC subroutinePC);
+  // Change the confidence of an existing assertion.
+  bool setAssertionConfidence(InstructionPC pc,
+                              SubroutinePC subroutinePC,
+                              AssertionConfidence confidence);
+  // Return the coordinates of the assertions, optionally of a given
+  // confidence, ordered by address.
+  std::vector<PCPair> assertionPCs(
+      std::optional<AssertionConfidence> confidence = std::nullopt) const;
+  // Whether an assertion is on an instruction the analysis hasn't reached.
+  bool isDanglingAssertion(PCPair pc) const;
+
+  // Accept code found by the sweep, turning it into an entry point.
+  bool acceptSweepCandidate(SubroutinePC pc);
+  // Reject code found by the sweep, so that it isn't proposed again.
+  bool rejectSweepCandidate(SubroutinePC pc);
+
+  // Declare a region of the ROM as data. Return false if the region is empty,
+  // outside the ROM, or overlaps an existing data block.
+  bool addDataBlock(u24 start, u24 end, DataType type);
+  // Remove the data block starting at the given address.
+  bool removeDataBlock(u24 start);
+  // Return the data block containing an address, if any.
+  const DataBlock* dataBlockAt(u24 address) const;
 
   // Return the label associated with an address, if any.
   std::optional<Label> getLabel(
       InstructionPC pc,
       std::optional<SubroutinePC> subroutinePC = std::nullopt) const;
+  // Return the address named by a label, or written in hexadecimal with an
+  // optional `$` prefix, if any. Labels take precedence.
+  std::optional<u24> resolveAddress(const std::string& text) const;
+
+  // Bookmark an address, with an optional note.
+  void addBookmark(u24 address, std::string note = "");
+  // Remove a bookmark. Return false if the address wasn't bookmarked.
+  bool removeBookmark(u24 address);
 
-  // Rename a subroutine or local label.
-  void renameLabel(std::string newLabel,
+  // Watch a variable in WRAM, with an optional note. Return false if the
+  // address is not in WRAM.
+  bool addWatch(u24 address, std::string note = "");
+  // Stop watching a variable. Return false if the address wasn't watched.
+  bool removeWatch(u24 address);
+
+  // Flag an instruction for review, or clear its flag.
+  void setReviewFlag(InstructionPC pc, bool flagged = true);
+
+  // Emulate BRK or COP as a call to a handler, or stop at them again if no
+  // handler is given.
+  void setInterruptHandler(Op op, std::optional<SubroutinePC> handler);
+
+  // Name a variable in WRAM, or remove its name if the label is empty.
+  // Return false if the label is invalid or names another variable, or the
+  // address is not in WRAM.
+  bool labelRAM(const std::string& label, u24 address);
+  // Whether a label is a valid identifier for asar.
+  static bool isValidLabel(const std::string& label);
+
+  // Return the instruction and subroutine a subroutine label or a combined
+  // local label (`sub.loc`) refers to, if any.
+  std::optional<PCPair> labelPC(const std::string& label) const;
+  // Rename a subroutine or local label. Return false if the new label is
+  // invalid, or no subroutine or instruction is at the given address.
+  bool renameLabel(std::string newLabel,
                    InstructionPC pc,
                    std::optional<SubroutinePC> subroutinePC = std::nullopt);
 
+  // Send an event to the event sink, if any.
+  void notify(const AnalysisEvent& event) const;
+
   // The ROM being analyzed.
-  const ROM rom;
+  ROM rom;
   // Map from PC to the set of instructions at that address.
   std::unordered_map<InstructionPC, InstructionSet> instructions;
   // All the analyzed subroutines.
   std::map<SubroutinePC, Subroutine> subroutines;
   // Instructions referenced by other instructions.
   std::unordered_map<InstructionPC, ReferenceSet> references;
+  // Instructions referencing each address (reverse of references).
+  std::unordered_map<InstructionPC, std::set<PCPair>> referrers;
+  // Number of passes the last run of the analysis took.
+  std::size_t passes = 0;
 
   // ROM's entry points.
   EntryPointSet entryPoints;
@@ -140,24 +377,106 @@ class Analysis {
   // Labels set by the user.
   std::unordered_map<PCPair, std::string, boost::hash<PCPair>> customLabels;
 
+  // Names of the variables in WRAM, by address.
+  std::map<u24, std::string> ramLabels;
+
+  // Bookmarked addresses, with their notes.
+  std::map<u24, std::string> bookmarks;
+
+  // Watched variables in WRAM, by address, with their notes.
+  std::map<u24, std::string> watches;
+
+  // Instructions flagged for review.
+  std::set<InstructionPC> reviewFlags;
+
+  // Handlers called by BRK and COP, when used as system calls.
+  std::map<Op, SubroutinePC> interruptHandlers;
+
   // State change assertions.
   std::unordered_map<PCPair, Assertion, boost::hash<PCPair>> assertions;
 
   // Map from PC to jump tables.
   std::unordered_map<InstructionPC, JumpTable> jumpTables;
 
+  // Analysis settings.
+  Settings settings;
+
+  // Address from which the next sweep resumes.
+  u32 sweepCursor = 0;
+  // Code found by the sweep, waiting to be accepted or rejected.
+  std::map<SubroutinePC, SweepCandidate> sweepQuarantine;
+  // Code found by the sweep and rejected, never proposed again.
+  std::map<SubroutinePC, SweepCandidate> sweepRejected;
+
+  // Regions of the ROM declared as data, by starting address.
+  std::map<u24, DataBlock> dataBlocks;
+
+  // Receiver of the discoveries made while the analysis runs (optional).
+  EventSink eventSink;
+
  private:
+  // Checksum of the ROM the saved analysis was made on, if known.
+  std::optional<u32> savedChecksum;
+
   void clear();                // Clear the results of the analysis.
   void reset();                // Reset the analysis (start from scratch).
   void generateLocalLabels();  // Generate local label names.
 
+  // Run the CPU from each entry point.
+  void runEntryPoints();
+  // Return the states an entry point is run with.
+  std::vector<State> entryStates(const EntryPoint& entryPoint) const;
+  // Return a CPU ready to run an entry point with the given state.
+  CPU entryPointCPU(const EntryPoint& entryPoint, State state) const;
+  // Whether a subroutine starts at the given address, as far as we know.
+  bool isSubroutineEntry(InstructionPC pc) const;
+  // Remember the subroutines found so far. Return true if the code of one
+  // of the new ones was analyzed as part of another subroutine.
+  bool learnSubroutineEntries();
+
+  // Subroutines found by the previous passes of the analysis.
+  std::set<SubroutinePC> knownSubroutines;
+
+  // Extend a new stack trace to the subroutines called by a subroutine.
+  void propagateStackTrace(SubroutinePC pc, const StackTrace& stackTrace);
+
+  friend class CPU;
+
   friend class boost::serialization::access;
   template <class Archive>
-  void serialize(Archive& ar, const unsigned int) {
+  void serialize(Archive& ar, const unsigned int version) {
     ar& entryPoints;
     ar& comments;
     ar& customLabels;
     ar& assertions;
     ar& jumpTables;
+    if (version >= 1) {
+      ar& settings;
+    }
+    if (version >= 2) {
+      ar& sweepCursor;
+      ar& sweepQuarantine;
+      ar& sweepRejected;
+    }
+    if (version >= 3) {
+      ar& dataBlocks;
+    }
+    if (version >= 4) {
+      ar& savedChecksum;
+    }
+    if (version >= 5) {
+      ar& ramLabels;
+    }
+    if (version >= 6) {
+      ar& bookmarks;
+    }
+    if (version >= 7) {
+      ar& watches;
+      ar& reviewFlags;
+    }
+    if (version >= 8) {
+      ar& interruptHandlers;
+    }
   }
 };
+BOOST_CLASS_VERSION(Analysis, 8)
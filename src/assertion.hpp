@@ -1,5 +1,7 @@
 #pragma once
 
+#include <boost/serialization/version.hpp>
+
 #include "state.hpp"
 
 /**
@@ -10,20 +12,38 @@ enum class AssertionType {
   Subroutine,
 };
 
+/**
+ * How much an assertion can be trusted.
+ */
+enum class AssertionConfidence {
+  Verified,  // Checked (e.g. in an emulator).
+  Probable,  // Believed to be correct.
+  Guess,     // Made up to move the analysis forward.
+};
+
 /**
  * Structure representing a state assertion.
  */
 struct Assertion {
   Assertion(AssertionType type = AssertionType::Instruction,
-            StateChange stateChange = StateChange())
-      : type{type}, stateChange{stateChange} {}
+            StateChange stateChange = StateChange(),
+            AssertionConfidence confidence = AssertionConfidence::Probable)
+      : type{type}, stateChange{stateChange}, confidence{confidence} {}
 
   AssertionType type;
   StateChange stateChange;
+  AssertionConfidence confidence;
 
   template <class Archive>
-  void serialize(Archive& ar, const unsigned int) {
+  void serialize(Archive& ar, const unsigned int version) {
     ar& type;
     ar& stateChange;
+    // Assertions saved before confidence tiers existed are probable.
+    if (version >= 1) {
+      ar& confidence;
+    } else {
+      confidence = AssertionConfidence::Probable;
+    }
   }
 };
+BOOST_CLASS_VERSION(Assertion, 1)
@@ -0,0 +1,471 @@
+#include <algorithm>
+#include <ctime>
+#include <fstream>
+#include <sstream>
+
+#include "batch.hpp"
+
+#include "analysis.hpp"
+#include "disassembly.hpp"
+#include "hardwareregisters.hpp"
+#include "utils.hpp"
+
+using namespace std;
+
+// Constructor.
+Batch::Batch(Analysis* analysis, ostream& output, ostream& errors)
+    : analysis{analysis}, scriptOutput{output}, errors{errors} {
+  // The state before any command can be restored too.
+  editHistory.push(analysis->saveState());
+}
+
+// Run the commands of a script, stopping at the first one that fails.
+// Return whether all the commands succeeded.
+bool Batch::run(istream& script) {
+  string line;
+  for (size_t lineNumber = 1; getline(script, line); lineNumber++) {
+    auto result = runCommand(line);
+    scriptOutput << result.output;
+    if (result.error.has_value()) {
+      errors << format("line %zu: %s", lineNumber, result.error->c_str())
+             << endl;
+      return false;
+    }
+  }
+  return true;
+}
+
+// Run a single command line, and return what it printed or why it failed.
+CommandResult Batch::runCommand(const string& line) {
+  istringstream fields(line.substr(0, line.find('#')));
+  vector<string> args;
+  for (string arg; fields >> arg;) {
+    args.push_back(arg);
+  }
+  if (args.empty()) {
+    return {};  // Blank line or comment.
+  }
+
+  output.str("");
+  auto error = execute(args);
+  if (!error.has_value() && isEdit(args[0])) {
+    editHistory.push(analysis->saveState());
+  }
+  return {output.str(), error};
+}
+
+// Execute a command. Return an error message if it fails.
+optional<string> Batch::execute(const vector<string>& args) {
+  auto& command = args[0];
+
+  if (command == "rom" && args.size() == 1) {
+    describeROM();
+  } else if (command == "rom" && args.size() == 2 && args[1] == "checksum") {
+    auto& rom = analysis->rom;
+    output << format("Checksum: $%04X", rom.headerChecksum());
+    if (rom.hasValidChecksum()) {
+      output << " (valid)" << endl;
+    } else {
+      output << format(" (invalid, expected $%04X)", rom.computeChecksum())
+             << endl;
+    }
+  } else if (command == "rom" && args.size() == 2 &&
+             args[1] == "fixchecksum") {
+    if (!analysis->rom.fixChecksum()) {
+      return "the ROM is too small to contain a header";
+    }
+    output << format("Checksum: $%04X (fixed)",
+                     analysis->rom.headerChecksum())
+           << endl;
+  } else if (command == "profile" && args.size() == 2) {
+    if (!analysis->settings.loadProfile(args[1])) {
+      return "could not load profile " + args[1];
+    }
+  } else if (command == "entrypoint" &&
+             (args.size() == 3 || args.size() == 4)) {
+    auto pc = parseAddress(args[1]);
+    if (!pc.has_value()) {
+      return "invalid address";
+    }
+    if (auto error = analysis->entryPointError(*pc)) {
+      return error;
+    }
+    auto entryState = args.size() == 4 ? Analysis::parseEntryState(args[3])
+                                       : EntryPoint();
+    if (!entryState.has_value()) {
+      return "invalid state";
+    }
+    analysis->addEntryPoint(args[2], *pc, entryState->state,
+                            entryState->dataBank, entryState->directPage);
+  } else if (command == "entrypoints" && args.size() == 2) {
+    auto issues = analysis->loadEntryPoints(args[1]);
+    if (!issues.empty()) {
+      return format("%s:%zu: %s", args[1].c_str(), issues[0].line,
+                    issues[0].message.c_str());
+    }
+  } else if (command == "label-ram" && args.size() == 3) {
+    auto address = parseAddress(args[2]);
+    if (!address.has_value()) {
+      return "invalid address";
+    }
+    if (!analysis->labelRAM(args[1], *address)) {
+      return "invalid label or address: " + args[1];
+    }
+  } else if (command == "rename" && args.size() == 3) {
+    auto pc = analysis->labelPC(args[1]);
+    if (!pc.has_value()) {
+      return "unknown label: " + args[1];
+    }
+    if (!analysis->renameLabel(args[2], pc->first, pc->second)) {
+      return "invalid label: " + args[2];
+    }
+  } else if (command == "bookmark" && args.size() >= 2) {
+    auto address = parseAddress(args[1]);
+    if (!address.has_value()) {
+      return "invalid address";
+    }
+    // The note is the rest of the line.
+    string note;
+    for (size_t i = 2; i < args.size(); i++) {
+      note += (i > 2 ? " " : "") + args[i];
+    }
+    analysis->addBookmark(*address, note);
+  } else if (command == "unbookmark" && args.size() == 2) {
+    auto address = parseAddress(args[1]);
+    if (!address.has_value()) {
+      return "invalid address";
+    }
+    if (!analysis->removeBookmark(*address)) {
+      return format("no bookmark at $%06X", *address);
+    }
+  } else if (command == "bookmarks" && args.size() == 1) {
+    listBookmarks();
+  } else if (command == "datablock" &&
+             (args.size() == 3 || args.size() == 4)) {
+    auto start = parseAddress(args[1]);
+    auto end = parseAddress(args[2]);
+    if (!start.has_value() || !end.has_value()) {
+      return "invalid address";
+    }
+    auto type = args.size() == 4 ? parseDataType(args[3]) : DataType::Bytes;
+    if (!type.has_value()) {
+      return "invalid data type: " + args[3];
+    }
+    if (!analysis->addDataBlock(*start, *end, *type)) {
+      return format("invalid data block $%06X..$%06X", *start, *end);
+    }
+  } else if (command == "undatablock" && args.size() == 2) {
+    auto start = parseAddress(args[1]);
+    if (!start.has_value()) {
+      return "invalid address";
+    }
+    if (!analysis->removeDataBlock(*start)) {
+      return format("no data block at $%06X", *start);
+    }
+  } else if (command == "datablocks" && args.size() == 1) {
+    for (auto& [start, block] : analysis->dataBlocks) {
+      output << format("$%06X..$%06X  %s\n", block.start, block.end,
+                       dataTypeName(block.type).c_str());
+    }
+  } else if (command == "guess-jumptables" && args.size() == 1) {
+    for (auto pc : analysis->guessJumpTables()) {
+      output << format("$%06X  %zu entries\n", pc,
+                       analysis->jumpTables.at(pc).targets.size());
+    }
+  } else if (command == "jumptable" && args.size() == 3) {
+    return defineJumpTable(args);
+  } else if (command == "interrupt" && args.size() == 3) {
+    if (args[1] != "brk" && args[1] != "cop") {
+      return "invalid interrupt: " + args[1];
+    }
+    auto op = args[1] == "brk" ? Op::BRK : Op::COP;
+    if (args[2] == "none") {
+      analysis->setInterruptHandler(op, nullopt);
+      return nullopt;
+    }
+    auto pc = parseAddress(args[2]);
+    if (!pc.has_value()) {
+      return "invalid address";
+    }
+    if (auto error = analysis->entryPointError(*pc)) {
+      return error;
+    }
+    analysis->setInterruptHandler(op, *pc);
+  } else if (command == "undo" && args.size() == 1) {
+    auto state = editHistory.undo();
+    if (!state.has_value()) {
+      return "nothing to undo";
+    }
+    restoreState(*state);
+  } else if (command == "redo" && args.size() == 1) {
+    auto state = editHistory.redo();
+    if (!state.has_value()) {
+      return "nothing to redo";
+    }
+    restoreState(*state);
+  } else if (command == "analyze" && args.size() == 1) {
+    analysis->run();
+    output << format("%zu subroutines analyzed",
+                     analysis->subroutines.size())
+           << endl;
+  } else if (command == "cycles" && args.size() == 2) {
+    auto pc = parseAddress(args[1]);
+    if (!pc.has_value()) {
+      return "invalid address";
+    }
+    auto search = analysis->subroutines.find(*pc);
+    if (search == analysis->subroutines.end()) {
+      return format("no subroutine at $%06X", *pc);
+    }
+    output << format("%s: ~%zu cycles", search->second.label.c_str(),
+                     search->second.cycles())
+           << endl;
+  } else if (command == "trace" && (args.size() == 3 || args.size() == 4)) {
+    return trace(args);
+  } else if (command == "stats" && args.size() == 1) {
+    output << analysis->statistics().report();
+  } else if (command == "data" && args.size() == 1) {
+    listDataReferences();
+  } else if (command == "save" && args.size() <= 2) {
+    auto name = args.size() == 2 ? args[1] : "";
+    if (!isValidSaveName(name)) {
+      return "invalid save name: " + name;
+    }
+    analysis->save(name);
+  } else if (command == "load" && args.size() <= 2) {
+    auto name = args.size() == 2 ? args[1] : "";
+    if (!isValidSaveName(name) || !analysis->load(name)) {
+      return "could not load " + analysis->rom.savePath(name);
+    }
+  } else if (command == "saves" && args.size() == 1) {
+    listSaves();
+  } else if (command == "export" && args.size() >= 2 && args.size() <= 4) {
+    return exportResults(args);
+  } else {
+    return "invalid command: " + command;
+  }
+  return nullopt;
+}
+
+// Restore a state from the edit history and rerun the analysis.
+void Batch::restoreState(const string& state) {
+  analysis->restoreState(state);
+  analysis->run();
+}
+
+// Define the complete jump table of an indexed jump or call.
+optional<string> Batch::defineJumpTable(const vector<string>& args) {
+  auto pc = parseAddress(args[1]);
+  if (!pc.has_value()) {
+    return "invalid address";
+  }
+  auto caller = analysis->anyInstruction(*pc);
+  if (caller == nullptr || !analysis->jumpTables.count(*pc) ||
+      caller->addressMode() != AddressMode::AbsoluteIndexedIndirect) {
+    return format("no jump or call indexed by X at $%06X", *pc);
+  }
+
+  auto& digits = args[2];
+  if (digits.empty() || digits.size() > 5 ||
+      !all_of(digits.begin(), digits.end(), ::isdigit) || stoul(digits) == 0 ||
+      stoul(digits) > 0x8000) {
+    return "invalid count";
+  }
+
+  // Each entry of the table is a word, in the ROM and in the bank of the jump.
+  size_t count = stoul(digits);
+  auto bank = *pc & 0xFF0000;
+  u32 table = *caller->argument();
+  if (table + count * 2 > 0x10000) {
+    return "jump table crosses the end of the bank";
+  }
+  for (size_t i = 0; i < count * 2; i++) {
+    if (!analysis->rom.isROM(bank | (table + i))) {
+      return "jump table outside the ROM";
+    }
+  }
+  u16 last = (count - 1) * 2;
+  analysis->defineJumpTable(*pc, {0, last}, JumpTableStatus::Complete);
+  return nullopt;
+}
+
+// Print the ROM's title, type and size, and whether it has a copier header.
+void Batch::describeROM() {
+  auto& rom = analysis->rom;
+  output << "Title: " << rom.title() << endl;
+  output << "Type: " << romTypeName(rom.romType) << endl;
+  output << format("Size: %zu KB", rom.realSize() / 1024) << endl;
+  output << "Copier header: " << (rom.hasCopierHeader() ? "yes" : "no")
+         << endl;
+}
+
+// Print the bookmarks, with the instruction at their address if any.
+void Batch::listBookmarks() {
+  for (auto& [address, note] : analysis->bookmarks) {
+    output << format("$%06X", address);
+    if (!note.empty()) {
+      output << "  " << note;
+    }
+    if (auto instruction = analysis->anyInstruction(address)) {
+      auto argument = instruction->argumentString();
+      output << "  ; " << instruction->name()
+             << (argument.empty() ? "" : " " + argument);
+    }
+    output << endl;
+  }
+}
+
+// Emulate the code from an address and a state, printing each instruction
+// and where the emulation stopped.
+optional<string> Batch::trace(const vector<string>& args) {
+  auto pc = parseAddress(args[1]);
+  if (!pc.has_value()) {
+    return "invalid address";
+  }
+  if (auto error = analysis->entryPointError(*pc)) {
+    return error;
+  }
+  auto entryPoint = Analysis::parseEntryState(args[2]);
+  if (!entryPoint.has_value()) {
+    return "invalid state";
+  }
+  auto count = DEFAULT_TRACE_LENGTH;
+  if (args.size() == 4) {
+    auto& digits = args[3];
+    if (digits.empty() || !all_of(digits.begin(), digits.end(), ::isdigit)) {
+      return "invalid count";
+    }
+    count = stoul(digits);
+  }
+  entryPoint->label = "trace";
+  entryPoint->pc = *pc;
+
+  auto describe = [](State state) {
+    return format("m=%d,x=%d", state.m, state.x);
+  };
+  size_t emulated = 0;
+  for (auto& step : analysis->trace(*entryPoint, count)) {
+    if (step.instruction.empty()) {
+      output << format("$%06X  stopped: %s", step.pc, step.note.c_str());
+    } else {
+      emulated++;
+      output << format("$%06X  %-16s %s -> %s", step.pc,
+                       step.instruction.c_str(),
+                       describe(step.before).c_str(),
+                       describe(step.after).c_str());
+      if (!step.note.empty()) {
+        output << "  " << step.note;
+      }
+    }
+    output << endl;
+  }
+  if (emulated == count) {
+    output << format("instruction limit (%zu) reached", count) << endl;
+  }
+  return nullopt;
+}
+
+// Print the data addresses accessed by the code, with how many
+// instructions access them and their names, if any.
+void Batch::listDataReferences() {
+  for (auto& [address, sources] : analysis->dataReferences()) {
+    output << format("$%06X  %zu", address, sources.size());
+    auto ramLabel = analysis->ramLabels.find(address);
+    auto hwRegister = HARDWARE_REGISTERS.find(address);
+    if (ramLabel != analysis->ramLabels.end()) {
+      output << "  " << ramLabel->second;
+    } else if (hwRegister != HARDWARE_REGISTERS.end()) {
+      output << "  !" << hwRegister->second;
+    }
+    output << endl;
+  }
+}
+
+// Print the saved analyses of the ROM, with when they were saved and how
+// many subroutines they had.
+void Batch::listSaves() {
+  for (auto& save : analysis->savedAnalyses()) {
+    output << (save.name.empty() ? "(default)" : save.name);
+    if (save.timestamp.has_value()) {
+      char date[sizeof("YYYY-MM-DD HH:MM")];
+      strftime(date, sizeof(date), "%Y-%m-%d %H:%M",
+               localtime(&*save.timestamp));
+      output << format("  %s  %zu subroutines", date, *save.subroutines);
+    }
+    output << endl;
+  }
+}
+
+// Whether a command changes the annotations of the analysis.
+bool Batch::isEdit(const string& command) {
+  return command == "profile" || command == "entrypoint" ||
+         command == "entrypoints" || command == "label-ram" ||
+         command == "rename" || command == "bookmark" ||
+         command == "unbookmark" || command == "datablock" ||
+         command == "undatablock" || command == "jumptable" ||
+         command == "guess-jumptables" || command == "interrupt" ||
+         command == "load";
+}
+
+// Whether a name can be given to a saved analysis: letters, digits, `_` and
+// `-` only (or empty, for the default save).
+bool Batch::isValidSaveName(const string& name) {
+  return all_of(name.begin(), name.end(),
+                [](char c) { return isalnum(c) || c == '_' || c == '-'; });
+}
+
+// Parse an address in hexadecimal, with an optional `$` prefix.
+optional<u24> Batch::parseAddress(const string& text) {
+  auto address = text[0] == '$' ? text.substr(1) : text;
+  size_t parsed = 0;
+  u24 value = 0;
+  try {
+    value = stoul(address, &parsed, 16);
+  } catch (...) {
+  }
+  if (parsed == 0 || parsed != address.size() || value > 0xFFFFFF) {
+    return nullopt;
+  }
+  return value;
+}
+
+// Export the results of the analysis in a given format.
+optional<string> Batch::exportResults(const vector<string>& args) {
+  // Only the call graph comes in more than one format.
+  if (args.size() == 4 && args[1] != "callgraph") {
+    return "unexpected argument: " + args[3];
+  }
+
+  Disassembly disassembly(analysis);
+  string text;
+  if (args[1] == "disassembly") {
+    text = disassembly.all();
+  } else if (args[1] == "assembly") {
+    text = disassembly.rom();
+  } else if (args[1] == "documentation") {
+    text = disassembly.markdown();
+  } else if (args[1] == "symbols") {
+    text = disassembly.symbols();
+  } else if (args[1] == "callgraph") {
+    auto graphFormat = args.size() == 4 ? args[3] : "dot";
+    if (graphFormat == "dot") {
+      text = disassembly.callGraph();
+    } else if (graphFormat == "json") {
+      text = disassembly.callGraphJSON();
+    } else {
+      return "invalid call graph format: " + graphFormat;
+    }
+  } else {
+    return "invalid export format: " + args[1];
+  }
+
+  if (args.size() == 2) {
+    output << text;
+    return nullopt;
+  }
+  ofstream file(args[2]);
+  if (!(file << text)) {
+    return "could not write " + args[2];
+  }
+  return nullopt;
+}
@@ -0,0 +1,126 @@
+#pragma once
+
+#include <iostream>
+#include <optional>
+#include <sstream>
+#include <string>
+#include <vector>
+
+#include "history.hpp"
+#include "types.hpp"
+
+class Analysis;
+
+// Output of a batch command, or the error that made it fail.
+struct CommandResult {
+  std::string output;                // What the command printed.
+  std::optional<std::string> error;  // Error message, if the command failed.
+};
+
+/**
+ * Run analysis commands from a script, without user interaction.
+ * Each line holds a command and its arguments, `#` starts a comment:
+ *
+ *   rom                                 Describe the ROM.
+ *   rom checksum                        Verify the checksum in the header.
+ *   rom fixchecksum                     Write the correct checksum in the
+ *                                       header (in memory only).
+ *   profile NAME                        Apply a settings profile.
+ *   entrypoint ADDRESS NAME [STATE]     Add an entry point.
+ *   entrypoints PATH                    Add the entry points listed in a file.
+ *   label-ram NAME ADDRESS              Name a variable in WRAM.
+ *   rename LABEL NEWLABEL               Rename a subroutine or local label
+ *                                       (`sub.loc`).
+ *   bookmark ADDRESS [NOTE]             Bookmark an address.
+ *   unbookmark ADDRESS                  Remove a bookmark.
+ *   bookmarks                           List the bookmarks.
+ *   datablock START END [TYPE]          Declare the region from START to END
+ *                                       (excluded) as data of a type (bytes,
+ *                                       words, pointers or text; bytes by
+ *                                       default).
+ *   undatablock START                   Remove a data block.
+ *   datablocks                          List the data blocks.
+ *   undo                                Undo the last edit.
+ *   redo                                Redo the last undone edit.
+ *   jumptable ADDRESS COUNT             Define the table of COUNT entries
+ *                                       indexed by X of a jump or call.
+ *   guess-jumptables                    Guess the tables of the unresolved
+ *                                       jumps and calls indexed by X, and
+ *                                       list them.
+ *   interrupt brk|cop ADDRESS|none      Emulate BRK or COP as a call to a
+ *                                       handler (a system call).
+ *   analyze                             Run the analysis.
+ *   stats                               Print how much of the ROM is covered.
+ *   data                                List the data addresses accessed, and
+ *                                       how many instructions access them.
+ *   cycles ADDRESS                      Estimate the cycles of a subroutine.
+ *   trace ADDRESS STATE [COUNT]         Emulate up to COUNT instructions (100
+ *                                       by default) from an address, without
+ *                                       changing the analysis.
+ *   save [NAME]                         Save the analysis (under a name, next
+ *                                       to the default save).
+ *   load [NAME]                         Load a saved analysis.
+ *   saves                               List the saved analyses.
+ *   export disassembly|assembly|documentation|callgraph|symbols [PATH]
+ *                                       Export the results (to the output if
+ *                                       no path is given).
+ *   export callgraph PATH dot|json      Export the call graph in a given
+ *                                       format (DOT by default).
+ */
+class Batch {
+ public:
+  // Constructor.
+  Batch(Analysis* analysis, std::ostream& output, std::ostream& errors);
+
+  // Run the commands of a script, stopping at the first one that fails.
+  // Return whether all the commands succeeded.
+  bool run(std::istream& script);
+  // Run a single command line, and return what it printed or why it failed.
+  CommandResult runCommand(const std::string& line);
+
+ private:
+  // Execute a command. Return an error message if it fails.
+  std::optional<std::string> execute(const std::vector<std::string>& args);
+  // Export the results of the analysis in a given format.
+  std::optional<std::string> exportResults(
+      const std::vector<std::string>& args);
+  // Restore a state from the edit history and rerun the analysis.
+  void restoreState(const std::string& state);
+  // Define the complete jump table of an indexed jump or call.
+  std::optional<std::string> defineJumpTable(
+      const std::vector<std::string>& args);
+  // Print the ROM's title, type and size, and whether it has a copier header.
+  void describeROM();
+  // Print the bookmarks, with the instruction at their address if any.
+  void listBookmarks();
+  // Emulate the code from an address and a state, printing each instruction
+  // and where the emulation stopped.
+  std::optional<std::string> trace(const std::vector<std::string>& args);
+  // Print the data addresses accessed by the code, with how many
+  // instructions access them and their names, if any.
+  void listDataReferences();
+  // Print the saved analyses of the ROM, with when they were saved and how
+  // many subroutines they had.
+  void listSaves();
+  // Whether a command changes the annotations of the analysis.
+  static bool isEdit(const std::string& command);
+  // Whether a name can be given to a saved analysis: letters, digits, `_` and
+  // `-` only (or empty, for the default save).
+  static bool isValidSaveName(const std::string& name);
+  // Parse an address in hexadecimal, with an optional `$` prefix.
+  static std::optional<u24> parseAddress(const std::string& text);
+
+  // Pointer to the analysis.
+  Analysis* analysis;
+  // Where the output of the commands of a script is written.
+  std::ostream& scriptOutput;
+  // Where errors are reported.
+  std::ostream& errors;
+  // Where exported results and messages of the current command are written.
+  std::ostringstream output;
+  // Annotations after each edit, to undo and redo them.
+  EditHistory editHistory;
+
+  // Number of instructions emulated by `trace` if not specified.
+  static const std::size_t DEFAULT_TRACE_LENGTH = 100;
+};
@@ -0,0 +1,31 @@
+#include "commandline.hpp"
+
+#include "analysis.hpp"
+#include "disassembly.hpp"
+
+using namespace std;
+
+// Apply settings to an analysis, and its saved state if any, warning when it
+// was saved for a different version of the ROM.
+void loadAnalysis(Analysis& analysis,
+                  const Settings& settings,
+                  ostream& errors) {
+  // Per-ROM settings, if saved, take precedence over the profile.
+  analysis.settings = settings;
+  if (analysis.load() && analysis.romChanged()) {
+    errors << "Warning: the saved analysis was made on a different version "
+              "of this ROM."
+           << endl;
+  }
+}
+
+// Analyze a ROM and print its disassembly, without user interaction.
+void dumpDisassembly(const string& romPath,
+                     const Settings& settings,
+                     ostream& output,
+                     ostream& errors) {
+  Analysis analysis(romPath);
+  loadAnalysis(analysis, settings, errors);
+  analysis.run();
+  output << Disassembly(&analysis).all();
+}
@@ -0,0 +1,19 @@
+#pragma once
+
+#include <iostream>
+#include <string>
+
+class Analysis;
+struct Settings;
+
+// Apply settings to an analysis, and its saved state if any, warning when it
+// was saved for a different version of the ROM.
+void loadAnalysis(Analysis& analysis,
+                  const Settings& settings,
+                  std::ostream& errors);
+
+// Analyze a ROM and print its disassembly, without user interaction.
+void dumpDisassembly(const std::string& romPath,
+                     const Settings& settings,
+                     std::ostream& output,
+                     std::ostream& errors);
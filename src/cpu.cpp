@@ -3,6 +3,7 @@
 #include "analysis.hpp"
 #include "instruction.hpp"
 #include "rom.hpp"
+#include "utils.hpp"
 
 using namespace std;
 
@@ -16,6 +17,7 @@ CPU::CPU(Analysis* analysis,
       state{state},
       A(this, true),
       X(this, false),
+      Y(this, false),
       analysis{analysis} {}
 
 // Copy constructor.
@@ -29,9 +31,20 @@ CPU::CPU(const CPU& cpu)
       stateInference{cpu.stateInference},
       A{cpu.A},
       X{cpu.X},
-      analysis{cpu.analysis} {
+      Y{cpu.Y},
+      interruptsDisabled{cpu.interruptsDisabled},
+      criticalSectionStart{cpu.criticalSectionStart},
+      stackTrace{cpu.stackTrace},
+      directPage{cpu.directPage},
+      dataBank{cpu.dataBank},
+      carry{cpu.carry},
+      dryRun{cpu.dryRun},
+      analysis{cpu.analysis},
+      savedInterruptStates{cpu.savedInterruptStates},
+      lastInstruction{cpu.lastInstruction} {
   A.cpu = this;
   X.cpu = this;
+  Y.cpu = this;
 }
 
 // Start emulating.
@@ -43,32 +56,150 @@ void CPU::run() {
 
 // Fetch and execute the next instruction.
 void CPU::step() {
+  // Stop if the dry run has emulated enough instructions,
+  // or has captured the snapshot it was looking for.
+  if (dryRun != nullptr &&
+      (dryRun->budget == 0u || dryRun->snapshot.has_value())) {
+    stop = true;
+    return;
+  }
+
   // Stop if we have jumped into RAM.
   if (ROM::isRAM(pc)) {
     return unknownStateChange(pc, UnknownReason::MutableCode);
   }
 
+  // Execution continues into the following subroutine.
+  if (lastInstruction != nullptr && pc != subroutinePC &&
+      pc == lastInstruction->pc + lastInstruction->size() &&
+      analysis->isSubroutineEntry(pc)) {
+    return fallThrough(pc);
+  }
+
+  // Stop if we have run into a region declared as data.
+  if (analysis->dataBlockAt(pc) != nullptr) {
+    return unknownStateChange(pc, UnknownReason::SuspectInstruction);
+  }
+
+  // In strict mode, stop if we have run into data.
   auto opcode = analysis->rom.readByte(pc);
+  auto& settings = analysis->settings;
+  if (settings.strictMode && settings.dataOpcodes.count(opcode)) {
+    return unknownStateChange(pc, UnknownReason::SuspectInstruction);
+  }
+
   auto argument = analysis->rom.readAddress(pc + 1);
-  auto instruction =
-      analysis->addInstruction(pc, subroutinePC, opcode, argument, state);
+  auto instruction = addInstruction(opcode, argument);
 
   // Stop the analysis if we have already visited this instruction.
   if (instruction == nullptr) {
+    traceStop(pc, "already visited");
     stop = true;
-  } else {
-    execute(instruction);
+    return;
+  }
+  if (dryRun == nullptr) {
+    return execute(instruction);
+  }
+  if (dryRun->snapshotPC == instruction->pcPair()) {
+    dryRun->snapshot = snapshot();
+    stop = true;
+    return;
+  }
+
+  // Record the instruction before emulating it: the CPU instances it
+  // spawns record their own steps in the meantime.
+  auto& steps = dryRun->steps;
+  auto index = steps.size();
+  auto argumentString = instruction->argumentString();
+  steps.push_back({instruction->pc,
+                   instruction->name() + (argumentString.empty() ? "" : " ") +
+                       argumentString,
+                   state, state, ""});
+  if (dryRun->budget.has_value()) {
+    --*dryRun->budget;
+  }
+  execute(instruction);
+
+  steps[index].after = state;
+  if (instruction->type() == InstructionType::Branch) {
+    auto target = *instruction->absoluteArgument();
+    steps[index].note = analysis->settings.takenBranchesOnly
+                            ? format("taken to $%06X", target)
+                            : format("not taken, then taken to $%06X", target);
+  }
+}
+
+// Add the instruction at PC to the analysis (or to the dry run). Return
+// nullptr if the instruction has already been visited.
+const Instruction* CPU::addInstruction(u8 opcode, u24 argument) {
+  // Code already visited is run again when reached inside a critical section
+  // it wasn't reached in before, to find out where the section ends.
+  auto subroutine = this->subroutine();
+  auto& visits = subroutine->criticalSectionVisits;
+  auto newVisit = criticalSectionStart.has_value() &&
+                  visits.insert({pc, *criticalSectionStart}).second;
+
+  // The code of a subroutine jumping through a table indexed by X is run
+  // again when reached with a new value of X, to take each entry it selects.
+  auto& indexedVisits = subroutine->indexedJumpVisits;
+  auto x = X.get();
+  if (subroutine->indexedJumps && x.has_value() &&
+      indexedVisits.size() < Subroutine::MAX_INDEXED_JUMP_VISITS &&
+      indexedVisits.insert({pc, *x}).second) {
+    newVisit = true;
+  }
+
+  // Instructions of a dry run are kept apart from the analysis.
+  if (dryRun != nullptr) {
+    auto [instruction, inserted] = dryRun->instructions.emplace(
+        pc, subroutinePC, opcode, argument, state, analysis);
+    return (inserted || newVisit) ? &(*instruction) : nullptr;
+  }
+
+  auto instruction =
+      analysis->addInstruction(pc, subroutinePC, opcode, argument, state);
+  if (instruction == nullptr && newVisit) {
+    auto& instructionSet = analysis->instructions.at(pc);
+    return &(*instructionSet.find(
+        Instruction(pc, subroutinePC, opcode, argument, state, analysis)));
   }
+  return instruction;
+}
+
+// Return what we know about the CPU at this point.
+CPUSnapshot CPU::snapshot() {
+  return {state,    A.get(),    X.get(),           Y.get(),
+          dataBank, directPage, interruptsDisabled};
 }
 
 // Emulate an instruction.
 void CPU::execute(const Instruction* instruction) {
+  lastInstruction = instruction;
   pc += instruction->size();
 
   // See if we can learn something about the *required*
   // state of the CPU based on the current instruction.
   deriveStateInference(instruction);
 
+  // Flag direct page accesses that rely on the power-on value of D.
+  if (directPage == DirectPageStatus::NeverSet &&
+      instruction->isDirectPage()) {
+    subroutine()->directPageBeforeInit.insert(instruction->pc);
+  }
+  // Remember the data bank used by absolute accesses.
+  if (dataBank.has_value() && instruction->usesDataBank()) {
+    subroutine()->dataBanks[instruction->pc] = *dataBank;
+  }
+  // Block moves touch two banks, and leave the destination bank in DBR.
+  // They count down A to $FFFF, and advance X and Y past the moved bytes.
+  if (auto banks = instruction->moveBanks()) {
+    subroutine()->blockMoves[instruction->pc] = *banks;
+    changeDataBank(banks->second);
+    A.setWhole(0xFFFF);
+    X.setWhole(nullopt);
+    Y.setWhole(nullopt);
+  }
+
   switch (instruction->type()) {
     case InstructionType::Branch:
       return branch(instruction);
@@ -91,8 +222,16 @@ void CPU::execute(const Instruction* instruction) {
         changeA(instruction);
       } else if (instruction->changesX()) {
         changeX(instruction);
+      } else if (instruction->changesY()) {
+        changeY(instruction);
       } else if (instruction->changesStackPointer()) {
         changeStackPointer(instruction);
+      } else if (instruction->changesInterruptFlag()) {
+        changeInterruptFlag(instruction);
+      } else if (instruction->changesDirectPage()) {
+        changeDirectPage(instruction);
+      } else if (instruction->changesCarry()) {
+        changeCarry(instruction);
       }
       break;
   }
@@ -102,13 +241,15 @@ void CPU::execute(const Instruction* instruction) {
 void CPU::branch(const Instruction* instruction) {
   // Run a parallel instance of the CPU to cover
   // the case in which the branch is not taken.
-  CPU cpu(*this);
-  cpu.run();
+  if (!analysis->settings.takenBranchesOnly) {
+    CPU cpu(*this);
+    cpu.run();
+  }
 
   // Log the fact that the current instruction references the
   // instruction pointed by the branch. Then take the branch.
   auto target = *instruction->absoluteArgument();
-  analysis->addReference(instruction->pc, target, subroutinePC);
+  addReference(instruction->pc, target);
   pc = target;
 }
 
@@ -121,12 +262,17 @@ void CPU::call(const Instruction* instruction) {
   }
 
   for (auto target : *targets) {
+    if (!followsCalls()) {
+      continue;
+    }
     // Create a parallel instance of the CPU to
     // execute the subroutine that is being called.
     CPU cpu(*this);
     cpu.pc = target;
     cpu.subroutinePC = target;
     cpu.stateChange = StateChange();
+    cpu.criticalSectionStart = nullopt;
+    cpu.stackTrace.push_back(instruction->pc);
     // Push the return address on the stack.
     switch (instruction->operation()) {
       case Op::JSR:
@@ -140,17 +286,53 @@ void CPU::call(const Instruction* instruction) {
     }
 
     // Emulate the called subroutine.
-    analysis->addSubroutine(target);
-    analysis->addReference(instruction->pc, target, subroutinePC);
+    addSubroutine(target, nullopt, false, cpu.stackTrace);
+    addReference(instruction->pc, target);
     cpu.run();
   }
   // Propagate called subroutines state to caller.
+  // The callees might have changed the interrupt disable flag and the carry.
+  interruptsDisabled = nullopt;
+  carry = nullopt;
+  for (auto target : *targets) {
+    auto callee = findSubroutine(target);
+    if (callee != nullptr && callee->setsDirectPage) {
+      directPage = DirectPageStatus::Unknown;
+      subroutine()->setsDirectPage = true;
+    }
+    if (callee != nullptr && callee->setsDataBank) {
+      changeDataBank(nullopt);
+    }
+  }
   propagateSubroutineState(instruction->pc, *targets);
 }
 
 // Interrupt emulation.
 void CPU::interrupt(const Instruction* instruction) {
-  return unknownStateChange(instruction->pc, UnknownReason::SuspectInstruction);
+  // Without a known handler, we can't tell where the interrupt leads.
+  auto handler = analysis->interruptHandlers.find(instruction->operation());
+  if (handler == analysis->interruptHandlers.end()) {
+    return unknownStateChange(instruction->pc,
+                              UnknownReason::SuspectInstruction);
+  }
+
+  // Emulate the handler like a called subroutine, which returns after the
+  // signature byte. The interrupt disables further interrupts.
+  auto target = handler->second;
+  if (followsCalls()) {
+    CPU cpu(*this);
+    cpu.pc = target;
+    cpu.subroutinePC = target;
+    cpu.stateChange = StateChange();
+    cpu.criticalSectionStart = nullopt;
+    cpu.interruptsDisabled = true;
+    cpu.stackTrace.push_back(instruction->pc);
+    addSubroutine(target, nullopt, false, cpu.stackTrace);
+    addReference(instruction->pc, target);
+    cpu.run();
+  }
+
+  propagateSubroutineState(instruction->pc, {target});
 }
 
 // Jump emulation.
@@ -163,7 +345,7 @@ void CPU::jump(const Instruction* instruction) {
 
   // Execute each target in its own CPU instance.
   for (auto target : *targets) {
-    analysis->addReference(instruction->pc, target, subroutinePC);
+    addReference(instruction->pc, target);
     CPU cpu(*this);
     cpu.pc = target;
     cpu.run();
@@ -176,6 +358,8 @@ void CPU::jump(const Instruction* instruction) {
 // Return emulation.
 void CPU::ret(const Instruction* instruction) {
   if (instruction->operation() == Op::RTI) {
+    // RTI restores the interrupt disable flag.
+    closeCriticalSection(instruction->pc);
     return standardRet(instruction);
   }
 
@@ -185,12 +369,87 @@ void CPU::ret(const Instruction* instruction) {
     return standardRet(instruction);
   }
 
+  // Either a bug or a stack trick: the return doesn't go where it seems.
+  if (checkReturnMismatch(instruction, stackEntries)) {
+    return unknownStateChange(instruction->pc, UnknownReason::ReturnMismatch);
+  }
+
+  // The address was pushed explicitly (e.g. PEA + RTS): jump to it.
+  if (auto target = returnJumpTarget(instruction, stackEntries)) {
+    subroutine()->returnJumps[instruction->pc] = *target;
+    addReference(instruction->pc, *target);
+    pc = *target;
+    return;
+  }
+
   return unknownStateChange(instruction->pc, UnknownReason::StackManipulation);
 }
 
+// Return the address a return instruction jumps to, if the entries popped
+// from the stack hold a known value.
+optional<InstructionPC> CPU::returnJumpTarget(
+    const Instruction* instruction,
+    const vector<StackEntry>& entries) const {
+  u24 value = 0;
+  for (size_t i = 0; i < entries.size(); i++) {
+    if (!holds_alternative<u8>(entries[i].data)) {
+      return nullopt;
+    }
+    value |= get<u8>(entries[i].data) << (i * 8);
+  }
+
+  // RTS stays in the current bank, RTL pops the bank too.
+  auto bank = entries.size() == 2 ? (instruction->pc & 0xFF0000)
+                                   : (value & 0xFF0000);
+  return bank | ((value + 1) & 0xFFFF);
+}
+
 // Emulate a simple return.
 void CPU::standardRet(const Instruction* instruction) {
+  if (auto start = criticalSectionStart) {
+    subroutine()->unclosedCriticalSections.insert({*start, instruction->pc});
+  }
   subroutine()->addStateChange(instruction->pc, stateChange);
+  traceStop(instruction->pc, "return");
+  stop = true;
+}
+
+// Emulate running into the beginning of another subroutine.
+// The state change of that subroutine is propagated as after a call, and
+// execution leaves the current subroutine there.
+void CPU::fallThrough(SubroutinePC target) {
+  auto pc = lastInstruction->pc;
+  subroutine()->fallThroughs[pc] = target;
+
+  // Entry points keep their own label.
+  optional<string> label;
+  auto isEntryPoint = false;
+  for (auto& entryPoint : analysis->entryPoints) {
+    if (entryPoint.pc == target) {
+      label = entryPoint.label;
+      isEntryPoint = true;
+    }
+  }
+
+  if (followsCalls()) {
+    CPU cpu(*this);
+    cpu.subroutinePC = target;
+    cpu.stateChange = StateChange();
+    cpu.criticalSectionStart = nullopt;
+    addSubroutine(target, label, isEntryPoint, stackTrace);
+    addReference(pc, target);
+    cpu.run();
+  }
+
+  propagateSubroutineState(pc, {target});
+  if (stop) {
+    return;
+  }
+  if (auto start = criticalSectionStart) {
+    subroutine()->unclosedCriticalSections.insert({*start, pc});
+  }
+  subroutine()->fallThroughStateChanges[pc] = stateChange;
+  traceStop(pc, "fall-through");
   stop = true;
 }
 
@@ -202,11 +461,21 @@ void CPU::sepRep(const Instruction* instruction) {
     case Op::SEP:
       state.set(arg);
       stateChange.set(arg);
+      if (arg & 0x04) {
+        disableInterrupts(instruction->pc);
+      }
       break;
 
     case Op::REP:
+      // In emulation mode, A and X can't be switched to 16-bits.
+      if (state.e) {
+        arg &= ~0x30;
+      }
       state.reset(arg);
       stateChange.reset(arg);
+      if (arg & 0x04) {
+        enableInterrupts(instruction->pc);
+      }
       break;
 
     default:
@@ -227,8 +496,22 @@ void CPU::pop(const Instruction* instruction) {
       if (entry.instruction && entry.instruction->operation() == Op::PHP) {
         // Regular state restoring.
         auto [state, stateChange] = get<pair<State, StateChange>>(entry.data);
+        // E is not part of P: it stays as it is, forcing 8-bits A and X.
+        state.e = this->state.e;
+        if (state.e) {
+          state.set(0x30);
+        }
         this->state = state;
         this->stateChange = stateChange;
+
+        // Restore the interrupt disable flag. If a critical section was
+        // opened after the PHP, this is where it ends.
+        auto [disabled, start] = savedInterruptStates[entry.instruction->pc];
+        if (!start.has_value()) {
+          closeCriticalSection(instruction->pc);
+        }
+        interruptsDisabled = disabled;
+        criticalSectionStart = start;
       } else {
         // Stack manipulation. Stop here.
         return unknownStateChange(instruction->pc,
@@ -237,27 +520,32 @@ void CPU::pop(const Instruction* instruction) {
     } break;
 
     case Op::PLA:
-      if (auto value = stack.popValue(state.sizeA())) {
-        A.set(*value);
+      for (auto& entry : stack.peek(state.sizeA())) {
+        if (entry.instruction && entry.instruction->operation() != Op::PHA) {
+          // Stack manipulation. Stop here.
+          return unknownStateChange(instruction->pc,
+                                    UnknownReason::StackManipulation);
+        }
       }
+      A.set(popRegister(Op::PHA, state.sizeA()));
       break;
 
     case Op::PLX:
-      if (auto value = stack.popValue(state.sizeX())) {
-        X.set(*value);
-      }
+      X.set(popRegister(Op::PHX, state.sizeX()));
       break;
 
     case Op::PLY:
-      stack.pop(state.sizeX());
+      Y.set(popRegister(Op::PHY, state.sizeX()));
       break;
 
     case Op::PLB:
-      stack.popOne();
+      changeDataBank(stack.popValue(1));
       break;
 
     case Op::PLD:
-      stack.pop(2);
+      directPage = stack.popValue(2).has_value() ? DirectPageStatus::Known
+                                                 : DirectPageStatus::Unknown;
+      subroutine()->setsDirectPage = true;
       break;
 
     default:
@@ -269,6 +557,8 @@ void CPU::pop(const Instruction* instruction) {
 void CPU::push(const Instruction* instruction) {
   switch (instruction->operation()) {
     case Op::PHP:
+      savedInterruptStates[instruction->pc] = {interruptsDisabled,
+                                               criticalSectionStart};
       return stack.pushState(state, stateChange, instruction);
 
     case Op::PHA:
@@ -278,14 +568,18 @@ void CPU::push(const Instruction* instruction) {
       return stack.pushValue(state.sizeX(), X.get(), instruction);
 
     case Op::PHY:
-      return stack.pushValue(state.sizeX(), nullopt, instruction);
+      return stack.pushValue(state.sizeX(), Y.get(), instruction);
 
     case Op::PHB:
+      return stack.pushOne(dataBank, instruction);
+
     case Op::PHK:
-      return stack.pushOne(nullopt, instruction);
+      return stack.pushOne(instruction->pc >> 16, instruction);
 
-    case Op::PHD:
     case Op::PEA:
+      return stack.pushValue(2, instruction->argument(), instruction);
+
+    case Op::PHD:
     case Op::PER:
     case Op::PEI:
       return stack.pushValue(2, nullopt, instruction);
@@ -295,6 +589,28 @@ void CPU::push(const Instruction* instruction) {
   }
 }
 
+// Pop the value of a register, if it was pushed by the matching instruction
+// with the same size.
+optional<u16> CPU::popRegister(Op pushOp, size_t size) {
+  auto entries = stack.pop(size);
+
+  u16 value = 0;
+  for (size_t i = 0; i < entries.size(); i++) {
+    auto pusher = entries[i].instruction;
+    if (pusher == nullptr || pusher->operation() != pushOp) {
+      return nullopt;
+    }
+    // Mismatched sizes (e.g. a 16-bit push popped in 8-bit mode).
+    auto pushSize =
+        pushOp == Op::PHA ? pusher->state.sizeA() : pusher->state.sizeX();
+    if (pushSize != size || !holds_alternative<u8>(entries[i].data)) {
+      return nullopt;
+    }
+    value |= get<u8>(entries[i].data) << (i * 8);
+  }
+  return value;
+}
+
 // Emulate instructions that modify the value of A.
 void CPU::changeA(const Instruction* instruction) {
   if (instruction->addressMode() == AddressMode::ImmediateM) {
@@ -330,6 +646,10 @@ void CPU::changeA(const Instruction* instruction) {
         A.setWhole(X.getWhole());
         break;
 
+      case Op::TYA:
+        A.setWhole(Y.getWhole());
+        break;
+
       default:
         A.set(nullopt);
         break;
@@ -360,6 +680,22 @@ void CPU::changeX(const Instruction* instruction) {
         X.setWhole(stack.pointer);
         break;
 
+      case Op::TYX:
+        X.setWhole(Y.getWhole());
+        break;
+
+      case Op::INX:
+        if (auto x = X.get()) {
+          X.set(*x + 1);
+        }
+        break;
+
+      case Op::DEX:
+        if (auto x = X.get()) {
+          X.set(*x - 1);
+        }
+        break;
+
       default:
         X.set(nullopt);
         break;
@@ -367,6 +703,48 @@ void CPU::changeX(const Instruction* instruction) {
   }
 }
 
+// Emulate instructions that modify the value of Y.
+void CPU::changeY(const Instruction* instruction) {
+  if (instruction->addressMode() == AddressMode::ImmediateX) {
+    auto arg = *instruction->argument();
+    switch (instruction->operation()) {
+      case Op::LDY:
+        Y.set(arg);
+        break;
+
+      default:
+        Y.set(nullopt);
+        break;
+    }
+  } else {
+    switch (instruction->operation()) {
+      case Op::TAY:
+        Y.setWhole(A.getWhole());
+        break;
+
+      case Op::TXY:
+        Y.setWhole(X.getWhole());
+        break;
+
+      case Op::INY:
+        if (auto y = Y.get()) {
+          Y.set(*y + 1);
+        }
+        break;
+
+      case Op::DEY:
+        if (auto y = Y.get()) {
+          Y.set(*y - 1);
+        }
+        break;
+
+      default:
+        Y.set(nullopt);
+        break;
+    }
+  }
+}
+
 // Emulate instructions that modify the value of the stack pointer.
 void CPU::changeStackPointer(const Instruction* instruction) {
   switch (instruction->operation()) {
@@ -387,6 +765,100 @@ void CPU::changeStackPointer(const Instruction* instruction) {
   }
 }
 
+// Emulate instructions that modify the interrupt disable flag.
+void CPU::changeInterruptFlag(const Instruction* instruction) {
+  switch (instruction->operation()) {
+    case Op::SEI:
+      return disableInterrupts(instruction->pc);
+
+    case Op::CLI:
+      return enableInterrupts(instruction->pc);
+
+    default:
+      __builtin_unreachable();
+  }
+}
+
+// Emulate instructions that modify the direct page register.
+void CPU::changeDirectPage(const Instruction* instruction) {
+  switch (instruction->operation()) {
+    case Op::TCD:
+      directPage = A.getWhole().has_value() ? DirectPageStatus::Known
+                                            : DirectPageStatus::Unknown;
+      break;
+
+    default:
+      __builtin_unreachable();
+  }
+  subroutine()->setsDirectPage = true;
+}
+
+// Emulate instructions that modify the carry or the emulation flag.
+void CPU::changeCarry(const Instruction* instruction) {
+  switch (instruction->operation()) {
+    case Op::CLC:
+      carry = false;
+      break;
+
+    case Op::SEC:
+      carry = true;
+      break;
+
+    case Op::XCE: {
+      // We can't tell which mode the CPU switches to.
+      if (!carry.has_value()) {
+        return unknownStateChange(instruction->pc,
+                                  UnknownReason::EmulationMode);
+      }
+      auto e = state.e;
+      state.e = *carry;
+      carry = e;
+      if (state.e) {
+        // Entering emulation mode forces A and X to 8-bits.
+        state.set(0x30);
+        stateChange.set(0x30);
+      }
+    } break;
+
+    default:
+      __builtin_unreachable();
+  }
+}
+
+// Emulate instructions that modify the data bank register.
+void CPU::changeDataBank(optional<u8> value) {
+  dataBank = value;
+  subroutine()->setsDataBank = true;
+}
+
+// Open a critical section.
+void CPU::disableInterrupts(InstructionPC pc) {
+  if (interruptsDisabled == true) {
+    subroutine()->nestedCriticalSections.insert(pc);
+  }
+  if (!criticalSectionStart.has_value()) {
+    criticalSectionStart = pc;
+  }
+  interruptsDisabled = true;
+}
+
+// Close a critical section.
+void CPU::enableInterrupts(InstructionPC pc) {
+  if (interruptsDisabled == false) {
+    subroutine()->redundantInterruptEnables.insert(pc);
+  }
+  closeCriticalSection(pc);
+  interruptsDisabled = false;
+}
+
+// Record a critical section.
+void CPU::closeCriticalSection(InstructionPC pc) {
+  if (auto start = criticalSectionStart) {
+    subroutine()->criticalSections.insert({*start, pc});
+    criticalSectionStart = nullopt;
+  }
+}
+
 // Apply a state change to the current CPU instance.
 void CPU::applyStateChange(StateChange stateChange) {
   if (auto m = stateChange.m) {
@@ -420,6 +892,20 @@ bool CPU::checkReturnManipulation(const Instruction* instruction,
   return false;
 }
 
+// Check whether the return instruction pops the address pushed by a call
+// of the other width (JSR returning with RTL, or JSL with RTS).
+bool CPU::checkReturnMismatch(const Instruction* instruction,
+                              const vector<StackEntry>& entries) const {
+  auto call = instruction->operation() == Op::RTS ? Op::JSL : Op::JSR;
+  for (auto& entry : entries) {
+    if (entry.instruction != nullptr &&
+        entry.instruction->operation() == call) {
+      return true;
+    }
+  }
+  return false;
+}
+
 // Given a jump or call instruction, return its target(s), if any.
 // Additionally, track jump tables when they're seen for the first time.
 optional<unordered_set<InstructionPC>> CPU::computeJumpTargets(
@@ -434,10 +920,29 @@ optional<unordered_set<InstructionPC>> CPU::computeJumpTargets(
   // Indirect jump/call.
   auto jumpTableSearch = analysis->jumpTables.find(instruction->pc);
   if (jumpTableSearch == analysis->jumpTables.end() ||
-      jumpTableSearch->second.status == JumpTableStatus::Unknown) {
+      jumpTableSearch->second.status == JumpTableStatus::Unknown ||
+      jumpTableSearch->second.status == JumpTableStatus::Emulated) {
+    // Table indexed by a known value of X: follow the single entry, and
+    // record it in the table of the jump.
+    if (auto target = indexedJumpTarget(instruction)) {
+      if (dryRun == nullptr) {
+        auto& jumpTable =
+            analysis->jumpTables
+                .try_emplace(instruction->pc,
+                             JumpTable{JumpTableStatus::Emulated, {}})
+                .first->second;
+        jumpTable.status = JumpTableStatus::Emulated;
+        jumpTable.targets[X.get()] = *target;
+        subroutine()->indexedJumps = true;
+      }
+      targets.insert(*target);
+      return targets;
+    }
     // Unknown jump table.
-    analysis->jumpTables.try_emplace(instruction->pc,
-                                     JumpTable{JumpTableStatus::Unknown, {}});
+    if (dryRun == nullptr) {
+      analysis->jumpTables.try_emplace(
+          instruction->pc, JumpTable{JumpTableStatus::Unknown, {}});
+    }
     return nullopt;
   } else {
     // Collect jump table's targets.
@@ -448,27 +953,98 @@ optional<unordered_set<InstructionPC>> CPU::computeJumpTargets(
   return targets;
 }
 
+// Return the target of a jump or call through a table indexed by X,
+// if the value of X is known.
+optional<InstructionPC> CPU::indexedJumpTarget(const Instruction* instruction) {
+  auto x = X.get();
+  if (instruction->addressMode() != AddressMode::AbsoluteIndexedIndirect ||
+      !x.has_value()) {
+    return nullopt;
+  }
+
+  // The table lives in the program bank.
+  auto bank = instruction->pc & 0xFF0000;
+  auto entry = bank | ((*instruction->argument() + *x) & 0xFFFF);
+  auto& rom = analysis->rom;
+  if (!rom.isROM(entry) || !rom.isROM(entry + 1)) {
+    return nullopt;
+  }
+  return bank | rom.readWord(entry);
+}
+
 // Derive a state inference from the current state and instruction.
 void CPU::deriveStateInference(const Instruction* instruction) {
   // If we're executing an instruction with a certain operand size,
   // and no state change has been performed in the current subroutine,
   // then we can infer that the state of the processor as we enter
   // the subroutine *must* be the same in all cases.
+  // The entry state of an entry point is only a guess, though: there, the
+  // inference is reported as a suggestion to the user instead of applied.
+  auto& settings = analysis->settings;
+  auto subroutine = this->subroutine();
   if (instruction->addressMode() == AddressMode::ImmediateM &&
       !stateChange.m.has_value()) {
-    stateInference.m = (bool)state.m;
+    if (subroutine->isEntryPoint) {
+      subroutine->assumedState.m = (bool)state.m;
+    } else if (settings.stateInference) {
+      stateInference.m = (bool)state.m;
+    }
   }
   if (instruction->addressMode() == AddressMode::ImmediateX &&
       !stateChange.x.has_value()) {
-    stateInference.x = (bool)state.x;
+    if (subroutine->isEntryPoint) {
+      subroutine->assumedState.x = (bool)state.x;
+    } else if (settings.stateInference) {
+      stateInference.x = (bool)state.x;
+    }
   }
 }
 
 // Return a pointer to the current subroutine object.
 Subroutine* CPU::subroutine() const {
+  if (dryRun != nullptr) {
+    auto& subroutines = dryRun->subroutines;
+    return &subroutines.try_emplace(subroutinePC, subroutinePC, "")
+                .first->second;
+  }
   return &analysis->subroutines.at(subroutinePC);
 }
 
+// Whether the CPU emulates the subroutines it calls or runs into.
+bool CPU::followsCalls() const {
+  return dryRun == nullptr || dryRun->followCalls;
+}
+
+// Return a pointer to the subroutine at the given PC, if visited.
+// Subroutines not followed by a dry run are looked up in the analysis.
+const Subroutine* CPU::findSubroutine(SubroutinePC pc) const {
+  auto& subroutines = followsCalls() && dryRun != nullptr
+                          ? dryRun->subroutines
+                          : analysis->subroutines;
+  auto search = subroutines.find(pc);
+  return search != subroutines.end() ? &search->second : nullptr;
+}
+
+// Add a subroutine to the analysis (or to the dry run).
+void CPU::addSubroutine(SubroutinePC pc,
+                        optional<string> label,
+                        bool isEntryPoint,
+                        const StackTrace& stackTrace) {
+  if (dryRun != nullptr) {
+    dryRun->subroutines.try_emplace(pc, pc, label.value_or(""), isEntryPoint);
+  } else {
+    analysis->addSubroutine(pc, label, isEntryPoint, stackTrace);
+  }
+}
+
+// Add a reference from an instruction of the current subroutine to another,
+// unless on a dry run.
+void CPU::addReference(InstructionPC source, InstructionPC target) {
+  if (dryRun == nullptr) {
+    analysis->addReference(source, target, subroutinePC);
+  }
+}
+
 // Take the state change of the given subroutines and
 // propagate it to to the current subroutine state.
 void CPU::propagateSubroutineState(
@@ -478,13 +1054,13 @@ void CPU::propagateSubroutineState(
 
   // Iterate through all the called subroutines.
   for (auto target : targets) {
-    auto& subroutine = analysis->subroutines.at(target);
+    auto subroutine = findSubroutine(target);
     // Unknown state change.
-    if (!subroutine.unknownStateChanges.empty()) {
+    if (subroutine == nullptr || !subroutine->unknownStateChanges.empty()) {
       return unknownStateChange(pc, UnknownReason::Unknown);
     }
     // Gather all state changes across subroutines.
-    stateChanges.merge(subroutine.simplifiedStateChanges(state));
+    stateChanges.merge(subroutine->simplifiedStateChanges(state));
   }
 
   // Ambiguous states.
@@ -501,6 +1077,9 @@ void CPU::unknownStateChange(InstructionPC pc, UnknownReason reason) {
   // Check if we have an assertion to specify what the state change is.
   auto assertion = analysis->getAssertion(pc, subroutinePC);
   if (assertion.has_value()) {
+    if (dryRun == nullptr) {
+      analysis->notify(AssertionConsumed{assertion->type, pc});
+    }
     switch (assertion->type) {
       case AssertionType::Instruction:
         applyStateChange(assertion->stateChange);
@@ -508,12 +1087,24 @@ void CPU::unknownStateChange(InstructionPC pc, UnknownReason reason) {
 
       case AssertionType::Subroutine:
         subroutine()->addStateChange(pc, assertion->stateChange);
+        traceStop(pc, "subroutine assertion");
         stop = true;
         break;
     }
   } else {
     // No assertions, we need stop here.
+    if (dryRun == nullptr) {
+      analysis->notify(UnknownStateChange{subroutinePC, pc, reason});
+    }
     subroutine()->addStateChange(pc, StateChange(UnknownReason(reason)));
+    traceStop(pc, unknownReasonName(reason));
     stop = true;
   }
 }
+
+// Record where the emulation stopped, if on a dry run.
+void CPU::traceStop(InstructionPC pc, const string& reason) const {
+  if (dryRun != nullptr) {
+    dryRun->steps.push_back({pc, "", state, state, reason});
+  }
+}
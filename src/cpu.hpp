@@ -1,17 +1,75 @@
 #pragma once
 
+#include <map>
+#include <optional>
+#include <string>
+#include <unordered_map>
 #include <unordered_set>
 #include <utility>
 #include <vector>
 
+#include "instruction.hpp"
+#include "opcodes.hpp"
 #include "register.hpp"
 #include "stack.hpp"
 #include "state.hpp"
+#include "subroutine.hpp"
 #include "types.hpp"
 
 class Analysis;
-class Instruction;
-class Subroutine;
+
+// What we know about the interrupt disable flag,
+// and the instruction that opened the current critical section.
+typedef std::pair<std::optional<bool>, std::optional<InstructionPC>>
+    InterruptState;
+
+// What we know about the direct page register.
+enum class DirectPageStatus {
+  NeverSet,  // Not set yet: still at its power-on value ($0000).
+  Known,     // Set to a known value.
+  Unknown,   // Set to an unknown value.
+};
+
+// What the emulator knows about the CPU when it reaches an instruction.
+struct CPUSnapshot {
+  State state;                             // CPU state (size of A and X).
+  std::optional<u16> a;                    // Value of A, if known.
+  std::optional<u16> x;                    // Value of X, if known.
+  std::optional<u16> y;                    // Value of Y, if known.
+  std::optional<u8> dataBank;              // Value of DBR, if known.
+  DirectPageStatus directPage;             // What we know about D.
+  std::optional<bool> interruptsDisabled;  // Interrupt disable flag.
+};
+
+// Instruction emulated while tracing the analysis, or point where the
+// emulation stopped.
+struct TraceStep {
+  InstructionPC pc;         // Address of the instruction.
+  std::string instruction;  // Instruction emulated (empty if stopped before).
+  State before;             // CPU state before the instruction.
+  State after;              // CPU state after the instruction.
+  std::string note;         // Branch decision, or why the emulation stopped.
+};
+
+// Recording of a dry run, in which the CPU emulates the code without changing
+// the analysis.
+struct DryRun {
+  // Whether the CPU follows calls (and runs into the following subroutines),
+  // or takes their state changes from the analysis.
+  bool followCalls = true;
+  // Instruction whose CPU snapshot is captured, if any.
+  std::optional<PCPair> snapshotPC;
+  // CPU snapshot captured at that instruction.
+  std::optional<CPUSnapshot> snapshot;
+  // Instructions left to emulate, if limited.
+  std::optional<std::size_t> budget;
+  // Instructions emulated, and points where the emulation stopped.
+  std::vector<TraceStep> steps;
+  // Instructions visited so far.
+  InstructionSet instructions;
+  // Subroutines visited so far, with what the emulation found out about them.
+  std::map<SubroutinePC, Subroutine> subroutines;
+};
 
 class CPU {
  public:
@@ -26,6 +84,9 @@ class CPU {
   void run();   // Start emulating.
   void step();  // Fetch and execute the next instruction.
 
+  // Return what we know about the CPU at this point.
+  CPUSnapshot snapshot();
+
   // Whether we should stop emulating after the current instruction.
   bool stop = false;
 
@@ -41,8 +102,32 @@ class CPU {
 
   Register A;  // Accumulator.
   Register X;  // Index X.
+  Register Y;  // Index Y.
+
+  // What we know about the interrupt disable flag.
+  std::optional<bool> interruptsDisabled;
+  // Instruction that opened the current critical section, if any.
+  std::optional<InstructionPC> criticalSectionStart;
+
+  // Call instructions leading to the current subroutine.
+  StackTrace stackTrace;
+
+  // What we know about the direct page register.
+  DirectPageStatus directPage = DirectPageStatus::NeverSet;
+
+  // Value of the data bank register, if known.
+  std::optional<u8> dataBank = 0x00;
+
+  // What we know about the carry flag.
+  std::optional<bool> carry;
+
+  // Recording of the dry run, if the CPU is not changing the analysis.
+  DryRun* dryRun = nullptr;
 
  private:
+  // Add the instruction at PC to the analysis (or to the dry run). Return
+  // nullptr if the instruction has already been visited.
+  const Instruction* addInstruction(u8 opcode, u24 argument);
   // Emulate an instruction.
   void execute(const Instruction* instruction);
 
@@ -52,16 +137,36 @@ class CPU {
   void jump(const Instruction* instruction);         // Jump emulation.
   void ret(const Instruction* instruction);          // Return emulation.
   void standardRet(const Instruction* instruction);  // Emulate a simple return.
+  // Emulate running into the beginning of another subroutine.
+  void fallThrough(SubroutinePC target);
   void sepRep(const Instruction* instruction);       // SEP/REP emulation.
   void pop(const Instruction* instruction);          // Pop value from stack.
   void push(const Instruction* instruction);         // Push value onto stack.
 
+  // Pop the value of a register, if it was pushed by the matching instruction
+  // with the same size.
+  std::optional<u16> popRegister(Op pushOp, std::size_t size);
+
   // Emulate instructions that modify the value of A.
   void changeA(const Instruction* instruction);
   // Emulate instructions that modify the value of X.
   void changeX(const Instruction* instruction);
+  // Emulate instructions that modify the value of Y.
+  void changeY(const Instruction* instruction);
   // Emulate instructions that modify the value of the stack pointer.
   void changeStackPointer(const Instruction* instruction);
+  // Emulate instructions that modify the interrupt disable flag.
+  void changeInterruptFlag(const Instruction* instruction);
+  // Emulate instructions that modify the direct page register.
+  void changeDirectPage(const Instruction* instruction);
+  // Emulate instructions that modify the carry or the emulation flag.
+  void changeCarry(const Instruction* instruction);
+  // Emulate instructions that modify the data bank register.
+  void changeDataBank(std::optional<u8> value);
+
+  void disableInterrupts(InstructionPC pc);     // Open a critical section.
+  void enableInterrupts(InstructionPC pc);      // Close a critical section.
+  void closeCriticalSection(InstructionPC pc);  // Record a critical section.
 
   // Apply a state change to the current CPU instance.
   void applyStateChange(StateChange stateChange);
@@ -69,17 +174,44 @@ class CPU {
   // Check whether the return instruction is operating on a manipulated stack.
   bool checkReturnManipulation(const Instruction* instruction,
                                std::vector<StackEntry> entries) const;
+  // Check whether the return instruction pops the address pushed by a call
+  // of the other width (JSR returning with RTL, or JSL with RTS).
+  bool checkReturnMismatch(const Instruction* instruction,
+                           const std::vector<StackEntry>& entries) const;
+
+  // Return the address a return instruction jumps to, if the entries popped
+  // from the stack hold a known value.
+  std::optional<InstructionPC> returnJumpTarget(
+      const Instruction* instruction,
+      const std::vector<StackEntry>& entries) const;
 
   // Given a jump or call instruction, return its target(s), if any.
   // Additionally, track jump tables when they're seen for the first time.
   std::optional<std::unordered_set<InstructionPC>> computeJumpTargets(
       const Instruction* instruction);
+  // Return the target of a jump or call through a table indexed by X,
+  // if the value of X is known.
+  std::optional<InstructionPC> indexedJumpTarget(
+      const Instruction* instruction);
 
   // Derive a state inference from the current state and instruction.
   void deriveStateInference(const Instruction* instruction);
 
   // Return a pointer to the current subroutine object.
   Subroutine* subroutine() const;
+  // Whether the CPU emulates the subroutines it calls or runs into.
+  bool followsCalls() const;
+  // Return a pointer to the subroutine at the given PC, if visited.
+  const Subroutine* findSubroutine(SubroutinePC pc) const;
+
+  // Add a subroutine to the analysis (or to the dry run).
+  void addSubroutine(SubroutinePC pc,
+                     std::optional<std::string> label,
+                     bool isEntryPoint,
+                     const StackTrace& stackTrace);
+  // Add a reference from an instruction of the current subroutine to another,
+  // unless on a dry run.
+  void addReference(InstructionPC source, InstructionPC target);
 
   // Take the state change of the given subroutines and
   // propagate it to to the current subroutine state.
@@ -90,9 +222,17 @@ class CPU {
   // Signal an unknown subroutine state change.
   void unknownStateChange(InstructionPC pc, UnknownReason reason);
 
+  // Record where the emulation stopped, if on a dry run.
+  void traceStop(InstructionPC pc, const std::string& reason) const;
+
   // Pointer to the analysis.
   Analysis* analysis;
 
+  // Interrupt states saved by PHP instructions, restored by PLP.
+  std::unordered_map<InstructionPC, InterruptState> savedInterruptStates;
+  // Last instruction executed, if any.
+  const Instruction* lastInstruction = nullptr;
+
   // Test functions.
   friend void runInstruction(CPU& cpu, u8 opcode, u24 argument);
 };
@@ -0,0 +1,29 @@
+#include "datablock.hpp"
+
+using namespace std;
+
+// Return the name of a data type.
+string dataTypeName(DataType type) {
+  switch (type) {
+    case DataType::Bytes:
+      return "bytes";
+    case DataType::Words:
+      return "words";
+    case DataType::Pointers:
+      return "pointers";
+    case DataType::Text:
+      return "text";
+  }
+  __builtin_unreachable();
+}
+
+// Return the data type with the given name, if any.
+optional<DataType> parseDataType(const string& name) {
+  for (auto type :
+       {DataType::Bytes, DataType::Words, DataType::Pointers, DataType::Text}) {
+    if (dataTypeName(type) == name) {
+      return type;
+    }
+  }
+  return nullopt;
+}
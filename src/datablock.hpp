@@ -0,0 +1,33 @@
+#pragma once
+
+#include <optional>
+#include <string>
+
+#include "types.hpp"
+
+// How the bytes of a data block are interpreted.
+enum class DataType {
+  Bytes,     // Plain bytes.
+  Words,     // 16-bit values.
+  Pointers,  // 16-bit addresses in the bank of the block.
+  Text,      // ASCII characters.
+};
+
+// Region of the ROM declared as data, which the analysis must not decode.
+struct DataBlock {
+  u24 start;      // First address of the block.
+  u24 end;        // Address following the last byte.
+  DataType type;  // How the bytes are interpreted.
+
+  template <class Archive>
+  void serialize(Archive& ar, const unsigned int) {
+    ar& start;
+    ar& end;
+    ar& type;
+  }
+};
+
+// Return the name of a data type.
+std::string dataTypeName(DataType type);
+// Return the data type with the given name, if any.
+std::optional<DataType> parseDataType(const std::string& name);
@@ -0,0 +1,960 @@
+#include <algorithm>
+#include <map>
+#include <set>
+
+#include "disassembly.hpp"
+
+#include "analysis.hpp"
+#include "hardwareregisters.hpp"
+#include "subroutine.hpp"
+#include "utils.hpp"
+
+using namespace std;
+
+// Constructor.
+Disassembly::Disassembly(const Analysis* analysis) : analysis{analysis} {}
+
+// Return the lines of the disassembly of a subroutine.
+vector<LineItem> Disassembly::subroutineItems(SubroutinePC pc) const {
+  auto& subroutine = analysis->subroutines.at(pc);
+  vector<LineItem> items;
+
+  items.push_back(
+      LabelLine{subroutine.label, {pc, pc}, subroutine.isEntryPoint});
+  if (analysis->settings.localLabelTable) {
+    auto table = localLabelTable(subroutine);
+    items.insert(items.end(), table.begin(), table.end());
+  }
+
+  for (auto& [instructionPC, instruction] : subroutine.instructions) {
+    if (auto label = instruction->label) {
+      items.push_back(LabelLine{*label, instruction->pcPair(), false});
+    }
+    items.push_back(instructionItem(instruction));
+
+    auto preview = jumpTablePreview(instruction);
+    items.insert(items.end(), preview.begin(), preview.end());
+  }
+
+  // Data blocks the subroutine runs into.
+  set<InstructionPC> stops;
+  for (auto& [stopPC, stateChange] : subroutine.unknownStateChanges) {
+    stops.insert(stopPC);
+  }
+  for (auto stopPC : stops) {
+    auto search = analysis->dataBlocks.find(stopPC);
+    if (search != analysis->dataBlocks.end()) {
+      auto data = dataBlockItems(search->second);
+      items.insert(items.end(), data.begin(), data.end());
+    }
+  }
+
+  items.push_back(BlankLine{});
+  return items;
+}
+
+// Return the label of a subroutine, or `unknown @ $XXXXXX` if it hasn't
+// been analyzed.
+string Disassembly::subroutineLabel(SubroutinePC pc) const {
+  auto search = analysis->subroutines.find(pc);
+  if (search == analysis->subroutines.end()) {
+    return format("unknown @ $%06X", pc);
+  }
+  return search->second.label;
+}
+
+// Return the lines of the disassembly of all the subroutines.
+vector<LineItem> Disassembly::items() const {
+  vector<LineItem> items;
+  for (auto& [subroutinePC, subroutine] : analysis->subroutines) {
+    auto subroutineItems = this->subroutineItems(subroutinePC);
+    items.insert(items.end(), subroutineItems.begin(), subroutineItems.end());
+  }
+  return items;
+}
+
+// Return the disassembly of all the subroutines as plain text.
+string Disassembly::all() const {
+  return render(items());
+}
+
+// Return the documentation of a subroutine as a Markdown section.
+string Disassembly::subroutineMarkdown(SubroutinePC pc) const {
+  auto& subroutine = analysis->subroutines.at(pc);
+
+  // Entry state, taken from the first instruction.
+  auto entry = subroutine.instructions.find(pc);
+  auto entryState = entry != subroutine.instructions.end()
+                        ? stateDescription(entry->second->state)
+                        : "unknown";
+
+  // Return states.
+  set<string> returnStates;
+  for (auto& [instructionPC, stateChange] : subroutine.knownStateChanges) {
+    returnStates.insert(stateChangeDescription(stateChange));
+  }
+  for (auto& [instructionPC, stateChange] :
+       subroutine.fallThroughStateChanges) {
+    returnStates.insert(stateChangeDescription(stateChange));
+  }
+  if (!subroutine.unknownStateChanges.empty()) {
+    returnStates.insert("unknown");
+  }
+
+  // Subroutines calling this one, and subroutines called by it.
+  set<SubroutinePC> callers;
+  for (auto& [source, referenceSet] : analysis->references) {
+    auto instruction = analysis->anyInstruction(source);
+    if (instruction == nullptr ||
+        instruction->type() != InstructionType::Call) {
+      continue;
+    }
+    for (auto& reference : referenceSet) {
+      if (reference.target == pc) {
+        callers.insert(reference.subroutinePC);
+      }
+    }
+  }
+  set<SubroutinePC> callees;
+  for (auto& [instructionPC, instruction] : subroutine.instructions) {
+    if (instruction->type() == InstructionType::Call) {
+      for (auto& [target, targetSubroutine] :
+           analysis->followTargets(instructionPC, pc)) {
+        callees.insert(targetSubroutine);
+      }
+    }
+  }
+
+  auto join = [](const auto& strings) {
+    string result;
+    for (auto& s : strings) {
+      result += (result.empty() ? "" : ", ") + s;
+    }
+    return result.empty() ? "none" : result;
+  };
+  auto labels = [this](const auto& pcs) {
+    vector<string> result;
+    for (auto subroutinePC : pcs) {
+      result.push_back("`" + analysis->subroutines.at(subroutinePC).label +
+                       "`");
+    }
+    return result;
+  };
+
+  // What the code of an entry point assumes about its guessed entry state.
+  string assumedState;
+  if (subroutine.isEntryPoint && !subroutine.assumedState.isEmpty()) {
+    assumedState = "- **Assumed entry state:** " +
+                   stateChangeDescription(subroutine.assumedState) + "\n";
+  }
+
+  auto code = render(subroutineItems(pc));
+  code.pop_back();  // Remove the trailing blank line.
+
+  return "## " + subroutine.label + "\n\n" +
+         "- **Entry state:** " + entryState + "\n" + assumedState +
+         "- **Return states:** " + join(returnStates) + "\n" +
+         "- **Callers:** " + join(labels(callers)) + "\n" +
+         "- **Callees:** " + join(labels(callees)) + "\n\n" +
+         "```asm\n" + code + "```\n";
+}
+
+// Return the documentation of all the subroutines as a Markdown document.
+string Disassembly::markdown() const {
+  string text = "# " + analysis->rom.title() + "\n";
+  for (auto& [pc, subroutine] : analysis->subroutines) {
+    text += "\n" + subroutineMarkdown(pc);
+  }
+  return text;
+}
+
+// Return the call graph in Graphviz DOT format. Entry points are boxed,
+// subroutines responsible for an unknown state are red, and calls through
+// jump tables are dashed. Each call is labeled with its address.
+string Disassembly::callGraph() const {
+  auto& subroutines = analysis->subroutines;
+  string text = "digraph calls {\n";
+  for (auto& [pc, subroutine] : subroutines) {
+    vector<string> attributes;
+    if (subroutine.isEntryPoint) {
+      attributes.push_back("shape=box");
+    }
+    if (subroutine.isResponsibleForUnknown()) {
+      attributes.push_back("color=red");
+    }
+
+    text += "  " + quote(subroutine.label);
+    for (size_t i = 0; i < attributes.size(); i++) {
+      text += (i == 0 ? " [" : ", ") + attributes[i];
+    }
+    text += attributes.empty() ? ";\n" : "];\n";
+  }
+  for (auto& site : analysis->callSites()) {
+    text += "  " + quote(subroutines.at(site.caller).label) + " -> " +
+            quote(subroutines.at(site.callee).label) +
+            format(" [label=\"$%06X\"", site.pc) +
+            (site.indirect ? ", style=dashed];\n" : "];\n");
+  }
+  return text + "}\n";
+}
+
+// Return the call graph in JSON format, as a list of nodes (subroutines)
+// and a list of edges (calls).
+string Disassembly::callGraphJSON() const {
+  string text = "{\n  \"nodes\": [";
+  bool first = true;
+  for (auto& [pc, subroutine] : analysis->subroutines) {
+    text += first ? "\n" : ",\n";
+    text += "    {\"label\": " + quote(subroutine.label) +
+            format(", \"pc\": %u", pc) + ", \"has_unknown_state_change\": " +
+            (subroutine.unknownStateChanges.empty() ? "false" : "true") +
+            ", \"is_entry_point\": " +
+            (subroutine.isEntryPoint ? "true" : "false") + "}";
+    first = false;
+  }
+  text += first ? "],\n" : "\n  ],\n";
+
+  text += "  \"edges\": [";
+  first = true;
+  for (auto& site : analysis->callSites()) {
+    text += first ? "\n" : ",\n";
+    text += format(
+        "    {\"source\": %u, \"target\": %u, \"call_site\": %u, "
+        "\"indirect\": %s}",
+        site.caller, site.callee, site.pc, site.indirect ? "true" : "false");
+    first = false;
+  }
+  text += first ? "]\n" : "\n  ]\n";
+  return text + "}\n";
+}
+
+// Return the labels and comments as a symbol file for the debuggers of
+// bsnes-plus and Mesen: a line `BB:AAAA text` per address, in a
+// `[labels]` and a `[comments]` section.
+string Disassembly::symbols() const {
+  vector<pair<u24, string>> labels;
+  for (auto& [pc, subroutine] : analysis->subroutines) {
+    labels.push_back({pc, subroutine.label});
+    for (auto& [instructionPC, instruction] : subroutine.instructions) {
+      if (instruction->label.has_value()) {
+        labels.push_back({instructionPC, instruction->label->combinedLabel()});
+      }
+    }
+  }
+  for (auto& [address, label] : analysis->ramLabels) {
+    labels.push_back({address, label});
+  }
+  sort(labels.begin(), labels.end());
+
+  // Multi-line comments are joined on a single line.
+  map<u24, string> comments;
+  for (auto& [pc, comment] : analysis->comments) {
+    auto text = comment;
+    replace(text.begin(), text.end(), '\n', ' ');
+    comments[pc] = text;
+  }
+
+  auto line = [](u24 address, const string& text) {
+    return format("%02X:%04X ", address >> 16, address & 0xFFFF) + text + "\n";
+  };
+  string text = "[labels]\n";
+  for (auto& [address, label] : labels) {
+    text += line(address, label);
+  }
+  text += "\n[comments]\n";
+  for (auto& [pc, comment] : comments) {
+    text += line(pc, comment);
+  }
+  return text;
+}
+
+// Return the lines of a data block.
+vector<LineItem> Disassembly::dataBlockItems(const DataBlock& block) const {
+  auto& rom = analysis->rom;
+  vector<LineItem> items{HeaderLine{HeaderKind::DataBlock}};
+
+  auto pc = block.start;
+  while (pc < block.end) {
+    DataLine line{pc, "db", "", ""};
+    auto separator = [&]() { return line.operands.empty() ? "" : ","; };
+
+    switch (block.type) {
+      case DataType::Bytes:
+        for (size_t i = 0; i < DATA_WIDTH && pc < block.end; i++, pc++) {
+          line.operands += format("%s$%02X", separator(), rom.readByte(pc));
+        }
+        break;
+
+      case DataType::Words:
+      case DataType::Pointers:
+        // A trailing odd byte is left as a byte.
+        if (pc + 1 == block.end) {
+          line.operands = format("$%02X", rom.readByte(pc++));
+          break;
+        }
+        line.directive = "dw";
+        for (size_t i = 0; i < DATA_WIDTH && pc + 1 < block.end; i++) {
+          auto word = rom.readWord(pc);
+          line.operands += format("%s$%04X", separator(), word);
+          pc += 2;
+
+          // Pointers go one per line, with the label they point to.
+          if (block.type == DataType::Pointers) {
+            auto label = analysis->getLabel((block.start & 0xFF0000) | word);
+            line.comment = label ? "-> " + label->combinedLabel() : "";
+            break;
+          }
+        }
+        break;
+
+      case DataType::Text:
+        // Printable characters are grouped into strings.
+        for (size_t i = 0; i < TEXT_WIDTH && pc < block.end; i++, pc++) {
+          auto c = rom.readByte(pc);
+          bool printable = c >= 0x20 && c <= 0x7E && c != '"';
+          bool inString = !line.operands.empty() && line.operands.back() == '"';
+          if (printable && inString) {
+            line.operands.insert(line.operands.size() - 1, 1, c);
+          } else if (printable) {
+            line.operands += separator() + string("\"") + (char)c + "\"";
+          } else {
+            line.operands += format("%s$%02X", separator(), c);
+          }
+        }
+        break;
+    }
+    items.push_back(line);
+  }
+
+  return items;
+}
+
+// Return the bytes spanned by a subroutine as a hex dump.
+string Disassembly::subroutineHexDump(SubroutinePC pc) const {
+  auto& instructions = analysis->subroutines.at(pc).instructions;
+  if (instructions.empty()) {
+    return "";
+  }
+
+  auto start = instructions.begin()->first;
+  auto last = instructions.rbegin()->second;
+  auto bytes = analysis->rom.read(start, last->pc + last->size() - start);
+  return formatHexDump(start, bytes);
+}
+
+// Return a region of the ROM as a hex dump, clamped to the end of the ROM,
+// showing one byte every `step`. Return nothing if the region is empty,
+// outside the ROM, or too large.
+optional<string> Disassembly::hexDump(u24 address,
+                                      size_t size,
+                                      size_t step) const {
+  if (size == 0 || size > MAX_HEX_DUMP_SIZE || step == 0 ||
+      !analysis->rom.isROM(address)) {
+    return nullopt;
+  }
+  return formatHexDump(address, analysis->rom.read(address, size), step);
+}
+
+// Format a sequence of bytes starting at a given address as a hex dump,
+// showing one byte every `step`.
+string Disassembly::formatHexDump(u24 start,
+                                  const vector<u8>& bytes,
+                                  size_t step) {
+  string text;
+  for (size_t i = 0, shown = 0; i < bytes.size(); i += step, shown++) {
+    if (shown % HEX_DUMP_WIDTH == 0) {
+      text += format("%s$%06X:", i == 0 ? "" : "\n", start + i);
+    }
+    text += format(" %02X", bytes[i]);
+  }
+  return text + "\n";
+}
+
+// Return the whole ROM as a source file that asar can reassemble.
+string Disassembly::rom() const {
+  auto& rom = analysis->rom;
+
+  // Instructions starting at each offset of the ROM, in a stable order.
+  map<u24, vector<Instruction*>> code;
+  for (auto& [pc, instructionSet] : analysis->instructions) {
+    for (auto& instruction : instructionSet) {
+      if (rom.isROM(pc)) {
+        code[rom.translate(pc)].push_back((Instruction*)&instruction);
+      }
+    }
+  }
+  for (auto& [offset, instructions] : code) {
+    sort(instructions.begin(), instructions.end(), [](auto a, auto b) {
+      return tuple(a->pc, a->state.p, a->state.e) <
+             tuple(b->pc, b->state.p, b->state.e);
+    });
+  }
+
+  // Data blocks, by the offset they start at.
+  map<u24, const DataBlock*> data;
+  for (auto& [start, block] : analysis->dataBlocks) {
+    data[rom.translate(start)] = &block;
+  }
+
+  string mapper;
+  switch (rom.romType) {
+    case ROMType::LoROM:
+    case ROMType::SDD1:
+      mapper = "lorom";
+      break;
+    case ROMType::HiROM:
+      mapper = "hirom";
+      break;
+    case ROMType::ExLoROM:
+      mapper = "exlorom";
+      break;
+    case ROMType::ExHiROM:
+      mapper = "exhirom";
+      break;
+    case ROMType::SA1:
+      mapper = "sa1rom";
+      break;
+  }
+  string text = "arch 65816\n" + mapper + "\n";
+
+  // Emit an `org` unless the address follows the previous byte in its bank.
+  optional<u24> last;
+  auto locate = [&](u24 address) {
+    if (!last.has_value() || address != *last + 1 ||
+        (address >> 16) != (*last >> 16)) {
+      text += format("\norg $%06X\n", address);
+    }
+  };
+  auto follows = [&](u24 offset) {
+    return last.has_value() && rom.isROM(*last + 1) &&
+           rom.translate(*last + 1) == offset &&
+           ((*last + 1) >> 16) == (*last >> 16);
+  };
+
+  auto decoding = [](const Instruction* instruction) {
+    auto operand = asarOperand(instruction);
+    return instruction->name() + (operand.empty() ? "" : " " + operand);
+  };
+
+  set<string> localLabels;
+  u24 offset = 0;
+  while (offset < rom.realSize()) {
+    if (auto block = data.find(offset); block != data.end()) {
+      auto& dataBlock = *block->second;
+      locate(dataBlock.start);
+      text += render(dataBlockItems(dataBlock));
+      last = dataBlock.end - 1;
+      offset += dataBlock.end - dataBlock.start;
+      continue;
+    }
+
+    auto search = code.find(offset);
+    if (search == code.end()) {
+      // Data: bytes not covered by any instruction.
+      string bytes;
+      size_t count = 0;
+      while (offset < rom.realSize() && count < HEX_DUMP_WIDTH &&
+             !code.count(offset) && !data.count(offset) &&
+             (count == 0 || follows(offset))) {
+        auto address = follows(offset) ? *last + 1 : rom.address(offset);
+        if (count == 0) {
+          locate(address);
+        }
+        bytes += format("%s$%02X", count == 0 ? "" : ",", rom.data[offset]);
+        last = address;
+        offset++;
+        count++;
+      }
+      text += "  db " + bytes + "\n";
+      continue;
+    }
+
+    auto& instructions = search->second;
+    auto instruction = instructions.front();
+    locate(instruction->pc);
+
+    if (auto subroutine = analysis->subroutines.find(instruction->pc);
+        subroutine != analysis->subroutines.end()) {
+      text += subroutine->second.label + ":\n";
+      localLabels.clear();
+    } else if (auto label = instruction->label) {
+      // Names repeated under the same subroutine label would clash.
+      if (localLabels.insert(label->localLabel).second) {
+        text += "." + label->localLabel + ":\n";
+      }
+    }
+
+    // The same bytes might have been decoded differently in other states.
+    set<string> alternatives{decoding(instruction)};
+    for (auto other : instructions) {
+      if (alternatives.insert(decoding(other)).second) {
+        text += format("  ; Warning: also decoded as `%s` (%s)\n",
+                       decoding(other).c_str(),
+                       stateDescription(other->state).c_str());
+      }
+    }
+
+    auto line = instructionItem(instruction);
+    line.operand = asarOperand(instruction);
+    if (analysis->settings.sizeSuffixes) {
+      line.mnemonic += sizeSuffix(instruction);
+    }
+    text += render(line) + "\n";
+    last = instruction->pc + instruction->size() - 1;
+
+    // Instructions starting inside this one can't be emitted.
+    auto end = offset + instruction->size();
+    for (offset++; offset < end; offset++) {
+      if (auto overlap = code.find(offset); overlap != code.end()) {
+        auto other = overlap->second.front();
+        text += format("  ; Warning: skipped `%s` at $%06X (overlaps)\n",
+                       decoding(other).c_str(), other->pc);
+      }
+    }
+  }
+
+  return text;
+}
+
+// Render a line of the disassembly as plain text.
+string Disassembly::render(const LineItem& item) {
+  if (auto labelLine = get_if<LabelLine>(&item)) {
+    auto& label = labelLine->label;
+    return label.localLabel.empty() ? format("%s:", label.c_str())
+                                    : format(".%s:", label.c_str());
+  }
+
+  if (auto instructionLine = get_if<InstructionLine>(&item)) {
+    auto& operand = instructionLine->operand;
+    auto padding = operand.size() < ARG_LEN ? ARG_LEN - operand.size() : 0;
+    auto code = "  " + instructionLine->mnemonic + " " + operand +
+                string(padding, ' ');
+
+    return code + renderComment(instructionLine->pc, instructionLine->comment,
+                                code.size());
+  }
+
+  if (auto headerLine = get_if<HeaderLine>(&item)) {
+    switch (headerLine->kind) {
+      case HeaderKind::JumpTablePreview:
+        return "  ; Jump table preview (unasserted):";
+      case HeaderKind::LocalLabels:
+        return "  ; Local labels:";
+      case HeaderKind::DataBlock:
+        return "  ; Data block:";
+    }
+  }
+
+  if (auto entryLine = get_if<JumpTableEntryLine>(&item)) {
+    auto label = entryLine->label;
+    return format("  ;   [$%02X] $%06X -> $%06X", entryLine->index,
+                  entryLine->address, entryLine->target) +
+           (label ? " " + label->combinedLabel() : "") +
+           (entryLine->plausible ? "" : " (outside ROM)");
+  }
+
+  if (auto dataLine = get_if<DataLine>(&item)) {
+    auto& operands = dataLine->operands;
+    auto padding = operands.size() < ARG_LEN ? ARG_LEN - operands.size() : 0;
+    auto code = "  " + dataLine->directive + string(OP_LEN - 2, ' ') + " " +
+                operands + string(padding, ' ');
+
+    return code +
+           renderComment(dataLine->pc, dataLine->comment, code.size());
+  }
+
+  if (auto entryLine = get_if<LocalLabelEntryLine>(&item)) {
+    return format("  ;   $%06X .%s", entryLine->pc,
+                  entryLine->label.localLabel.c_str());
+  }
+
+  return "";
+}
+
+// Render a sequence of lines as plain text.
+string Disassembly::render(const vector<LineItem>& items) {
+  string text;
+  for (auto& item : items) {
+    text += render(item) + "\n";
+  }
+  return text;
+}
+
+// Render the gutter and comment of a line starting at a given column.
+// Each line of a multi-line comment is rendered on its own line.
+string Disassembly::renderComment(u24 pc,
+                                  const string& comment,
+                                  size_t column) {
+  string text = format("; $%06X |", pc);
+  size_t start = 0;
+  while (true) {
+    auto end = comment.find('\n', start);
+    auto line = comment.substr(start, end - start);
+    text += line.empty() ? "" : " " + line;
+    if (end == string::npos) {
+      return text;
+    }
+    text += "\n" + string(column, ' ') + ";         |";
+    start = end + 1;
+  }
+}
+
+// Quote a string for DOT or JSON, escaping quotes and backslashes.
+string Disassembly::quote(const string& text) {
+  string quoted = "\"";
+  for (auto c : text) {
+    if (c == '"' || c == '\\') {
+      quoted += '\\';
+    }
+    quoted += c;
+  }
+  return quoted + "\"";
+}
+
+// Return the automatically generated comment of an instruction.
+string Disassembly::instructionComment(const Instruction* instruction) {
+  if (!instruction->comment().empty()) {
+    return instruction->comment();
+  }
+
+  if (instruction->isSepRep()) {
+    auto size = instruction->operation() == Op::SEP ? 8 : 16;
+    auto arg = *instruction->argument();
+
+    if ((arg & 0x30) == 0x30) {
+      return format("A: %d-bits, X: %d-bits", size, size);
+    } else if ((arg & 0x20) == 0x20) {
+      return format("A: %d-bits", size);
+    } else if ((arg & 0x10) == 0x10) {
+      return format("X: %d-bits", size);
+    }
+  }
+
+  auto op = instruction->operation();
+  if (op == Op::PEA || op == Op::PEI) {
+    auto comment = pushComment(instruction);
+    if (!comment.empty()) {
+      return comment;
+    }
+  }
+
+  // BRK and COP used as system calls.
+  if (auto analysis = instruction->analysis;
+      analysis != nullptr && (op == Op::BRK || op == Op::COP)) {
+    auto handler = analysis->interruptHandlers.find(op);
+    if (handler != analysis->interruptHandlers.end()) {
+      auto subroutine = analysis->subroutines.find(handler->second);
+      return "Handled by " + (subroutine != analysis->subroutines.end()
+                                  ? subroutine->second.label
+                                  : format("$%06X", handler->second));
+    }
+  }
+
+  if (auto subroutine = instruction->subroutine()) {
+    auto regionClear = subroutine->regionClears.find(instruction->pc);
+    if (regionClear != subroutine->regionClears.end()) {
+      return regionClearDescription(regionClear->second);
+    }
+    auto blockMove = subroutine->blockMoves.find(instruction->pc);
+    if (blockMove != subroutine->blockMoves.end()) {
+      auto [source, destination] = blockMove->second;
+      return format("Moves from bank $%02X to bank $%02X", source,
+                    destination);
+    }
+    auto fallThrough = subroutine->fallThroughs.find(instruction->pc);
+    if (fallThrough != subroutine->fallThroughs.end()) {
+      auto& target = instruction->analysis->subroutines.at(fallThrough->second);
+      return "Falls through to " + target.label;
+    }
+    auto returnJump = subroutine->returnJumps.find(instruction->pc);
+    if (returnJump != subroutine->returnJumps.end()) {
+      return returnJumpComment(instruction, returnJump->second);
+    }
+  }
+
+  auto comment = criticalSectionComment(instruction);
+  if (comment.empty()) {
+    comment = directPageComment(instruction);
+  }
+  if (comment.empty()) {
+    comment = dataBankComment(instruction);
+  }
+  return comment;
+}
+
+// Return the full address of an absolute access through a data bank other
+// than the bank of the code.
+string Disassembly::dataBankComment(const Instruction* instruction) {
+  if (!instruction->usesDataBank()) {
+    return "";
+  }
+  auto address = instruction->dataAddress();
+  if (!address.has_value() || (*address >> 16) == (instruction->pc >> 16)) {
+    return "";
+  }
+  return format("Data at $%06X", *address);
+}
+
+// Return a description of a return jumping to an address pushed on the
+// stack.
+string Disassembly::returnJumpComment(const Instruction* instruction,
+                                      InstructionPC target) {
+  if (auto label = instruction->analysis->getLabel(
+          target, instruction->subroutinePC)) {
+    return "Jumps to " + label->combinedLabel();
+  }
+  return format("Jumps to $%06X", target);
+}
+
+// Return a description of the value pushed by PEA or PEI.
+string Disassembly::pushComment(const Instruction* instruction) {
+  auto value = *instruction->argument();
+  if (instruction->operation() == Op::PEI) {
+    auto comment = format("Pushes the word at $%02X", value);
+    auto directPage = directPageComment(instruction);
+    return directPage.empty() ? comment : comment + " (" + directPage + ")";
+  }
+
+  auto hwRegister = HARDWARE_REGISTERS.find(value);
+  if (hwRegister != HARDWARE_REGISTERS.end()) {
+    return "Pushes !" + hwRegister->second;
+  }
+
+  auto analysis = instruction->analysis;
+  if (analysis != nullptr) {
+    auto address = (instruction->pc & 0xFF0000) | value;
+    if (auto label = analysis->getLabel(address)) {
+      return "Pushes " + label->combinedLabel();
+    }
+    // Return addresses are pushed minus one (e.g. to dispatch through RTS).
+    if (auto label = analysis->getLabel(address + 1)) {
+      return "Pushes " + label->combinedLabel() + "-1";
+    }
+  }
+  return "";
+}
+
+// Return a marker for direct page accesses before D is set.
+string Disassembly::directPageComment(const Instruction* instruction) {
+  auto subroutine = instruction->subroutine();
+  if (subroutine != nullptr &&
+      subroutine->directPageBeforeInit.count(instruction->pc)) {
+    return "D not set yet";
+  }
+  return "";
+}
+
+// Return a description of the critical section events at an instruction.
+string Disassembly::criticalSectionComment(const Instruction* instruction) {
+  auto subroutine = instruction->subroutine();
+  if (subroutine == nullptr) {
+    return "";
+  }
+
+  auto pc = instruction->pc;
+  vector<string> events;
+
+  for (auto& [start, end] : subroutine->criticalSections) {
+    if (start == pc) {
+      events.push_back("critical section begins");
+      break;
+    }
+  }
+  if (subroutine->nestedCriticalSections.count(pc)) {
+    events.push_back("nested critical section");
+  }
+  for (auto& [start, end] : subroutine->unclosedCriticalSections) {
+    if (start == pc) {
+      events.push_back("critical section not closed on some path");
+      break;
+    }
+  }
+  for (auto& [start, end] : subroutine->criticalSections) {
+    if (end == pc) {
+      events.push_back("critical section ends");
+      break;
+    }
+  }
+  for (auto& [start, end] : subroutine->unclosedCriticalSections) {
+    if (end == pc) {
+      events.push_back("returning with interrupts disabled");
+      break;
+    }
+  }
+  if (subroutine->redundantInterruptEnables.count(pc)) {
+    events.push_back("interrupts already enabled");
+  }
+
+  string comment;
+  for (auto& event : events) {
+    comment += (comment.empty() ? "" : ", ") + event;
+  }
+  return comment;
+}
+
+// Return a description of the size of A and X in a given state.
+string Disassembly::stateDescription(State state) {
+  return format("A: %d-bits, X: %d-bits%s", (int)state.sizeA() * 8,
+                (int)state.sizeX() * 8, state.e ? " (emulation mode)" : "");
+}
+
+// Return a description of a state change.
+string Disassembly::stateChangeDescription(StateChange stateChange) {
+  if (stateChange.unknown()) {
+    return "unknown";
+  } else if (stateChange.isEmpty()) {
+    return "no change";
+  }
+
+  string description;
+  if (auto m = stateChange.m) {
+    description += format("A: %d-bits", *m ? 8 : 16);
+  }
+  if (auto x = stateChange.x) {
+    description += (description.empty() ? "" : ", ");
+    description += format("X: %d-bits", *x ? 8 : 16);
+  }
+  return description;
+}
+
+// Return a description of what is known about the CPU, one item per line.
+string Disassembly::snapshotDescription(const CPUSnapshot& snapshot) {
+  auto value = [](optional<u16> v, size_t size) {
+    return v ? format(size == 1 ? "$%02X" : "$%04X", *v) : "unknown";
+  };
+
+  string description = stateDescription(snapshot.state) + "\n";
+  description += "A: " + value(snapshot.a, snapshot.state.sizeA()) + "\n";
+  description += "X: " + value(snapshot.x, snapshot.state.sizeX()) + "\n";
+  description += "Y: " + value(snapshot.y, snapshot.state.sizeX()) + "\n";
+  description += "DB: " + value(snapshot.dataBank, 1) + "\n";
+
+  description += "D: ";
+  switch (snapshot.directPage) {
+    case DirectPageStatus::NeverSet:
+      description += "not set yet ($0000)\n";
+      break;
+    case DirectPageStatus::Known:
+      description += "known\n";
+      break;
+    case DirectPageStatus::Unknown:
+      description += "unknown\n";
+      break;
+  }
+
+  description += "Interrupts: ";
+  if (auto disabled = snapshot.interruptsDisabled) {
+    description += *disabled ? "disabled" : "enabled";
+  } else {
+    description += "unknown";
+  }
+  return description;
+}
+
+// Return the line of an instruction.
+InstructionLine Disassembly::instructionItem(Instruction* instruction) const {
+  InstructionLine line;
+  line.instruction = instruction;
+  line.pc = instruction->pc;
+  line.mnemonic = instruction->name();
+  line.operand = instruction->argumentString();
+  line.operandLabel = instruction->argumentLabel();
+  line.comment = instructionComment(instruction);
+
+  auto stateChange = instruction->stateChange();
+  if (instruction->assertion().has_value()) {
+    line.status = InstructionStatus::AssertedStateChange;
+  } else if (stateChange.has_value() && stateChange->unknown()) {
+    line.status = InstructionStatus::UnknownStateChange;
+  } else if (auto jumpTable = instruction->jumpTable()) {
+    line.status = jumpTable->status == JumpTableStatus::Complete
+                      ? InstructionStatus::CompleteJumpTable
+                      : InstructionStatus::PartialJumpTable;
+  } else {
+    line.status = InstructionStatus::None;
+  }
+
+  return line;
+}
+
+// Return a preview of the unasserted jump table used by an instruction.
+vector<LineItem> Disassembly::jumpTablePreview(
+    const Instruction* instruction) const {
+  auto jumpTable = instruction->jumpTable();
+  if (instruction->addressMode() != AddressMode::AbsoluteIndexedIndirect ||
+      jumpTable == nullptr || jumpTable->status != JumpTableStatus::Unknown) {
+    return {};
+  }
+
+  // The table is in the same bank as the instruction.
+  auto& rom = analysis->rom;
+  auto bank = instruction->pc & 0xFF0000;
+  auto tableAddress = bank | *instruction->argument();
+  auto tableEnd = tableAddress + JUMP_TABLE_PREVIEW_SIZE * 2 - 1;
+  if (!rom.isROM(tableAddress) || !rom.isROM(tableEnd)) {
+    return {};
+  }
+
+  vector<LineItem> items = {HeaderLine{HeaderKind::JumpTablePreview}};
+  for (u16 index = 0; index < JUMP_TABLE_PREVIEW_SIZE * 2; index += 2) {
+    auto address = tableAddress + index;
+    auto target = bank | rom.readWord(address);
+
+    JumpTableEntryLine entry;
+    entry.index = index;
+    entry.address = address;
+    entry.target = target;
+    entry.label = analysis->getLabel(target);
+    entry.plausible = rom.isROM(target);
+    items.push_back(entry);
+  }
+  return items;
+}
+
+// Return an operand that asar assembles back into the same bytes.
+string Disassembly::asarOperand(const Instruction* instruction) {
+  // Branches are encoded relative to the target, which asar recomputes.
+  auto mode = instruction->addressMode();
+  if (mode == AddressMode::Relative || mode == AddressMode::RelativeLong) {
+    return format("$%06X", *instruction->absoluteArgument());
+  }
+  // Otherwise the number of digits determines the size of the operand.
+  return instruction->argumentString(false);
+}
+
+// Return the suffix forcing the size of an instruction's operand in asar
+// (`.b`, `.w` or `.l`), if it can be forced.
+string Disassembly::sizeSuffix(const Instruction* instruction) {
+  // Branches and block moves have a single encoding.
+  auto mode = instruction->addressMode();
+  if (mode == AddressMode::Relative || mode == AddressMode::RelativeLong ||
+      mode == AddressMode::Move) {
+    return "";
+  }
+  switch (instruction->argumentSize()) {
+    case 1:
+      return ".b";
+    case 2:
+      return ".w";
+    case 3:
+      return ".l";
+    default:
+      return "";
+  }
+}
+
+// Return a summary of the local labels of a subroutine.
+vector<LineItem> Disassembly::localLabelTable(
+    const Subroutine& subroutine) const {
+  vector<LineItem> items;
+  for (auto& [pc, instruction] : subroutine.instructions) {
+    if (auto label = instruction->label) {
+      items.push_back(LocalLabelEntryLine{*label, pc});
+    }
+  }
+
+  if (!items.empty()) {
+    items.insert(items.begin(), HeaderLine{HeaderKind::LocalLabels});
+  }
+  return items;
+}
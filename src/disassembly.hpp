@@ -0,0 +1,213 @@
+#pragma once
+
+#include <optional>
+#include <string>
+#include <variant>
+#include <vector>
+
+#include "cpu.hpp"
+#include "datablock.hpp"
+#include "instruction.hpp"
+#include "label.hpp"
+#include "types.hpp"
+
+class Analysis;
+struct Subroutine;
+
+// Status of an instruction, as highlighted in the disassembly. An unknown
+// state is shown this way, on the line of the instruction where it arises,
+// rather than as a block of lines of its own.
+enum class InstructionStatus {
+  None,
+  AssertedStateChange,
+  CompleteJumpTable,
+  PartialJumpTable,
+  UnknownStateChange,
+};
+
+// Line containing a subroutine or local label.
+struct LabelLine {
+  Label label;        // Subroutine or local label.
+  PCPair pc;          // Coordinates of the labeled address.
+  bool isEntryPoint;  // Whether the label is an entry point.
+};
+
+// Line containing an instruction.
+struct InstructionLine {
+  Instruction* instruction;           // Pointer to the instruction.
+  InstructionPC pc;                   // Instruction's address.
+  std::string mnemonic;               // Name of the operation.
+  std::string operand;                // Argument as a string.
+  std::optional<Label> operandLabel;  // Label the argument refers to, if any.
+  std::string comment;                // User-defined or automatic comment.
+  InstructionStatus status;           // Highlighting status.
+};
+
+// Kinds of header lines.
+enum class HeaderKind {
+  JumpTablePreview,
+  LocalLabels,
+  DataBlock,
+};
+
+// Line introducing a block of related lines.
+struct HeaderLine {
+  HeaderKind kind;  // Kind of header.
+};
+
+// Line previewing an entry of an unasserted jump table.
+struct JumpTableEntryLine {
+  u16 index;                   // Offset of the entry inside the table.
+  u24 address;                 // Address of the entry.
+  InstructionPC target;        // Address the entry would point to.
+  std::optional<Label> label;  // Label of the target, if analyzed.
+  bool plausible;              // Whether the target is inside the ROM.
+};
+
+// Line of a data block, as assembler directives.
+struct DataLine {
+  u24 pc;                 // Address of the first byte.
+  std::string directive;  // `db` or `dw`.
+  std::string operands;   // Values, separated by commas.
+  std::string comment;    // Automatic comment.
+};
+
+// Line summarizing a local label of a subroutine.
+struct LocalLabelEntryLine {
+  Label label;       // Local label.
+  InstructionPC pc;  // Labeled address.
+};
+
+// Empty line separating subroutines.
+struct BlankLine {};
+
+// Line of the disassembly, carrying plain data.
+typedef std::variant<LabelLine,
+                     InstructionLine,
+                     HeaderLine,
+                     JumpTableEntryLine,
+                     LocalLabelEntryLine,
+                     DataLine,
+                     BlankLine>
+    LineItem;
+
+/**
+ * Disassembly builder: turns the results of an analysis into lines.
+ */
+class Disassembly {
+ public:
+  // Constructor.
+  Disassembly(const Analysis* analysis);
+
+  // Return the lines of the disassembly of a subroutine.
+  std::vector<LineItem> subroutineItems(SubroutinePC pc) const;
+  // Return the label of a subroutine, or `unknown @ $XXXXXX` if it hasn't
+  // been analyzed.
+  std::string subroutineLabel(SubroutinePC pc) const;
+  // Return the lines of the disassembly of all the subroutines.
+  std::vector<LineItem> items() const;
+  // Return the disassembly of all the subroutines as plain text.
+  std::string all() const;
+
+  // Return the documentation of a subroutine as a Markdown section.
+  std::string subroutineMarkdown(SubroutinePC pc) const;
+  // Return the documentation of all the subroutines as a Markdown document.
+  std::string markdown() const;
+  // Return the call graph in Graphviz DOT format. Entry points are boxed,
+  // subroutines responsible for an unknown state are red, and calls through
+  // jump tables are dashed. Each call is labeled with its address.
+  std::string callGraph() const;
+  // Return the call graph in JSON format, as a list of nodes (subroutines)
+  // and a list of edges (calls).
+  std::string callGraphJSON() const;
+  // Return the labels and comments as a symbol file for the debuggers of
+  // bsnes-plus and Mesen: a line `BB:AAAA text` per address, in a
+  // `[labels]` and a `[comments]` section.
+  std::string symbols() const;
+
+  // Return the lines of a data block.
+  std::vector<LineItem> dataBlockItems(const DataBlock& block) const;
+
+  // Return the bytes spanned by a subroutine as a hex dump.
+  std::string subroutineHexDump(SubroutinePC pc) const;
+  // Return a region of the ROM as a hex dump, clamped to the end of the ROM,
+  // showing one byte every `step`. Return nothing if the region is empty,
+  // outside the ROM, or too large.
+  std::optional<std::string> hexDump(u24 address,
+                                     size_t size,
+                                     size_t step = 1) const;
+
+  // Return the whole ROM as a source file that asar can reassemble.
+  std::string rom() const;
+
+  // Render a line of the disassembly as plain text.
+  static std::string render(const LineItem& item);
+  // Render a sequence of lines as plain text.
+  static std::string render(const std::vector<LineItem>& items);
+  // Render the gutter and comment of a line starting at a given column.
+  // Each line of a multi-line comment is rendered on its own line.
+  static std::string renderComment(u24 pc,
+                                   const std::string& comment,
+                                   size_t column);
+
+  // Quote a string for DOT or JSON, escaping quotes and backslashes.
+  static std::string quote(const std::string& text);
+
+  // Return the automatically generated comment of an instruction.
+  static std::string instructionComment(const Instruction* instruction);
+  // Return a description of a return jumping to an address pushed on the
+  // stack.
+  static std::string returnJumpComment(const Instruction* instruction,
+                                       InstructionPC target);
+  // Return a description of the value pushed by PEA or PEI.
+  static std::string pushComment(const Instruction* instruction);
+  // Return a description of the critical section events at an instruction.
+  static std::string criticalSectionComment(const Instruction* instruction);
+  // Return a marker for direct page accesses before D is set.
+  static std::string directPageComment(const Instruction* instruction);
+  // Return the full address of an absolute access through a data bank other
+  // than the bank of the code.
+  static std::string dataBankComment(const Instruction* instruction);
+  // Return a description of the size of A and X in a given state.
+  static std::string stateDescription(State state);
+  // Return a description of a state change.
+  static std::string stateChangeDescription(StateChange stateChange);
+  // Return a description of what is known about the CPU, one item per line.
+  static std::string snapshotDescription(const CPUSnapshot& snapshot);
+
+  // Number of entries shown when previewing a jump table.
+  static const size_t JUMP_TABLE_PREVIEW_SIZE = 8;
+  // Number of bytes per line of a hex dump.
+  static const size_t HEX_DUMP_WIDTH = 16;
+  // Maximum number of bytes shown by a hex dump.
+  static const size_t MAX_HEX_DUMP_SIZE = 0x1000;
+  // Number of values per line of a data block.
+  static const size_t DATA_WIDTH = 8;
+  // Number of characters per line of a text data block.
+  static const size_t TEXT_WIDTH = 32;
+
+  static const size_t LINE_LEN = 30;
+  static const size_t OP_LEN = 3;
+  static const size_t ARG_LEN = LINE_LEN - OP_LEN - 1;
+
+ private:
+  // Return the line of an instruction.
+  InstructionLine instructionItem(Instruction* instruction) const;
+  // Return a preview of the unasserted jump table used by an instruction.
+  std::vector<LineItem> jumpTablePreview(const Instruction* instruction) const;
+  // Return a summary of the local labels of a subroutine.
+  std::vector<LineItem> localLabelTable(const Subroutine& subroutine) const;
+  // Return an operand that asar assembles back into the same bytes.
+  static std::string asarOperand(const Instruction* instruction);
+  // Return the suffix forcing the size of an instruction's operand in asar
+  // (`.b`, `.w` or `.l`), if it can be forced.
+  static std::string sizeSuffix(const Instruction* instruction);
+  // Format a sequence of bytes starting at a given address as a hex dump,
+  // showing one byte every `step`.
+  static std::string formatHexDump(u24 start,
+                                   const std::vector<u8>& bytes,
+                                   size_t step = 1);
+
+  // Pointer to the analysis.
+  const Analysis* analysis;
+};
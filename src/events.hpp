@@ -0,0 +1,44 @@
+#pragma once
+
+#include <functional>
+#include <string>
+#include <variant>
+
+#include "assertion.hpp"
+#include "state.hpp"
+#include "types.hpp"
+
+// A new subroutine has been found.
+struct SubroutineDiscovered {
+  SubroutinePC pc;    // Subroutine's PC.
+  std::string label;  // Subroutine's label.
+};
+
+// A new instruction has been analyzed.
+struct InstructionAdded {
+  InstructionPC pc;           // Instruction's address.
+  SubroutinePC subroutinePC;  // Subroutine to which the instruction belongs.
+};
+
+// The analysis of a subroutine has stopped at an unknown state change.
+struct UnknownStateChange {
+  SubroutinePC subroutinePC;  // Subroutine that has been stopped.
+  InstructionPC pc;           // Instruction causing the unknown state change.
+  UnknownReason reason;       // Why the state change is unknown.
+};
+
+// An assertion has been used in place of an unknown state change.
+struct AssertionConsumed {
+  AssertionType type;  // Type of the assertion.
+  InstructionPC pc;    // Instruction the assertion refers to.
+};
+
+// Discovery made during the analysis.
+typedef std::variant<SubroutineDiscovered,
+                     InstructionAdded,
+                     UnknownStateChange,
+                     AssertionConsumed>
+    AnalysisEvent;
+
+// Function receiving the events, in the order they happen.
+typedef std::function<void(const AnalysisEvent&)> EventSink;
@@ -0,0 +1,87 @@
+#include <QBoxLayout>
+#include <QDialogButtonBox>
+#include <QGroupBox>
+#include <QLabel>
+#include <QLineEdit>
+#include <QRadioButton>
+
+#include "adddatablockdialog.hpp"
+
+AddDataBlockDialog::AddDataBlockDialog(QWidget* parent) : QDialog(parent) {
+  setWindowTitle("Add Data Block");
+  setupLayout();
+  setFixedSize(sizeHint());
+}
+
+auto AddDataBlockDialog::createTextAreas() {
+  auto hbox = new QHBoxLayout;
+
+  auto startVbox = new QVBoxLayout;
+  auto startLabel = new QLabel("Start:", this);
+  startText = new QLineEdit(this);
+  startVbox->addWidget(startLabel);
+  startVbox->addWidget(startText);
+
+  auto endVbox = new QVBoxLayout;
+  auto endLabel = new QLabel("End (excluded):", this);
+  endText = new QLineEdit(this);
+  endVbox->addWidget(endLabel);
+  endVbox->addWidget(endText);
+
+  hbox->addLayout(startVbox);
+  hbox->addLayout(endVbox);
+
+  return hbox;
+}
+
+auto AddDataBlockDialog::createTypeGroup() {
+  auto groupBox = new QGroupBox("Type", this);
+  bytesType = new QRadioButton("Bytes", groupBox);
+  wordsType = new QRadioButton("Words", groupBox);
+  pointersType = new QRadioButton("Pointers", groupBox);
+  textType = new QRadioButton("Text", groupBox);
+
+  auto hbox = new QHBoxLayout(groupBox);
+  hbox->addWidget(bytesType);
+  hbox->addWidget(wordsType);
+  hbox->addWidget(pointersType);
+  hbox->addWidget(textType);
+
+  bytesType->setChecked(true);
+  return groupBox;
+}
+
+auto AddDataBlockDialog::createButtonBox() {
+  auto buttonBox = new QDialogButtonBox(
+      QDialogButtonBox::Ok | QDialogButtonBox::Cancel, this);
+
+  connect(buttonBox, &QDialogButtonBox::accepted, this,
+          &AddDataBlockDialog::accept);
+  connect(buttonBox, &QDialogButtonBox::rejected, this, &QDialog::reject);
+
+  return buttonBox;
+}
+
+void AddDataBlockDialog::setupLayout() {
+  auto vbox = new QVBoxLayout(this);
+  vbox->addLayout(createTextAreas());
+  vbox->addWidget(createTypeGroup());
+  vbox->addWidget(createButtonBox());
+}
+
+void AddDataBlockDialog::accept() {
+  start = startText->text().toInt(nullptr, 16);
+  end = endText->text().toInt(nullptr, 16);
+
+  if (wordsType->isChecked()) {
+    type = DataType::Words;
+  } else if (pointersType->isChecked()) {
+    type = DataType::Pointers;
+  } else if (textType->isChecked()) {
+    type = DataType::Text;
+  } else {
+    type = DataType::Bytes;
+  }
+
+  QDialog::accept();
+}
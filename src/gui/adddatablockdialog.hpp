@@ -0,0 +1,37 @@
+#pragma once
+
+#include <QDialog>
+
+#include "datablock.hpp"
+#include "types.hpp"
+
+class QLineEdit;
+class QRadioButton;
+
+class AddDataBlockDialog : public QDialog {
+  Q_OBJECT
+
+ public:
+  AddDataBlockDialog(QWidget* parent = nullptr);
+
+  u24 start;
+  u24 end;
+  DataType type;
+
+ private slots:
+  void accept();
+
+ private:
+  auto createTextAreas();
+  auto createTypeGroup();
+  auto createButtonBox();
+  void setupLayout();
+
+  QLineEdit* startText;
+  QLineEdit* endText;
+
+  QRadioButton* bytesType;
+  QRadioButton* wordsType;
+  QRadioButton* pointersType;
+  QRadioButton* textType;
+};
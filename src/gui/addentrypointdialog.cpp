@@ -34,6 +34,27 @@ auto AddEntryPointDialog::createTextAreas() {
   return hbox;
 }
 
+auto AddEntryPointDialog::createRegisterTextAreas() {
+  auto hbox = new QHBoxLayout;
+
+  auto dataBankVbox = new QVBoxLayout;
+  auto dataBankLabel = new QLabel("DB (optional):", this);
+  dataBankText = new QLineEdit(this);
+  dataBankVbox->addWidget(dataBankLabel);
+  dataBankVbox->addWidget(dataBankText);
+
+  auto directPageVbox = new QVBoxLayout;
+  auto directPageLabel = new QLabel("DP (optional):", this);
+  directPageText = new QLineEdit(this);
+  directPageVbox->addWidget(directPageLabel);
+  directPageVbox->addWidget(directPageText);
+
+  hbox->addLayout(dataBankVbox);
+  hbox->addLayout(directPageVbox);
+
+  return hbox;
+}
+
 auto AddEntryPointDialog::createRegisterStateGroup(QString reg) {
   QGroupBox** groupBox = (reg == "M") ? &mStateGroup : &xStateGroup;
   QRadioButton** zeroRadio = (reg == "M") ? &mStateZero : &xStateZero;
@@ -70,6 +91,7 @@ void AddEntryPointDialog::setupLayout() {
   hbox->addWidget(createRegisterStateGroup("M"));
   hbox->addWidget(createRegisterStateGroup("X"));
   vbox->addLayout(hbox);
+  vbox->addLayout(createRegisterTextAreas());
 
   vbox->addWidget(createButtonBox());
 }
@@ -79,5 +101,12 @@ void AddEntryPointDialog::accept() {
   pc = pcText->text().toInt(nullptr, 16);
   state = State(mStateOne->isChecked(), xStateOne->isChecked());
 
+  // Registers left empty are unknown.
+  bool ok;
+  auto dataBankValue = dataBankText->text().toUInt(&ok, 16);
+  dataBank = ok ? std::optional<u8>(dataBankValue) : std::nullopt;
+  auto directPageValue = directPageText->text().toUInt(&ok, 16);
+  directPage = ok ? std::optional<u16>(directPageValue) : std::nullopt;
+
   QDialog::accept();
 }
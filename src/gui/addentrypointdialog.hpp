@@ -1,6 +1,7 @@
 #pragma once
 
 #include <QDialog>
+#include <optional>
 #include <string>
 
 #include "state.hpp"
@@ -19,18 +20,23 @@ class AddEntryPointDialog : public QDialog {
   std::string label;
   SubroutinePC pc;
   State state;
+  std::optional<u8> dataBank;
+  std::optional<u16> directPage;
 
  private slots:
   void accept();
 
  private:
   auto createTextAreas();
+  auto createRegisterTextAreas();
   auto createRegisterStateGroup(QString reg);
   auto createButtonBox();
   void setupLayout();
 
   QLineEdit* labelText;
   QLineEdit* pcText;
+  QLineEdit* dataBankText;
+  QLineEdit* directPageText;
 
   QGroupBox* mStateGroup;
   QRadioButton* mStateZero;
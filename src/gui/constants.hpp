@@ -3,12 +3,14 @@
 #include <QColor>
 #include <QString>
 
+#include "theme.hpp"
+
 const QString APP_TITLE = "Gilgamesh";
 const QString APP_ICON_PATH = "resources/gilgamesh.png";
 const QString MONOSPACE_FONT = "Iosevka Fixed SS09 Extended";
 
-const QColor ASSERTION_COLOR = "mediumpurple";
-const QColor ENTRYPOINT_COLOR = "darkmagenta";
-const QColor JUMPTABLE_COLOR = "royalblue";
-const QColor PARTIAL_JUMPTABLE_COLOR = "gold";
-const QColor UNKNOWN_COLOR = "orangered";
+// Color of a role of the current theme, invalid if the role is not colored.
+inline QColor themeColor(std::string Theme::*role) {
+  auto& color = Theme::current().*role;
+  return color.empty() ? QColor() : QColor(QString::fromStdString(color));
+}
@@ -1,14 +1,19 @@
+#include <QApplication>
+#include <QClipboard>
 #include <QInputDialog>
 #include <QMenu>
+#include <QMessageBox>
 #include <QScrollBar>
 
 #include "gui/disassemblyview.hpp"
 
 #include "analysis.hpp"
+#include "disassembly.hpp"
 #include "gui/constants.hpp"
 #include "gui/editassertiondialog.hpp"
 #include "gui/editjumptabledialog.hpp"
 #include "gui/highlighter.hpp"
+#include "gui/listdialog.hpp"
 #include "gui/mainwindow.hpp"
 #include "instruction.hpp"
 #include "subroutine.hpp"
@@ -43,6 +48,9 @@ void DisassemblyView::reset() {
 }
 
 void DisassemblyView::renderAnalysis(Analysis* analysis) {
+  if (this->analysis != analysis) {
+    history.clear();
+  }
   this->analysis = analysis;
 
   reset();
@@ -60,11 +68,52 @@ void DisassemblyView::renderAnalysis(Analysis* analysis) {
 }
 
 void DisassemblyView::jumpToLabel(Label label) {
-  jumpToBlock(labelToBlock[label.combinedLabel().c_str()]);
+  auto combinedLabel = QString::fromStdString(label.combinedLabel());
+  auto pc = labelToPC.find(combinedLabel);
+  if (pc == labelToPC.end()) {
+    return;
+  }
+  history.visit(cursorPC(), *pc);
+  jumpToBlock(labelToBlock[combinedLabel]);
+}
+
+void DisassemblyView::goToPC(PCPair pc) {
+  history.visit(cursorPC(), pc);
+  jumpToPC(pc);
+}
+
+void DisassemblyView::goBack() {
+  if (auto pc = history.back()) {
+    jumpToPC(*pc);
+  }
+}
+
+void DisassemblyView::goForward() {
+  if (auto pc = history.forward()) {
+    jumpToPC(*pc);
+  }
 }
 
 void DisassemblyView::jumpToPC(PCPair pc, int verticalOffset) {
-  jumpToBlock(pcToBlock[pc], verticalOffset);
+  // The instruction might be gone after the analysis was run again.
+  auto block = pcToBlock.find(pc);
+  if (block != pcToBlock.end()) {
+    jumpToBlock(*block, verticalOffset);
+  }
+}
+
+optional<PCPair> DisassemblyView::cursorPC() const {
+  auto block = textCursor().blockNumber();
+  if (auto instruction = blockToInstruction.value(block)) {
+    return instruction->pcPair();
+  }
+  if (blockToLabel.contains(block)) {
+    auto label = QString::fromStdString(blockToLabel[block].combinedLabel());
+    if (labelToPC.contains(label)) {
+      return labelToPC[label];
+    }
+  }
+  return lastClickedPC;
 }
 
 void DisassemblyView::jumpToBlock(int block, int verticalOffset) {
@@ -113,93 +162,77 @@ void DisassemblyView::setBlockState(BlockState state) {
 }
 
 void DisassemblyView::renderSubroutine(const Subroutine& subroutine) {
-  auto label = subroutine.label;
-  append(qformat("%s:", label.c_str()));
+  Disassembly disassembly(analysis);
+  for (auto& item : disassembly.subroutineItems(subroutine.pc)) {
+    if (auto labelLine = get_if<LabelLine>(&item)) {
+      renderLabel(*labelLine);
+    } else if (auto instructionLine = get_if<InstructionLine>(&item)) {
+      renderInstruction(*instructionLine);
+    } else {
+      append(QString::fromStdString(Disassembly::render(item)));
+    }
+  }
+}
+
+void DisassemblyView::renderLabel(const LabelLine& line) {
+  append(QString::fromStdString(Disassembly::render(line)));
 
   auto block = textCursor().blockNumber();
-  blockToLabel[block] = label;
-  labelToBlock[label.c_str()] = block;
-  labelToPC[label.c_str()] = {subroutine.pc, subroutine.pc};
+  auto combinedLabel = QString::fromStdString(line.label.combinedLabel());
+  blockToLabel[block] = line.label;
+  labelToBlock[combinedLabel] = block;
+  labelToPC[combinedLabel] = line.pc;
 
-  if (subroutine.isEntryPoint) {
+  if (line.isEntryPoint) {
     setBlockState(BlockState::EntryPointLabel);
   }
-
-  for (auto& [pc, instruction] : subroutine.instructions) {
-    renderInstruction(instruction);
-  }
-  append("");
 }
 
-void DisassemblyView::renderInstruction(Instruction* instruction) {
-  PCPair pc = {instruction->pc, instruction->subroutinePC};
-  if (auto label = instruction->label) {
-    append(qformat(".%s:", label->c_str()));
-    auto block = textCursor().blockNumber();
-    auto combinedLabel = QString::fromStdString(label->combinedLabel());
-    blockToLabel[block] = combinedLabel;
-    labelToBlock[combinedLabel] = block;
-    labelToPC[combinedLabel] = pc;
-  }
-
+void DisassemblyView::renderInstruction(const InstructionLine& line) {
   // Instruction name.
   auto cursor = textCursor();
   auto format = defaultFormat;
   cursor.insertText("\n  ");
-  cursor.insertText((instruction->name() + " ").c_str());
+  cursor.insertText((line.mnemonic + " ").c_str());
 
   // Instruction argument.
-  if (auto argumentLabel = instruction->argumentLabel()) {
+  if (auto operandLabel = line.operandLabel) {
     format.setAnchor(true);
-    format.setAnchorHref(argumentLabel->combinedLabel().c_str());
+    format.setAnchorHref(operandLabel->combinedLabel().c_str());
   }
-  QString argument = instruction->argumentString().c_str();
+  QString argument = line.operand.c_str();
   cursor.insertText(argument, format);
 
   // Instruction comment.
   format = defaultFormat;
-  cursor.insertText(QString(ARG_LEN - argument.size(), ' '), format);
-  cursor.insertText(qformat("; $%06X |%s", instruction->pc,
-                            instructionComment(instruction).c_str()),
-                    format);
-
-  auto instructionStateChange = instruction->stateChange();
-  if (instruction->assertion().has_value()) {
-    setBlockState(BlockState::AssertedStateChange);
-  } else if (instructionStateChange.has_value() &&
-             instructionStateChange->unknown()) {
-    setBlockState(BlockState::UnknownStateChange);
-  } else if (auto jumpTable = instruction->jumpTable()) {
-    if (jumpTable->status == JumpTableStatus::Complete) {
+  int padding = max((int)Disassembly::ARG_LEN - (int)argument.size(), 0);
+  cursor.insertText(QString(padding, ' '), format);
+  auto column = 2 + line.mnemonic.size() + 1 + argument.size() + padding;
+  auto comment = QString::fromStdString(
+      Disassembly::renderComment(line.pc, line.comment, column));
+  // Keep continuation lines in the instruction's block.
+  cursor.insertText(comment.replace('\n', QChar::LineSeparator), format);
+
+  switch (line.status) {
+    case InstructionStatus::AssertedStateChange:
+      setBlockState(BlockState::AssertedStateChange);
+      break;
+    case InstructionStatus::CompleteJumpTable:
       setBlockState(BlockState::CompleteJumpTable);
-    } else {
+      break;
+    case InstructionStatus::PartialJumpTable:
       setBlockState(BlockState::PartialJumpTable);
-    }
+      break;
+    case InstructionStatus::UnknownStateChange:
+      setBlockState(BlockState::UnknownStateChange);
+      break;
+    default:
+      break;
   }
 
   auto block = textCursor().blockNumber();
-  blockToInstruction[block] = instruction;
-  pcToBlock[pc] = block;
-}
-
-string DisassemblyView::instructionComment(const Instruction* instruction) {
-  if (!instruction->comment().empty()) {
-    return " " + instruction->comment();
-  }
-
-  if (instruction->isSepRep()) {
-    auto size = instruction->operation() == Op::SEP ? 8 : 16;
-    auto arg = *instruction->argument();
-
-    if ((arg & 0x30) == 0x30) {
-      return format(" A: %d-bits, X: %d-bits", size, size);
-    } else if ((arg & 0x20) == 0x20) {
-      return format(" A: %d-bits", size);
-    } else if ((arg & 0x10) == 0x10) {
-      return format(" X: %d-bits", size);
-    }
-  }
-  return "";
+  blockToInstruction[block] = line.instruction;
+  pcToBlock[line.instruction->pcPair()] = block;
 }
 
 void DisassemblyView::contextMenuEvent(QContextMenuEvent* e) {
@@ -221,6 +254,30 @@ void DisassemblyView::contextMenuEvent(QContextMenuEvent* e) {
     connect(editComment, &QAction::triggered, this,
             [=]() { this->editCommentDialog(instruction); });
 
+    auto copyBytes = menu->addAction("Copy Subroutine Bytes");
+    connect(copyBytes, &QAction::triggered, this, [=]() {
+      auto dump = Disassembly(analysis).subroutineHexDump(
+          instruction->subroutinePC);
+      QApplication::clipboard()->setText(QString::fromStdString(dump));
+    });
+
+    auto showState = menu->addAction("Show CPU State...");
+    connect(showState, &QAction::triggered, this,
+            [=]() { this->showStateDialog(instruction); });
+
+    if (!analysis->followTargets(instruction->pc, instruction->subroutinePC)
+             .empty()) {
+      auto follow = menu->addAction("Follow");
+      connect(follow, &QAction::triggered, this,
+              [=]() { this->followInstruction(instruction); });
+    }
+
+    if (!analysis->referencesTo(instruction->pc).empty()) {
+      auto references = menu->addAction("References...");
+      connect(references, &QAction::triggered, this,
+              [=]() { this->referencesDialog(instruction->pc); });
+    }
+
     if (instruction->isControl() &&
         !instruction->absoluteArgument().has_value()) {
       auto editJumpTable = menu->addAction("Edit Jump Table...");
@@ -275,11 +332,11 @@ void DisassemblyView::editCommentDialog(Instruction* instruction) {
   auto comment = QString::fromStdString(instruction->comment());
 
   bool ok;
-  QString newComment = QInputDialog::getText(
-      this, "Edit Comment", "Comment:", QLineEdit::Normal, comment, &ok);
+  QString newComment = QInputDialog::getMultiLineText(
+      this, "Edit Comment", "Comment:", comment, &ok);
 
   if (ok) {
-    instruction->setComment(newComment.toStdString());
+    instruction->setComment(newComment.trimmed().toStdString());
     mainWindow()->runAnalysis();
   }
 }
@@ -300,20 +357,91 @@ void DisassemblyView::editJumpTableDialog(Instruction* instruction) {
   }
 }
 
+void DisassemblyView::showStateDialog(Instruction* instruction) {
+  auto snapshot =
+      analysis->cpuSnapshot(instruction->pc, instruction->subroutinePC);
+  if (!snapshot.has_value()) {
+    QMessageBox::warning(this, "CPU State",
+                         "The analysis doesn't reach this instruction.");
+    return;
+  }
+
+  auto description = Disassembly::snapshotDescription(*snapshot);
+  QMessageBox::information(this, qformat("CPU State at $%06X", instruction->pc),
+                           QString::fromStdString(description));
+}
+
 void DisassemblyView::editLabelDialog(Label label) {
   bool ok;
   QString newLabel = QInputDialog::getText(
       this, "Edit Label", "Label:", QLineEdit::Normal, label, &ok);
 
   if (ok && !newLabel.isEmpty()) {
-    auto& [pc, subroutinePC] = labelToPC[label.combinedLabel().c_str()];
-    analysis->renameLabel(newLabel.toStdString(), pc, subroutinePC);
+    auto pc = labelToPC.find(label.combinedLabel().c_str());
+    if (pc == labelToPC.end() ||
+        !analysis->renameLabel(newLabel.toStdString(), pc->first,
+                               pc->second)) {
+      QMessageBox::warning(this, "Edit Label",
+                           "The label is invalid, or its code is gone.");
+      return;
+    }
     mainWindow()->runAnalysis();
   }
 }
 
+void DisassemblyView::followInstruction(Instruction* instruction) {
+  auto targets =
+      analysis->followTargets(instruction->pc, instruction->subroutinePC);
+  if (targets.size() == 1) {
+    return goToPC(targets[0]);
+  }
+
+  ListDialog dialog("Follow", {"Target", "Label"}, this);
+  for (auto& [pc, subroutinePC] : targets) {
+    auto label = analysis->getLabel(pc, subroutinePC);
+    dialog.addRow({pc, subroutinePC},
+                  {qformat("$%06X", pc),
+                   label ? QString::fromStdString(label->combinedLabel())
+                         : QString()});
+  }
+  dialog.onActivated = [&](PCPair pc) {
+    goToPC(pc);
+    dialog.accept();
+  };
+  dialog.exec();
+}
+
+void DisassemblyView::referencesDialog(InstructionPC target) {
+  auto references = analysis->referencesTo(target);
+  if (references.empty()) {
+    QMessageBox::information(this, "References",
+                             qformat("No references to $%06X.", target));
+    return;
+  }
+
+  ListDialog dialog("References", {"Source", "Subroutine", "Instruction"},
+                    this);
+  for (auto& [pc, subroutinePC] : references) {
+    auto& subroutine = analysis->subroutines.at(subroutinePC);
+    auto source = subroutine.instructions.at(pc);
+    dialog.addRow({pc, subroutinePC},
+                  {qformat("$%06X", pc),
+                   QString::fromStdString(subroutine.label),
+                   QString::fromStdString(source->name() + " " +
+                                          source->argumentString())});
+  }
+  dialog.onActivated = [&](PCPair pc) {
+    goToPC(pc);
+    dialog.accept();
+  };
+  dialog.exec();
+}
+
 void DisassemblyView::highlightCurrentLine() {
-  QColor lineColor = QColor(Qt::yellow).lighter(160);
+  auto lineColor = themeColor(&Theme::currentLine);
+  if (!lineColor.isValid()) {
+    return setExtraSelections({});
+  }
 
   // Reuse background color if there's one.
   auto formats = textCursor().block().layout()->formats();
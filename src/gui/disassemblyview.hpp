@@ -4,6 +4,7 @@
 #include <QTextEdit>
 #include <optional>
 
+#include "history.hpp"
 #include "instruction.hpp"
 #include "label.hpp"
 #include "types.hpp"
@@ -11,6 +12,8 @@
 class Analysis;
 class Highlighter;
 class Instruction;
+struct InstructionLine;
+struct LabelLine;
 class MainWindow;
 class Subroutine;
 
@@ -32,6 +35,10 @@ class DisassemblyView : public QTextEdit {
  public slots:
   void renderAnalysis(Analysis* analysis);
   void jumpToLabel(Label label);
+  void goBack();
+  void goForward();
+  void goToPC(PCPair pc);
+  void referencesDialog(InstructionPC target);
 
  private:
   MainWindow* mainWindow();
@@ -42,10 +49,11 @@ class DisassemblyView : public QTextEdit {
   std::optional<Label> getLabelFromPos(const QPoint pos) const;
   void jumpToBlock(int block, int verticalOffset = 0);
   void jumpToPC(PCPair pc, int verticalOffset = 0);
+  std::optional<PCPair> cursorPC() const;
 
   void renderSubroutine(const Subroutine& subroutine);
-  void renderInstruction(Instruction* instruction);
-  std::string instructionComment(const Instruction* instruction);
+  void renderLabel(const LabelLine& line);
+  void renderInstruction(const InstructionLine& line);
 
   void contextMenuEvent(QContextMenuEvent* e) override;
   void mouseMoveEvent(QMouseEvent* e) override;
@@ -54,7 +62,9 @@ class DisassemblyView : public QTextEdit {
   void editAssertionDialog(Instruction* instruction);
   void editCommentDialog(Instruction* instruction);
   void editJumpTableDialog(Instruction* instruction);
+  void showStateDialog(Instruction* instruction);
   void editLabelDialog(Label label);
+  void followInstruction(Instruction* instruction);
 
   void highlightCurrentLine();
 
@@ -72,7 +82,5 @@ class DisassemblyView : public QTextEdit {
   int lastClickedVerticalOffset;
   std::optional<PCPair> lastClickedPC;
 
-  static const size_t LINE_LEN = 30;
-  static const size_t OP_LEN = 3;
-  static const size_t ARG_LEN = LINE_LEN - OP_LEN - 1;
+  NavigationHistory history;
 };
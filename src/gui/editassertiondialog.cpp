@@ -22,6 +22,7 @@ auto EditAssertionDialog::createRadioButtons() {
   hbox->addWidget(createAssertionTypeGroup());
   hbox->addWidget(createRegisterAssertionGroup("M"));
   hbox->addWidget(createRegisterAssertionGroup("X"));
+  hbox->addWidget(createConfidenceGroup());
   return hbox;
 }
 
@@ -77,6 +78,21 @@ QGroupBox* EditAssertionDialog::createRegisterAssertionGroup(QString reg) {
   return *groupBox;
 }
 
+QGroupBox* EditAssertionDialog::createConfidenceGroup() {
+  confidenceGroup = new QGroupBox("Confidence", this);
+
+  confidenceVerified = createRadioButton("Verified", confidenceGroup);
+  confidenceProbable = createRadioButton("Probable", confidenceGroup);
+  confidenceGuess = createRadioButton("Guess", confidenceGroup);
+
+  auto vbox = new QVBoxLayout(confidenceGroup);
+  vbox->addWidget(confidenceVerified);
+  vbox->addWidget(confidenceProbable);
+  vbox->addWidget(confidenceGuess);
+
+  return confidenceGroup;
+}
+
 void EditAssertionDialog::applyToAssertion() {
   if (assertionTypeNone->isChecked()) {
     assertion = nullopt;
@@ -102,6 +118,14 @@ void EditAssertionDialog::applyToAssertion() {
   } else {
     assertion->stateChange.x = nullopt;
   }
+
+  if (confidenceVerified->isChecked()) {
+    assertion->confidence = AssertionConfidence::Verified;
+  } else if (confidenceGuess->isChecked()) {
+    assertion->confidence = AssertionConfidence::Guess;
+  } else {
+    assertion->confidence = AssertionConfidence::Probable;
+  }
 }
 
 void EditAssertionDialog::restoreFromAssertion() {
@@ -132,6 +156,17 @@ void EditAssertionDialog::restoreFromAssertion() {
     xAssertionZero->setChecked(true);
   }
 
+  auto confidence = assertion.has_value() ? assertion->confidence
+                                          : AssertionConfidence::Probable;
+
+  if (confidence == AssertionConfidence::Verified) {
+    confidenceVerified->setChecked(true);
+  } else if (confidence == AssertionConfidence::Guess) {
+    confidenceGuess->setChecked(true);
+  } else {
+    confidenceProbable->setChecked(true);
+  }
+
   applyDisabledState();
 }
 
@@ -139,6 +174,7 @@ void EditAssertionDialog::applyDisabledState() {
   bool disabled = assertionTypeNone->isChecked();
   mAssertionGroup->setDisabled(disabled);
   xAssertionGroup->setDisabled(disabled);
+  confidenceGroup->setDisabled(disabled);
 }
 
 QRadioButton* EditAssertionDialog::createRadioButton(QString title,
@@ -31,6 +31,7 @@ class EditAssertionDialog : public QDialog {
 
   QGroupBox* createAssertionTypeGroup();
   QGroupBox* createRegisterAssertionGroup(QString reg);
+  QGroupBox* createConfidenceGroup();
   QRadioButton* createRadioButton(QString title, QGroupBox* group);
 
   QGroupBox* assertionTypeGroup;
@@ -47,4 +48,9 @@ class EditAssertionDialog : public QDialog {
   QRadioButton* xAssertionNone;
   QRadioButton* xAssertionZero;
   QRadioButton* xAssertionOne;
+
+  QGroupBox* confidenceGroup;
+  QRadioButton* confidenceVerified;
+  QRadioButton* confidenceProbable;
+  QRadioButton* confidenceGuess;
 };
@@ -3,10 +3,11 @@
 #include <QDialogButtonBox>
 #include <QLabel>
 #include <QLineEdit>
-#include <QValidator>
+#include <QMessageBox>
 
 #include "analysis.hpp"
 #include "editjumptabledialog.hpp"
+#include "utils.hpp"
 
 using namespace std;
 
@@ -21,28 +22,15 @@ EditJumpTableDialog::EditJumpTableDialog(const JumpTable* jumpTable,
 }
 
 auto EditJumpTableDialog::createTextAreas() {
-  auto hbox = new QHBoxLayout;
+  auto vbox = new QVBoxLayout;
 
-  auto startVbox = new QVBoxLayout;
-  auto startLabel = new QLabel("Start:", this);
-  startText = new QLineEdit(this);
-  startVbox->addWidget(startLabel);
-  startVbox->addWidget(startText);
+  // Offsets in hexadecimal: `X`, `X..Y` (Y excluded) or `X..=Y` (Y included).
+  auto rangeLabel = new QLabel("Range (e.g. 0..=6, empty to clear):", this);
+  rangeText = new QLineEdit(this);
+  vbox->addWidget(rangeLabel);
+  vbox->addWidget(rangeText);
 
-  auto endVbox = new QVBoxLayout;
-  auto endLabel = new QLabel("End:", this);
-  endText = new QLineEdit(this);
-  endVbox->addWidget(endLabel);
-  endVbox->addWidget(endText);
-
-  auto validator = new QIntValidator(0x0000, 0xFFFF, this);
-  startText->setValidator(validator);
-  endText->setValidator(validator);
-
-  hbox->addLayout(startVbox);
-  hbox->addLayout(endVbox);
-
-  return hbox;
+  return vbox;
 }
 
 auto EditJumpTableDialog::createButtonBox() {
@@ -71,25 +59,25 @@ void EditJumpTableDialog::setupLayout() {
 void EditJumpTableDialog::restoreFromJumpTable(const JumpTable* jumpTable) {
   range = jumpTable->range();
   if (range.has_value()) {
-    startText->setText(QString::number(range->first));
-    endText->setText(QString::number(range->second));
+    rangeText->setText(qformat("%X..=%X", range->first, range->second));
   }
 
   completeCheckBox->setChecked(jumpTable->status == JumpTableStatus::Complete);
 }
 
 void EditJumpTableDialog::accept() {
-  bool ok = true;
-  auto start = startText->text().toInt(&ok);
-  auto end = endText->text().toInt(&ok);
-
-  if (ok) {
-    range = {start, end};
-    status = completeCheckBox->isChecked() ? JumpTableStatus::Complete
-                                           : JumpTableStatus::Partial;
-  } else {
+  auto text = rangeText->text().trimmed();
+  if (text.isEmpty()) {
     range = nullopt;
+    return QDialog::accept();
   }
 
+  range = JumpTable::parseRange(text.toStdString());
+  if (!range.has_value()) {
+    QMessageBox::warning(this, "Edit Jump Table", "Invalid range.");
+    return;
+  }
+  status = completeCheckBox->isChecked() ? JumpTableStatus::Complete
+                                         : JumpTableStatus::Partial;
   QDialog::accept();
 }
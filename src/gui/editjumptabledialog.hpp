@@ -29,7 +29,6 @@ class EditJumpTableDialog : public QDialog {
   auto createCheckBox();
   void setupLayout();
 
-  QLineEdit* startText;
-  QLineEdit* endText;
+  QLineEdit* rangeText;
   QCheckBox* completeCheckBox;
 };
@@ -11,31 +11,46 @@ Highlighter::Highlighter(QTextDocument* parent) : QSyntaxHighlighter(parent) {
   setupPatterns();
 }
 
+// Color the text of a format, if the theme has a color for the role.
+static void setForeground(QTextCharFormat& format, std::string Theme::*role) {
+  auto color = themeColor(role);
+  if (color.isValid()) {
+    format.setForeground(color);
+  }
+}
+
+// Color the background of a format, if the theme has a color for the role.
+static void setBackground(QTextCharFormat& format, std::string Theme::*role) {
+  auto color = themeColor(role);
+  if (color.isValid()) {
+    format.setBackground(color);
+    setForeground(format, &Theme::highlightedText);
+  }
+}
+
 void Highlighter::setupFormats() {
-  argumentAliasFormat.setForeground(Qt::darkRed);
+  setForeground(argumentAliasFormat, &Theme::label);
 
-  assertedStateChangeFormat.setBackground(ASSERTION_COLOR);
-  assertedStateChangeFormat.setForeground(Qt::white);
+  setBackground(assertedStateChangeFormat, &Theme::assertion);
 
-  commentFormat.setForeground(Qt::gray);
+  setForeground(commentFormat, &Theme::comment);
 
-  completeJumpTableFormat.setBackground(JUMPTABLE_COLOR);
-  completeJumpTableFormat.setForeground(Qt::white);
+  setBackground(completeJumpTableFormat, &Theme::jumpTable);
 
-  entryPointFormat.setForeground(ENTRYPOINT_COLOR);
+  setForeground(entryPointFormat, &Theme::entryPoint);
   entryPointFormat.setFontWeight(QFont::Bold);
 
-  labelFormat.setForeground(Qt::darkRed);
+  setForeground(labelFormat, &Theme::label);
   labelFormat.setFontWeight(QFont::Bold);
 
-  localLabelFormat.setForeground(Qt::darkRed);
+  setForeground(localLabelFormat, &Theme::label);
 
-  opcodeFormat.setForeground(Qt::blue);
+  setForeground(opcodeFormat, &Theme::opcode);
 
-  partialJumpTableFormat.setBackground(PARTIAL_JUMPTABLE_COLOR);
+  setBackground(partialJumpTableFormat, &Theme::partialJumpTable);
+  partialJumpTableFormat.clearForeground();
 
-  unknownStateChangeFormat.setBackground(UNKNOWN_COLOR);
-  unknownStateChangeFormat.setForeground(Qt::white);
+  setBackground(unknownStateChangeFormat, &Theme::unknown);
 }
 
 void Highlighter::setupPatterns() {
@@ -0,0 +1,45 @@
+#include <QBoxLayout>
+#include <QDialogButtonBox>
+#include <QTreeWidget>
+
+#include "gui/listdialog.hpp"
+
+#include "gui/constants.hpp"
+
+ListDialog::ListDialog(QString title, QStringList headers, QWidget* parent)
+    : QDialog(parent) {
+  setWindowTitle(title);
+  setupLayout(headers);
+  resize(700, 400);
+}
+
+void ListDialog::setupLayout(QStringList headers) {
+  tree = new QTreeWidget(this);
+  tree->setFont(QFont(MONOSPACE_FONT));
+  tree->setHeaderLabels(headers);
+  tree->setRootIsDecorated(false);
+
+  connect(tree, &QTreeWidget::itemDoubleClicked, this,
+          [this](QTreeWidgetItem* item, int) {
+            if (onActivated) {
+              onActivated(rowToPC[tree->indexOfTopLevelItem(item)]);
+            }
+          });
+
+  auto buttonBox = new QDialogButtonBox(QDialogButtonBox::Close, this);
+  connect(buttonBox, &QDialogButtonBox::rejected, this, &QDialog::reject);
+
+  auto vbox = new QVBoxLayout(this);
+  vbox->addWidget(tree);
+  vbox->addWidget(buttonBox);
+}
+
+void ListDialog::addRow(PCPair pc, QStringList columns, QColor color) {
+  auto item = new QTreeWidgetItem(tree, columns);
+  if (color.isValid()) {
+    for (int i = 0; i < columns.size(); i++) {
+      item->setForeground(i, color);
+    }
+  }
+  rowToPC.push_back(pc);
+}
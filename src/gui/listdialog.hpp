@@ -0,0 +1,28 @@
+#pragma once
+
+#include <QColor>
+#include <QDialog>
+#include <QStringList>
+#include <functional>
+#include <vector>
+
+#include "instruction.hpp"
+
+class QTreeWidget;
+
+class ListDialog : public QDialog {
+  Q_OBJECT
+
+ public:
+  ListDialog(QString title, QStringList headers, QWidget* parent = nullptr);
+
+  void addRow(PCPair pc, QStringList columns, QColor color = QColor());
+
+  std::function<void(PCPair)> onActivated;
+
+ private:
+  void setupLayout(QStringList headers);
+
+  QTreeWidget* tree;
+  std::vector<PCPair> rowToPC;
+};
@@ -1,12 +1,94 @@
 #include <QApplication>
+#include <QCommandLineParser>
+#include <fstream>
+#include <iostream>
 
+#include "analysis.hpp"
+#include "batch.hpp"
+#include "commandline.hpp"
 #include "gui/mainwindow.hpp"
+#include "theme.hpp"
+
+// Create the application, without a GUI in batch and dump modes.
+QCoreApplication* createApplication(int& argc, char* argv[]) {
+  for (int i = 1; i < argc; i++) {
+    if (!qstrcmp(argv[i], "--batch") || !qstrcmp(argv[i], "--dump")) {
+      return new QCoreApplication(argc, argv);
+    }
+  }
+  return new QApplication(argc, argv);
+}
+
+// Analyze a ROM running the commands of a script, without user interaction.
+int runBatch(const QString& romPath,
+             const QString& scriptPath,
+             const Settings& settings) {
+  std::ifstream script(scriptPath.toStdString());
+  if (!script) {
+    std::cerr << "Could not open " << scriptPath.toStdString() << std::endl;
+    return 1;
+  }
+
+  Analysis analysis(romPath.toStdString());
+  loadAnalysis(analysis, settings, std::cerr);
+  return Batch(&analysis, std::cout, std::cerr).run(script) ? 0 : 1;
+}
 
 int main(int argc, char* argv[]) {
-  QApplication app(argc, argv);
+  QScopedPointer<QCoreApplication> app(createApplication(argc, argv));
+
+  QCommandLineParser parser;
+  parser.addHelpOption();
+  parser.addOption({"profile", "Apply a settings profile.", "name"});
+  parser.addOption(
+      {"theme", "Color theme (default, mono or colorblind).", "name"});
+  parser.addOption(
+      {"batch", "Run the commands of a script, without a GUI.", "script"});
+  parser.addOption(
+      {"dump", "Print the disassembly of the ROM, without a GUI."});
+  parser.addPositionalArgument("rom", "ROM to analyze.");
+  parser.process(*app);
+
+  if (parser.isSet("batch") || parser.isSet("dump")) {
+    Settings settings;
+    if (parser.isSet("profile") &&
+        !settings.loadProfile(parser.value("profile").toStdString())) {
+      std::cerr << "Could not load profile "
+                << parser.value("profile").toStdString() << std::endl;
+      return 1;
+    }
+    if (parser.positionalArguments().isEmpty()) {
+      std::cerr << "No ROM to analyze." << std::endl;
+      return 1;
+    }
+    auto romPath = parser.positionalArguments().first();
+    if (parser.isSet("dump")) {
+      dumpDisassembly(romPath.toStdString(), settings, std::cout, std::cerr);
+      return 0;
+    }
+    return runBatch(romPath, parser.value("batch"), settings);
+  }
+
+  // Without a chosen theme, honor NO_COLOR (https://no-color.org).
+  std::string theme = "default";
+  if (parser.isSet("theme")) {
+    theme = parser.value("theme").toStdString();
+  } else if (!qEnvironmentVariableIsEmpty("NO_COLOR")) {
+    theme = "mono";
+  }
+  if (!Theme::select(theme)) {
+    std::cerr << "Unknown theme " << theme << std::endl;
+    return 1;
+  }
 
   MainWindow window;
+  if (parser.isSet("profile")) {
+    window.loadProfile(parser.value("profile"));
+  }
+  if (!parser.positionalArguments().isEmpty()) {
+    window.openROM(parser.positionalArguments().first());
+  }
   window.showMaximized();
 
-  return app.exec();
+  return app->exec();
 }
@@ -4,10 +4,18 @@
 #include "gui/mainwindow.hpp"
 
 #include "analysis.hpp"
+#include "disassembly.hpp"
+#include "gui/adddatablockdialog.hpp"
 #include "gui/addentrypointdialog.hpp"
 #include "gui/disassemblyview.hpp"
+#include "gui/listdialog.hpp"
+#include "gui/settingsdialog.hpp"
 #include "gui/subroutinesview.hpp"
+#include "hardwareregisters.hpp"
+#include "migration.hpp"
+#include "sweep.hpp"
 #include "rom.hpp"
+#include "utils.hpp"
 
 MainWindow::MainWindow(QWidget* parent) : QMainWindow(parent) {
   setWindowTitle(APP_TITLE);
@@ -26,13 +34,109 @@ void MainWindow::setupMenus() {
       "&Open ROM...", this, [this]() { openROM(); }, QKeySequence::Open);
   fileMenu->addAction(
       "&Save Analysis", this, [this]() { saveAnalysis(); }, QKeySequence::Save);
+  fileMenu->addAction("&Migrate Analysis...", this,
+                      &MainWindow::migrateAnalysisDialog);
+  fileMenu->addAction("&Export Documentation...", this,
+                      &MainWindow::exportDocumentationDialog);
+  fileMenu->addAction("Export &Assembly...", this,
+                      &MainWindow::exportAssemblyDialog);
+  fileMenu->addAction("Export D&isassembly...", this,
+                      &MainWindow::exportDisassemblyDialog);
+  fileMenu->addAction("Export &Call Graph...", this,
+                      &MainWindow::exportCallGraphDialog);
+  fileMenu->addAction("Export S&ymbols...", this,
+                      &MainWindow::exportSymbolsDialog);
   fileMenu->addSeparator();
   fileMenu->addAction("E&xit", qApp, &QApplication::quit, QKeySequence::Quit);
 
   QMenu* editMenu = new QMenu("&Edit", this);
   menuBar()->addMenu(editMenu);
+  editMenu->addAction("&Undo", this, &MainWindow::undo, QKeySequence::Undo);
+  editMenu->addAction("&Redo", this, &MainWindow::redo, QKeySequence::Redo);
+  editMenu->addSeparator();
   editMenu->addAction("Add &Entry Point...", this,
                       &MainWindow::addEntryPointDialog);
+  editMenu->addAction("&Load Entry Points...", this,
+                      &MainWindow::loadEntryPointsDialog);
+  editMenu->addAction("Add &Data Block...", this,
+                      &MainWindow::addDataBlockDialog);
+  editMenu->addAction("Data &Blocks...", this, &MainWindow::dataBlocksDialog);
+  editMenu->addAction("Name &RAM Variable...", this,
+                      &MainWindow::labelRAMDialog);
+
+  QMenu* analysisMenu = new QMenu("&Analysis", this);
+  menuBar()->addMenu(analysisMenu);
+  // Rebuild the results from scratch, keeping the user's annotations.
+  analysisMenu->addAction(
+      "&Reanalyze", this,
+      [this]() {
+        if (analysis != nullptr) {
+          runAnalysis();
+        }
+      },
+      QKeySequence::Refresh);
+  analysisMenu->addSeparator();
+  analysisMenu->addAction("&Critical Sections...", this,
+                          &MainWindow::criticalSectionsDialog);
+  analysisMenu->addAction("&Indirect Jumps...", this,
+                          &MainWindow::indirectJumpsDialog);
+  analysisMenu->addAction("&Guess Jump Tables", this,
+                          &MainWindow::guessJumpTables);
+  analysisMenu->addAction("Clobbered &Registers...", this,
+                          &MainWindow::clobbersDialog);
+  analysisMenu->addAction("&Leaf Subroutines...", this,
+                          &MainWindow::leavesDialog);
+  analysisMenu->addAction("RAM Acc&esses...", this,
+                          &MainWindow::ramReferencesDialog);
+  analysisMenu->addAction("&Direct Page Before Init...", this,
+                          &MainWindow::directPageBeforeInitDialog);
+  analysisMenu->addAction("&Memory Clears...", this,
+                          &MainWindow::regionClearsDialog);
+  analysisMenu->addAction("&Assertions...", this,
+                          &MainWindow::assertionsDialog);
+  analysisMenu->addAction("Call &Path...", this, &MainWindow::callPathDialog);
+  analysisMenu->addAction("&Overlapping Code...", this,
+                          &MainWindow::overlappingCodeDialog);
+  analysisMenu->addAction("Check &Completeness...", this,
+                          &MainWindow::completenessDialog);
+  analysisMenu->addAction("S&tatistics...", this,
+                          &MainWindow::statisticsDialog);
+  analysisMenu->addAction("U&nexplored Regions...", this,
+                          &MainWindow::unexploredDialog);
+  analysisMenu->addSeparator();
+  analysisMenu->addAction("S&weep ROM...", this, &MainWindow::sweepDialog);
+  analysisMenu->addAction("Sweep Res&ults...", this,
+                          &MainWindow::sweepResultsDialog);
+  analysisMenu->addSeparator();
+  analysisMenu->addAction("S&ettings...", this, &MainWindow::settingsDialog);
+  analysisMenu->addAction("&Save Profile...", this,
+                          &MainWindow::saveProfileDialog);
+  analysisMenu->addAction("&Load Profile...", this,
+                          &MainWindow::loadProfileDialog);
+
+  QMenu* navigateMenu = new QMenu("&Navigate", this);
+  menuBar()->addMenu(navigateMenu);
+  navigateMenu->addAction(
+      "&Back", this, [this]() { disassemblyView->goBack(); },
+      QKeySequence::Back);
+  navigateMenu->addAction(
+      "&Forward", this, [this]() { disassemblyView->goForward(); },
+      QKeySequence::Forward);
+  navigateMenu->addSeparator();
+  navigateMenu->addAction("&Go to Address...", this,
+                          &MainWindow::goToAddressDialog);
+  navigateMenu->addAction("&Memory...", this, &MainWindow::memoryDialog);
+  navigateMenu->addAction("&References...", this,
+                          &MainWindow::referencesDialog);
+  navigateMenu->addAction("Find &Bytes...", this, &MainWindow::findBytesDialog);
+  navigateMenu->addAction("Find &Instruction...", this,
+                          &MainWindow::findInstructionDialog);
+  navigateMenu->addSeparator();
+  navigateMenu->addAction("Add Boo&kmark...", this,
+                          &MainWindow::addBookmarkDialog);
+  navigateMenu->addAction("Remo&ve Bookmark...", this,
+                          &MainWindow::removeBookmarkDialog);
+  navigateMenu->addAction("Book&marks...", this, &MainWindow::bookmarksDialog);
 
   QMenu* helpMenu = new QMenu("&Help", this);
   menuBar()->addMenu(helpMenu);
@@ -61,9 +165,14 @@ void MainWindow::setupSignals() {
 
 void MainWindow::runAnalysis() {
   analysis->run();
+  recordEdit();
   emit analysisChanged(analysis);
 }
 
+void MainWindow::recordEdit() {
+  editHistory.push(analysis->saveState());
+}
+
 void MainWindow::openROM(const QString& path) {
   QString fileName = path;
 
@@ -77,7 +186,22 @@ void MainWindow::openROM(const QString& path) {
       delete analysis;
     }
     analysis = new Analysis(fileName.toStdString());
-    analysis->load();
+    nextMemoryAddress = std::nullopt;
+    editHistory.clear();
+    // Per-ROM settings, if saved, take precedence over the profile.
+    analysis->settings = settings;
+    if (analysis->load() && analysis->romChanged()) {
+      auto answer = QMessageBox::question(
+          this, "Open ROM",
+          "The saved analysis was made on a different version of this ROM, "
+          "so its annotations might end up at the wrong addresses.\n"
+          "Apply it anyway?");
+      if (answer != QMessageBox::Yes) {
+        delete analysis;
+        analysis = new Analysis(fileName.toStdString());
+        analysis->settings = settings;
+      }
+    }
     runAnalysis();
   }
 }
@@ -86,12 +210,1122 @@ void MainWindow::saveAnalysis() {
   analysis->save();
 }
 
+void MainWindow::undo() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  // Restoring a state doesn't record it as a new edit.
+  if (auto state = editHistory.undo()) {
+    analysis->restoreState(*state);
+    analysis->run();
+    emit analysisChanged(analysis);
+  }
+}
+
+void MainWindow::redo() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  if (auto state = editHistory.redo()) {
+    analysis->restoreState(*state);
+    analysis->run();
+    emit analysisChanged(analysis);
+  }
+}
+
+void MainWindow::migrateAnalysisDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  auto fileName = QFileDialog::getOpenFileName(
+      this, "Migrate Analysis From ROM", "", "SNES ROMs (*.smc *.sfc *.fig)");
+  if (fileName.isEmpty()) {
+    return;
+  }
+
+  Analysis oldAnalysis(fileName.toStdString());
+  if (!oldAnalysis.load()) {
+    QMessageBox::warning(this, "Migrate Analysis",
+                         "No saved analysis found for the selected ROM.");
+    return;
+  }
+  oldAnalysis.run();
+
+  Migration migration(&oldAnalysis, analysis);
+  migration.run();
+  runAnalysis();
+
+  if (migration.issues.empty()) {
+    return;
+  }
+  ListDialog dialog("Migration Report", {"Problem", "Item", "Old PC"}, this);
+  for (auto& issue : migration.issues) {
+    QString problem;
+    switch (issue.problem) {
+      case MigrationProblem::Unmatched:
+        problem = "Unmatched";
+        break;
+      case MigrationProblem::Ambiguous:
+        problem = "Ambiguous";
+        break;
+      case MigrationProblem::Collision:
+        problem = "Collision";
+        break;
+    }
+    dialog.addRow({issue.pc, issue.pc},
+                  {problem, QString::fromStdString(issue.item),
+                   qformat("$%06X", issue.pc)});
+  }
+  dialog.exec();
+}
+
+void MainWindow::exportDocumentationDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  auto fileName = QFileDialog::getSaveFileName(this, "Export Documentation", "",
+                                               "Markdown (*.md)");
+  if (fileName.isEmpty()) {
+    return;
+  }
+
+  QFile file(fileName);
+  if (!file.open(QIODevice::WriteOnly | QIODevice::Text)) {
+    QMessageBox::warning(this, "Export Documentation",
+                         "Could not write the selected file.");
+    return;
+  }
+  file.write(Disassembly(analysis).markdown().c_str());
+}
+
+void MainWindow::exportAssemblyDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  auto fileName = QFileDialog::getSaveFileName(this, "Export Assembly", "",
+                                               "Assembly (*.asm)");
+  if (fileName.isEmpty()) {
+    return;
+  }
+
+  QFile file(fileName);
+  if (!file.open(QIODevice::WriteOnly | QIODevice::Text)) {
+    QMessageBox::warning(this, "Export Assembly",
+                         "Could not write the selected file.");
+    return;
+  }
+  file.write(Disassembly(analysis).rom().c_str());
+}
+
+void MainWindow::exportDisassemblyDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  auto fileName = QFileDialog::getSaveFileName(this, "Export Disassembly", "",
+                                               "Text (*.txt)");
+  if (fileName.isEmpty()) {
+    return;
+  }
+
+  QFile file(fileName);
+  if (!file.open(QIODevice::WriteOnly | QIODevice::Text)) {
+    QMessageBox::warning(this, "Export Disassembly",
+                         "Could not write the selected file.");
+    return;
+  }
+  file.write(Disassembly(analysis).all().c_str());
+}
+
+void MainWindow::exportCallGraphDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  QString filter;
+  auto fileName =
+      QFileDialog::getSaveFileName(this, "Export Call Graph", "",
+                                   "Graphviz (*.dot);;JSON (*.json)", &filter);
+  if (fileName.isEmpty()) {
+    return;
+  }
+
+  QFile file(fileName);
+  if (!file.open(QIODevice::WriteOnly | QIODevice::Text)) {
+    QMessageBox::warning(this, "Export Call Graph",
+                         "Could not write the selected file.");
+    return;
+  }
+  Disassembly disassembly(analysis);
+  bool json = filter.startsWith("JSON") || fileName.endsWith(".json");
+  auto text = json ? disassembly.callGraphJSON() : disassembly.callGraph();
+  file.write(text.c_str());
+}
+
+void MainWindow::exportSymbolsDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  auto fileName = QFileDialog::getSaveFileName(this, "Export Symbols", "",
+                                               "Symbols (*.sym)");
+  if (fileName.isEmpty()) {
+    return;
+  }
+
+  QFile file(fileName);
+  if (!file.open(QIODevice::WriteOnly | QIODevice::Text)) {
+    QMessageBox::warning(this, "Export Symbols",
+                         "Could not write the selected file.");
+    return;
+  }
+  file.write(Disassembly(analysis).symbols().c_str());
+}
+
 void MainWindow::addEntryPointDialog() {
   AddEntryPointDialog dialog(this);
   if (dialog.exec()) {
-    analysis->addEntryPoint(dialog.label, dialog.pc, dialog.state);
+    if (auto error = analysis->entryPointError(dialog.pc)) {
+      QMessageBox::warning(this, "Add Entry Point",
+                           QString::fromStdString(*error) + ".");
+      return;
+    }
+    auto overlaps = analysis->overlappingInstructions().size();
+    analysis->addEntryPoint(dialog.label, dialog.pc, dialog.state,
+                            dialog.dataBank, dialog.directPage);
+    runAnalysis();
+
+    // A wrong entry state can decode the same bytes differently.
+    if (analysis->overlappingInstructions().size() > overlaps) {
+      QMessageBox::warning(this, "Add Entry Point",
+                           "The new entry point decodes some code differently "
+                           "than the existing analysis.");
+      overlappingCodeDialog();
+    }
+  }
+}
+
+void MainWindow::loadEntryPointsDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  auto fileName = QFileDialog::getOpenFileName(this, "Load Entry Points", "",
+                                               "Text files (*.txt)");
+  if (fileName.isEmpty()) {
+    return;
+  }
+
+  auto issues = analysis->loadEntryPoints(fileName.toStdString());
+  runAnalysis();
+
+  if (issues.empty()) {
+    return;
+  }
+  ListDialog dialog("Entry Points Not Loaded", {"Line", "Problem"}, this);
+  for (auto& issue : issues) {
+    dialog.addRow({0, 0}, {QString::number(issue.line),
+                           QString::fromStdString(issue.message)});
+  }
+  dialog.exec();
+}
+
+void MainWindow::addDataBlockDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  AddDataBlockDialog dialog(this);
+  if (dialog.exec()) {
+    if (!analysis->addDataBlock(dialog.start, dialog.end, dialog.type)) {
+      QMessageBox::warning(this, "Add Data Block",
+                           "The data block is empty, outside the ROM, or "
+                           "overlaps an existing data block.");
+      return;
+    }
+    runAnalysis();
+  }
+}
+
+void MainWindow::labelRAMDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  bool ok;
+  auto text = QInputDialog::getText(this, "Name RAM Variable", "Address:",
+                                    QLineEdit::Normal, QString(), &ok);
+  if (!ok) {
+    return;
+  }
+  auto address = ROM::wramAddress(text.trimmed().remove('$').toUInt(&ok, 16));
+  if (!ok || !address.has_value()) {
+    QMessageBox::warning(this, "Name RAM Variable",
+                         "The address is not in WRAM.");
+    return;
+  }
+
+  auto search = analysis->ramLabels.find(*address);
+  auto label = QInputDialog::getText(
+      this, "Name RAM Variable",
+      qformat("Name of $%06X (empty to remove it):", *address),
+      QLineEdit::Normal,
+      search != analysis->ramLabels.end()
+          ? QString::fromStdString(search->second)
+          : QString(),
+      &ok);
+  if (!ok) {
+    return;
+  }
+  if (!analysis->labelRAM(label.trimmed().toStdString(), *address)) {
+    QMessageBox::warning(this, "Name RAM Variable",
+                         "The name is invalid or already in use.");
+    return;
+  }
+  runAnalysis();
+}
+
+void MainWindow::dataBlocksDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  ListDialog dialog("Data Blocks", {"Start", "End", "Type"}, this);
+  for (auto& [start, block] : analysis->dataBlocks) {
+    dialog.addRow({start, start},
+                  {qformat("$%06X", start), qformat("$%06X", block.end),
+                   QString::fromStdString(dataTypeName(block.type))});
+  }
+
+  bool removed = false;
+  dialog.onActivated = [this, &dialog, &removed](PCPair pc) {
+    auto answer = QMessageBox::question(
+        this, "Data Blocks",
+        qformat("Remove the data block at $%06X?", pc.first));
+    if (answer == QMessageBox::Yes) {
+      removed = analysis->removeDataBlock(pc.first);
+      dialog.accept();
+    }
+  };
+  dialog.exec();
+
+  if (removed) {
+    runAnalysis();
+  }
+}
+
+void MainWindow::criticalSectionsDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  ListDialog dialog("Critical Sections", {"Subroutine", "PC", "Event"}, this);
+  for (auto& [pc, subroutine] : analysis->subroutines) {
+    for (auto& [instructionPC, instruction] : subroutine.instructions) {
+      auto comment = Disassembly::criticalSectionComment(instruction);
+      if (!comment.empty()) {
+        dialog.addRow(instruction->pcPair(),
+                      {QString::fromStdString(subroutine.label),
+                       qformat("$%06X", instructionPC),
+                       QString::fromStdString(comment)});
+      }
+    }
+  }
+
+  dialog.onActivated = [this](PCPair pc) { disassemblyView->goToPC(pc); };
+  dialog.exec();
+}
+
+void MainWindow::guessJumpTables() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  if (analysis->guessJumpTables().empty()) {
+    QMessageBox::information(this, "Guess Jump Tables",
+                             "No jump table could be guessed.");
+    return;
+  }
+  runAnalysis();
+  indirectJumpsDialog();
+}
+
+void MainWindow::indirectJumpsDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  ListDialog dialog("Indirect Jumps",
+                    {"Kind", "PC", "Subroutine", "Status", "Targets"}, this);
+  for (auto type : {InstructionType::Call, InstructionType::Jump}) {
+    for (auto pc : analysis->indirectJumps(type)) {
+      auto instruction = analysis->anyInstruction(pc);
+      auto& jumpTable = analysis->jumpTables.at(pc);
+
+      QString status;
+      QColor color;
+      switch (jumpTable.status) {
+        case JumpTableStatus::Unknown:
+          status = "Unresolved";
+          color = themeColor(&Theme::unknown);
+          break;
+        case JumpTableStatus::Partial:
+          status = jumpTable.guessed ? "Guessed" : "Partial";
+          color = themeColor(&Theme::partialJumpTable);
+          break;
+        case JumpTableStatus::Complete:
+          status = "Complete";
+          color = themeColor(&Theme::jumpTable);
+          break;
+        case JumpTableStatus::Emulated:
+          status = "Resolved by emulation";
+          color = themeColor(&Theme::jumpTable);
+          break;
+      }
+
+      auto subroutine = instruction->subroutine();
+      dialog.addRow(instruction->pcPair(),
+                    {type == InstructionType::Call ? "Call" : "Jump",
+                     qformat("$%06X", pc),
+                     QString::fromStdString(subroutine->label),
+                     status,
+                     QString::number(jumpTable.targets.size())},
+                    color);
+    }
+  }
+  // Returns to an address pushed on the stack are always resolved.
+  for (auto& [pc, target] : analysis->returnJumps()) {
+    auto& subroutine = analysis->subroutines.at(pc.second);
+    dialog.addRow(pc,
+                  {"Return", qformat("$%06X", pc.first),
+                   QString::fromStdString(subroutine.label), "Complete", "1"},
+                  themeColor(&Theme::jumpTable));
+  }
+
+  dialog.onActivated = [this](PCPair pc) { disassemblyView->goToPC(pc); };
+  dialog.exec();
+}
+
+void MainWindow::clobbersDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  ListDialog dialog("Clobbered Registers", {"Subroutine", "X", "Y"}, this);
+  for (auto& [pc, subroutine] : analysis->subroutines) {
+    if (subroutine.xPreservation == Preservation::Clobbered ||
+        subroutine.yPreservation == Preservation::Clobbered) {
+      dialog.addRow(
+          {pc, pc},
+          {QString::fromStdString(subroutine.label),
+           QString::fromStdString(preservationName(subroutine.xPreservation)),
+           QString::fromStdString(
+               preservationName(subroutine.yPreservation))});
+    }
+  }
+
+  dialog.onActivated = [this](PCPair pc) { disassemblyView->goToPC(pc); };
+  dialog.exec();
+}
+
+void MainWindow::leavesDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  ListDialog dialog("Leaf Subroutines", {"Subroutine", "PC", "Instructions"},
+                    this);
+  for (auto& [pc, subroutine] : analysis->subroutines) {
+    if (subroutine.isLeaf()) {
+      dialog.addRow({pc, pc},
+                    {QString::fromStdString(subroutine.label),
+                     qformat("$%06X", pc),
+                     QString::number(subroutine.instructions.size())});
+    }
+  }
+
+  dialog.onActivated = [this](PCPair pc) { disassemblyView->goToPC(pc); };
+  dialog.exec();
+}
+
+void MainWindow::ramReferencesDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  ListDialog dialog("RAM Accesses",
+                    {"Address", "Name", "Accesses", "Source", "Subroutine",
+                     "Instruction"},
+                    this);
+  for (auto& [address, sources] : analysis->ramReferences()) {
+    // Named variable or hardware register.
+    QString name;
+    auto ramLabel = analysis->ramLabels.find(address);
+    auto hwRegister = HARDWARE_REGISTERS.find(address);
+    if (ramLabel != analysis->ramLabels.end()) {
+      name = QString::fromStdString(ramLabel->second);
+    } else if (hwRegister != HARDWARE_REGISTERS.end()) {
+      name = QString::fromStdString("!" + hwRegister->second);
+    }
+
+    for (auto& [pc, subroutinePC] : sources) {
+      auto& subroutine = analysis->subroutines.at(subroutinePC);
+      auto source = subroutine.instructions.at(pc);
+      dialog.addRow({pc, subroutinePC},
+                    {qformat("$%06X", address), name,
+                     QString::number(sources.size()), qformat("$%06X", pc),
+                     QString::fromStdString(subroutine.label),
+                     QString::fromStdString(source->name() + " " +
+                                            source->argumentString())});
+    }
+  }
+
+  dialog.onActivated = [this](PCPair pc) { disassemblyView->goToPC(pc); };
+  dialog.exec();
+}
+
+void MainWindow::directPageBeforeInitDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  ListDialog dialog("Direct Page Before Init",
+                    {"Subroutine", "PC", "Instruction"}, this);
+  for (auto& [pc, subroutine] : analysis->subroutines) {
+    for (auto instructionPC : subroutine.directPageBeforeInit) {
+      auto instruction = subroutine.instructions.at(instructionPC);
+      dialog.addRow(instruction->pcPair(),
+                    {QString::fromStdString(subroutine.label),
+                     qformat("$%06X", instructionPC),
+                     QString::fromStdString(instruction->name() + " " +
+                                            instruction->argumentString())});
+    }
+  }
+
+  dialog.onActivated = [this](PCPair pc) { disassemblyView->goToPC(pc); };
+  dialog.exec();
+}
+
+void MainWindow::regionClearsDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  ListDialog dialog("Memory Clears", {"Subroutine", "PC", "Region"}, this);
+  for (auto& [pc, subroutine] : analysis->subroutines) {
+    for (auto& [instructionPC, regionClear] : subroutine.regionClears) {
+      auto instruction = subroutine.instructions.at(instructionPC);
+      dialog.addRow(instruction->pcPair(),
+                    {QString::fromStdString(subroutine.label),
+                     qformat("$%06X", instructionPC),
+                     QString::fromStdString(
+                         regionClearDescription(regionClear))});
+    }
+  }
+
+  dialog.onActivated = [this](PCPair pc) { disassemblyView->goToPC(pc); };
+  dialog.exec();
+}
+
+void MainWindow::assertionsDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  const QStringList filters = {"All", "Verified", "Probable", "Guess"};
+  bool ok;
+  auto filter = QInputDialog::getItem(this, "Assertions",
+                                      "Confidence:", filters, 0, false, &ok);
+  if (!ok) {
+    return;
+  }
+
+  std::optional<AssertionConfidence> confidence;
+  if (filter != "All") {
+    confidence = static_cast<AssertionConfidence>(filters.indexOf(filter) - 1);
+  }
+
+  ListDialog dialog("Assertions", {"PC", "Subroutine", "Type", "Confidence"},
+                    this);
+  Disassembly disassembly(analysis);
+  for (auto pcPair : analysis->assertionPCs(confidence)) {
+    auto& assertion = analysis->assertions.at(pcPair);
+
+    QColor color;
+    switch (assertion.confidence) {
+      case AssertionConfidence::Verified:
+        color = themeColor(&Theme::verifiedAssertion);
+        break;
+      case AssertionConfidence::Probable:
+        color = themeColor(&Theme::assertion);
+        break;
+      case AssertionConfidence::Guess:
+        color = themeColor(&Theme::guessAssertion);
+        break;
+    }
+    // Assertions on instructions the analysis doesn't reach anymore.
+    if (analysis->isDanglingAssertion(pcPair)) {
+      color = themeColor(&Theme::unknown);
+    }
+
+    auto type = assertion.type == AssertionType::Instruction ? "Instruction"
+                                                             : "Subroutine";
+    dialog.addRow(pcPair,
+                  {qformat("$%06X", pcPair.first),
+                   QString::fromStdString(
+                       disassembly.subroutineLabel(pcPair.second)),
+                   type,
+                   filters[static_cast<int>(assertion.confidence) + 1]},
+                  color);
+  }
+
+  dialog.onActivated = [this](PCPair pc) { disassemblyView->goToPC(pc); };
+  dialog.exec();
+}
+
+void MainWindow::callPathDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  QStringList labels;
+  std::vector<SubroutinePC> pcs;
+  for (auto& [pc, subroutine] : analysis->subroutines) {
+    labels.append(QString::fromStdString(subroutine.label));
+    pcs.push_back(pc);
+  }
+
+  bool ok;
+  auto from = QInputDialog::getItem(this, "Call Path", "From:", labels, 0,
+                                    false, &ok);
+  if (!ok) {
+    return;
+  }
+  auto to =
+      QInputDialog::getItem(this, "Call Path", "To:", labels, 0, false, &ok);
+  if (!ok) {
+    return;
+  }
+
+  auto path = analysis->shortestPath(pcs[labels.indexOf(from)],
+                                     pcs[labels.indexOf(to)]);
+  if (path.subroutines.empty() && path.gaps.empty()) {
+    QMessageBox::information(this, "Call Path",
+                             qformat("%s is never called from %s.",
+                                     to.toStdString().c_str(),
+                                     from.toStdString().c_str()));
+    return;
+  }
+
+  ListDialog dialog("Call Path", {"Step", "PC", "Subroutine"}, this);
+  for (size_t i = 0; i < path.subroutines.size(); i++) {
+    auto pc = path.subroutines[i];
+    dialog.addRow({pc, pc},
+                  {QString::number(i), qformat("$%06X", pc),
+                   QString::fromStdString(analysis->subroutines.at(pc).label)});
+  }
+  // Unresolved jumps might hide other (or shorter) paths.
+  for (auto pc : path.gaps) {
+    auto instruction = analysis->anyInstruction(pc);
+    dialog.addRow(instruction->pcPair(),
+                  {"Gap", qformat("$%06X", pc),
+                   QString::fromStdString(instruction->subroutine()->label)},
+                  themeColor(&Theme::unknown));
+  }
+
+  dialog.onActivated = [this](PCPair pc) { disassemblyView->goToPC(pc); };
+  dialog.exec();
+}
+
+void MainWindow::overlappingCodeDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  ListDialog dialog("Overlapping Code", {"PC", "Overlaps", "Subroutine"},
+                    this);
+  for (auto& [pc, other] : analysis->overlappingInstructions()) {
+    auto instruction = analysis->anyInstruction(other);
+    dialog.addRow(instruction->pcPair(),
+                  {qformat("$%06X", other), qformat("$%06X", pc),
+                   QString::fromStdString(instruction->subroutine()->label)},
+                  themeColor(&Theme::unknown));
+  }
+
+  dialog.onActivated = [this](PCPair pc) { disassemblyView->goToPC(pc); };
+  dialog.exec();
+}
+
+void MainWindow::completenessDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  if (analysis->isComplete()) {
+    QMessageBox::information(this, "Completeness",
+                             "The control flow is fully resolved.");
+  } else {
+    QMessageBox::information(
+        this, "Completeness",
+        qformat("The control flow has %d unresolved locations.",
+                (int)analysis->unresolvedCount()));
+  }
+}
+
+void MainWindow::statisticsDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  // Counts of unresolved problems are red, or green when there's none.
+  auto statistics = analysis->statistics();
+  auto colored = [](size_t count) {
+    auto color =
+        themeColor(count ? &Theme::unknown : &Theme::verifiedAssertion);
+    if (!color.isValid()) {
+      return QString::number(count);
+    }
+    return qformat("<font color=\"%s\">%zu</font>",
+                   color.name().toStdString().c_str(), count);
+  };
+
+  auto text = qformat("Code: %zu bytes, %.1f%% of the ROM (%zu bytes)<br>",
+                      statistics.codeBytes, statistics.coverage(),
+                      statistics.romSize);
+  text += qformat("Subroutines: %zu<br>", statistics.subroutines);
+  text += "Subroutines with unknown state changes: " +
+          colored(statistics.unknownSubroutines) + "<br>";
+  for (auto& [reason, count] : statistics.unknownReasons) {
+    text += QString::fromStdString("&nbsp;&nbsp;" + unknownReasonName(reason) +
+                                   ": ") +
+            colored(count) + "<br>";
+  }
+  text += "Unresolved indirect jumps: " + colored(statistics.unresolvedJumps);
+
+  QMessageBox box(QMessageBox::NoIcon, "Statistics", text, QMessageBox::Ok,
+                  this);
+  box.setTextFormat(Qt::RichText);
+  box.exec();
+}
+
+void MainWindow::unexploredDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  ListDialog dialog("Unexplored Regions", {"Start", "End", "Size"}, this);
+  for (auto& range : analysis->unexploredRanges()) {
+    dialog.addRow({range.start, range.start},
+                  {qformat("$%06X", range.start), qformat("$%06X", range.end),
+                   QString::number(range.size)});
+  }
+  dialog.exec();
+}
+
+void MainWindow::goToAddressDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  bool ok;
+  auto text = QInputDialog::getText(this, "Go to Address", "Address:",
+                                    QLineEdit::Normal, QString(), &ok);
+  if (!ok) {
+    return;
+  }
+  auto address = text.remove('$').toUInt(&ok, 16);
+  if (!ok) {
+    QMessageBox::warning(this, "Go to Address", "Invalid address.");
+    return;
+  }
+
+  auto pc = analysis->locate(address);
+  if (!pc.has_value()) {
+    QMessageBox::warning(
+        this, "Go to Address",
+        qformat("$%06X is not part of any analyzed instruction.", address));
+    return;
+  }
+  disassemblyView->goToPC(*pc);
+}
+
+void MainWindow::addBookmarkDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  bool ok;
+  auto text = QInputDialog::getText(this, "Add Bookmark", "Address or label:",
+                                    QLineEdit::Normal, QString(), &ok);
+  if (!ok) {
+    return;
+  }
+  auto address = analysis->resolveAddress(text.trimmed().toStdString());
+  if (!address.has_value()) {
+    QMessageBox::warning(this, "Add Bookmark", "Unknown address or label.");
+    return;
+  }
+
+  auto search = analysis->bookmarks.find(*address);
+  auto note = QInputDialog::getText(
+      this, "Add Bookmark", qformat("Note for $%06X:", *address),
+      QLineEdit::Normal,
+      search != analysis->bookmarks.end()
+          ? QString::fromStdString(search->second)
+          : QString(),
+      &ok);
+  if (ok) {
+    analysis->addBookmark(*address, note.trimmed().toStdString());
+    recordEdit();
+  }
+}
+
+void MainWindow::removeBookmarkDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  bool ok;
+  auto text = QInputDialog::getText(this, "Remove Bookmark",
+                                    "Address or label:", QLineEdit::Normal,
+                                    QString(), &ok);
+  if (!ok) {
+    return;
+  }
+  auto address = analysis->resolveAddress(text.trimmed().toStdString());
+  if (!address.has_value() || !analysis->removeBookmark(*address)) {
+    QMessageBox::warning(this, "Remove Bookmark", "No such bookmark.");
+    return;
+  }
+  recordEdit();
+}
+
+void MainWindow::bookmarksDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  ListDialog dialog("Bookmarks", {"Address", "Note", "Instruction"}, this);
+  for (auto& [address, note] : analysis->bookmarks) {
+    QString instructionText;
+    if (auto instruction = analysis->anyInstruction(address)) {
+      instructionText = QString::fromStdString(
+          instruction->name() + " " + instruction->argumentString());
+    }
+    dialog.addRow({address, address},
+                  {qformat("$%06X", address), QString::fromStdString(note),
+                   instructionText});
+  }
+
+  // Bookmarks outside the code (e.g. in RAM) can't be shown.
+  dialog.onActivated = [this](PCPair pc) {
+    if (auto location = analysis->locate(pc.first)) {
+      disassemblyView->goToPC(*location);
+    }
+  };
+  dialog.exec();
+}
+
+void MainWindow::memoryDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  bool ok;
+  auto text = QInputDialog::getText(
+      this, "Memory", "Address or label, size and step (+ for the next block):",
+      QLineEdit::Normal, QString(), &ok);
+  if (!ok) {
+    return;
+  }
+  auto fields = text.split(' ', Qt::SkipEmptyParts);
+  if (fields.isEmpty() || fields.size() > 3) {
+    QMessageBox::warning(this, "Memory", "Usage: ADDRESS [SIZE [STEP]]");
+    return;
+  }
+
+  // `+` resumes right after the previous dump.
+  std::optional<u24> address;
+  if (fields[0] == "+") {
+    address = nextMemoryAddress;
+    if (!address.has_value()) {
+      QMessageBox::warning(this, "Memory", "No previous dump to continue.");
+      return;
+    }
+  } else {
+    address = analysis->resolveAddress(fields[0].toStdString());
+  }
+  if (!address.has_value()) {
+    QMessageBox::warning(this, "Memory", "Unknown address or label.");
+    return;
+  }
+
+  // Size and step are in hexadecimal.
+  size_t size = 0x100;
+  size_t step = 1;
+  if (fields.size() > 1) {
+    size = fields[1].remove('$').toUInt(&ok, 16);
+  }
+  if (ok && fields.size() > 2) {
+    step = fields[2].remove('$').toUInt(&ok, 16);
+  }
+  if (!ok) {
+    QMessageBox::warning(this, "Memory", "Invalid size or step.");
+    return;
+  }
+
+  auto dump = Disassembly(analysis).hexDump(*address, size, step);
+  if (!dump.has_value()) {
+    QMessageBox::warning(this, "Memory",
+                         qformat("Can't dump $%zX bytes at $%06X, every %zu.",
+                                 size, *address, step));
+    return;
+  }
+  nextMemoryAddress = *address + size;
+
+  QMessageBox box(QMessageBox::NoIcon, "Memory",
+                  QString::fromStdString(*dump), QMessageBox::Ok, this);
+  box.setFont(QFont(MONOSPACE_FONT));
+  box.exec();
+}
+
+void MainWindow::referencesDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  bool ok;
+  auto text = QInputDialog::getText(this, "References", "Address or label:",
+                                    QLineEdit::Normal, QString(), &ok);
+  if (!ok) {
+    return;
+  }
+
+  auto target = analysis->resolveAddress(text.trimmed().toStdString());
+  if (!target.has_value()) {
+    QMessageBox::warning(this, "References", "Unknown address or label.");
+    return;
+  }
+
+  disassemblyView->referencesDialog(*target);
+}
+
+void MainWindow::findBytesDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  bool ok;
+  auto text = QInputDialog::getText(this, "Find Bytes",
+                                    "Pattern (e.g. A9 ?? 8D 00 21):",
+                                    QLineEdit::Normal, QString(), &ok);
+  if (!ok) {
+    return;
+  }
+  auto pattern = ROM::parsePattern(text.toStdString());
+  if (!pattern.has_value()) {
+    QMessageBox::warning(this, "Find Bytes", "Invalid pattern.");
+    return;
+  }
+
+  auto addresses = analysis->rom.find(*pattern, ROM::MAX_FIND_RESULTS + 1);
+  if (addresses.size() > ROM::MAX_FIND_RESULTS) {
+    addresses.resize(ROM::MAX_FIND_RESULTS);
+    QMessageBox::information(
+        this, "Find Bytes",
+        qformat("Too many matches, only the first %d are shown.",
+                (int)ROM::MAX_FIND_RESULTS));
+  }
+
+  ListDialog dialog("Find Bytes", {"Address", "Subroutine"}, this);
+  for (auto address : addresses) {
+    auto instructions = analysis->instructionsAt(address);
+    QStringList labels;
+    for (auto& [pc, subroutinePC] : instructions) {
+      auto& label = analysis->subroutines.at(subroutinePC).label;
+      labels.append(QString::fromStdString(label));
+    }
+    labels.removeDuplicates();
+
+    if (instructions.empty()) {
+      dialog.addRow({address, address},
+                    {qformat("$%06X", address), "(unexplored)"},
+                    themeColor(&Theme::unknown));
+    } else {
+      dialog.addRow(instructions[0],
+                    {qformat("$%06X", address), labels.join(", ")});
+    }
+  }
+
+  dialog.onActivated = [this](PCPair pc) {
+    if (!analysis->instructionsAt(pc.first).empty()) {
+      disassemblyView->goToPC(pc);
+    }
+  };
+  dialog.exec();
+}
+
+void MainWindow::findInstructionDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  bool ok;
+  auto text = QInputDialog::getText(this, "Find Instruction",
+                                    "Instruction (e.g. sta $2100):",
+                                    QLineEdit::Normal, QString(), &ok);
+  if (!ok) {
+    return;
+  }
+
+  // Mnemonic, followed by an optional argument in hex.
+  auto fields = text.simplified().toLower().split(' ');
+  std::optional<Op> operation;
+  for (size_t op = 0; op < std::size(OPCODE_NAMES); op++) {
+    if (fields[0].toStdString() == OPCODE_NAMES[op]) {
+      operation = static_cast<Op>(op);
+    }
+  }
+  std::optional<u24> argument;
+  if (fields.size() > 1) {
+    argument = fields[1].remove('$').remove('#').toUInt(&ok, 16);
+  }
+  if (!operation.has_value() || !ok || fields.size() > 2) {
+    QMessageBox::warning(this, "Find Instruction", "Invalid instruction.");
+    return;
+  }
+
+  ListDialog dialog("Find Instruction", {"Address", "Subroutine"}, this);
+  for (auto& pc : analysis->findInstructions(*operation, argument)) {
+    auto& label = analysis->subroutines.at(pc.second).label;
+    dialog.addRow(pc, {qformat("$%06X", pc.first),
+                       QString::fromStdString(label)});
+  }
+
+  dialog.onActivated = [this](PCPair pc) { disassemblyView->goToPC(pc); };
+  dialog.exec();
+}
+
+void MainWindow::sweepDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  // Sweep in chunks, so that the user can interrupt it and resume it later.
+  QProgressDialog progress("Sweeping the ROM...", "Stop", 0, Sweep::END >> 8,
+                           this);
+  progress.setWindowModality(Qt::WindowModal);
+  Sweep sweep(analysis);
+  while (!sweep.run(0x400)) {
+    progress.setValue(analysis->sweepCursor >> 8);
+    if (progress.wasCanceled()) {
+      return;
+    }
+  }
+  progress.setValue(Sweep::END >> 8);
+
+  sweepResultsDialog();
+}
+
+void MainWindow::sweepResultsDialog() {
+  if (analysis == nullptr) {
+    return;
+  }
+
+  ListDialog dialog("Sweep Results", {"PC", "Instructions", "Suspicion"},
+                    this);
+  for (auto& [pc, candidate] : analysis->sweepQuarantine) {
+    dialog.addRow({pc, pc},
+                  {qformat("$%06X", pc),
+                   QString::number(candidate.instructions),
+                   QString::number(candidate.suspicion)},
+                  candidate.suspicion > 0 ? themeColor(&Theme::unknown)
+                                          : QColor());
+  }
+
+  bool reviewed = false;
+  dialog.onActivated = [this, &dialog, &reviewed](PCPair pc) {
+    auto answer = QMessageBox::question(
+        this, "Sweep Results",
+        qformat("Accept the code at $%06X as a subroutine?", pc.first),
+        QMessageBox::Yes | QMessageBox::No | QMessageBox::Cancel);
+    if (answer == QMessageBox::Yes) {
+      reviewed = analysis->acceptSweepCandidate(pc.first);
+    } else if (answer == QMessageBox::No) {
+      reviewed = analysis->rejectSweepCandidate(pc.first);
+    }
+    if (reviewed) {
+      dialog.accept();
+    }
+  };
+  dialog.exec();
+
+  if (reviewed) {
+    runAnalysis();
+    if (!analysis->sweepQuarantine.empty()) {
+      sweepResultsDialog();
+    }
+  }
+}
+
+void MainWindow::settingsDialog() {
+  SettingsDialog dialog(analysis ? analysis->settings : settings, this);
+  if (dialog.exec()) {
+    if (analysis != nullptr) {
+      analysis->settings = dialog.settings;
+      runAnalysis();
+    } else {
+      settings = dialog.settings;
+    }
+  }
+}
+
+void MainWindow::saveProfileDialog() {
+  bool ok;
+  QString name = QInputDialog::getText(this, "Save Profile",
+                                       "Profile name:", QLineEdit::Normal,
+                                       QString(), &ok);
+
+  if (ok && !name.isEmpty()) {
+    auto& current = analysis ? analysis->settings : settings;
+    if (!current.saveProfile(name.toStdString())) {
+      QMessageBox::warning(this, "Save Profile", "Could not save the profile.");
+    }
+  }
+}
+
+void MainWindow::loadProfileDialog() {
+  QStringList profiles;
+  for (auto& profile : Settings::profiles()) {
+    profiles.append(QString::fromStdString(profile));
+  }
+
+  bool ok;
+  QString name = QInputDialog::getItem(this, "Load Profile", "Profile:",
+                                       profiles, 0, false, &ok);
+  if (ok && !name.isEmpty()) {
+    loadProfile(name);
+  }
+}
+
+bool MainWindow::loadProfile(const QString& name) {
+  if (!settings.loadProfile(name.toStdString())) {
+    QMessageBox::warning(this, "Load Profile",
+                         qformat("Could not load profile \"%s\".",
+                                 name.toStdString().c_str()));
+    return false;
+  }
+
+  if (analysis != nullptr) {
+    analysis->settings = settings;
     runAnalysis();
   }
+  return true;
 }
 
 void MainWindow::about() {
@@ -1,6 +1,11 @@
 #pragma once
 
 #include <QMainWindow>
+#include <optional>
+
+#include "history.hpp"
+#include "settings.hpp"
+#include "types.hpp"
 
 class Analysis;
 class DisassemblyView;
@@ -12,20 +17,64 @@ class MainWindow : public QMainWindow {
  public:
   MainWindow(QWidget* parent = nullptr);
   void runAnalysis();
+  bool loadProfile(const QString& name);
 
  signals:
   void analysisChanged(Analysis* analysis);
 
- private slots:
+ public slots:
   void openROM(const QString& path = QString());
+
+ private slots:
   void saveAnalysis();
+  void migrateAnalysisDialog();
+  void undo();
+  void redo();
+  void exportDocumentationDialog();
+  void exportAssemblyDialog();
+  void exportDisassemblyDialog();
+  void exportCallGraphDialog();
+  void exportSymbolsDialog();
   void addEntryPointDialog();
+  void loadEntryPointsDialog();
+  void addDataBlockDialog();
+  void dataBlocksDialog();
+  void labelRAMDialog();
+  void criticalSectionsDialog();
+  void indirectJumpsDialog();
+  void guessJumpTables();
+  void clobbersDialog();
+  void leavesDialog();
+  void ramReferencesDialog();
+  void directPageBeforeInitDialog();
+  void regionClearsDialog();
+  void assertionsDialog();
+  void callPathDialog();
+  void overlappingCodeDialog();
+  void completenessDialog();
+  void statisticsDialog();
+  void unexploredDialog();
+  void goToAddressDialog();
+  void addBookmarkDialog();
+  void removeBookmarkDialog();
+  void bookmarksDialog();
+  void memoryDialog();
+  void referencesDialog();
+  void findBytesDialog();
+  void findInstructionDialog();
+  void sweepDialog();
+  void sweepResultsDialog();
+  void settingsDialog();
+  void saveProfileDialog();
+  void loadProfileDialog();
   void about();
 
  private:
   void setupMenus();
   void setupWidgets();
   void setupSignals();
+  // Record the current annotations in the undo history.
+  void recordEdit();
 
   QDockWidget* leftDockWidget;
 
@@ -33,6 +82,11 @@ class MainWindow : public QMainWindow {
   SubroutinesView* subroutinesView;
 
   Analysis* analysis = nullptr;
+  Settings settings;  // Settings applied to newly opened ROMs.
+  // Address right after the last memory dump, where `+` resumes.
+  std::optional<u24> nextMemoryAddress;
+  // Annotations after each edit, to undo and redo them.
+  EditHistory editHistory;
 };
 
 #define ACCESS_MAIN_WINDOW \
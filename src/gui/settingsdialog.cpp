@@ -0,0 +1,130 @@
+#include <QBoxLayout>
+#include <QCheckBox>
+#include <QDialogButtonBox>
+#include <QLabel>
+#include <QLineEdit>
+
+#include "settingsdialog.hpp"
+
+#include "utils.hpp"
+
+SettingsDialog::SettingsDialog(Settings settings, QWidget* parent)
+    : QDialog(parent), settings{settings} {
+  setWindowTitle("Settings");
+  setupLayout();
+  setFixedSize(sizeHint());
+
+  restoreFromSettings();
+}
+
+auto SettingsDialog::createTextAreas() {
+  auto hbox = new QHBoxLayout;
+
+  auto subroutineVbox = new QVBoxLayout;
+  auto subroutineLabel = new QLabel("Subroutine prefix:", this);
+  subroutinePrefixText = new QLineEdit(this);
+  subroutineVbox->addWidget(subroutineLabel);
+  subroutineVbox->addWidget(subroutinePrefixText);
+
+  auto localVbox = new QVBoxLayout;
+  auto localLabel = new QLabel("Local label prefix:", this);
+  localLabelPrefixText = new QLineEdit(this);
+  localVbox->addWidget(localLabel);
+  localVbox->addWidget(localLabelPrefixText);
+
+  auto dataOpcodesVbox = new QVBoxLayout;
+  auto dataOpcodesLabel = new QLabel("Data opcodes (strict mode):", this);
+  dataOpcodesText = new QLineEdit(this);
+  dataOpcodesVbox->addWidget(dataOpcodesLabel);
+  dataOpcodesVbox->addWidget(dataOpcodesText);
+
+  hbox->addLayout(subroutineVbox);
+  hbox->addLayout(localVbox);
+  hbox->addLayout(dataOpcodesVbox);
+
+  return hbox;
+}
+
+auto SettingsDialog::createCheckBoxes() {
+  auto vbox = new QVBoxLayout;
+  stateInferenceCheckBox = new QCheckBox("State inference", this);
+  strictModeCheckBox = new QCheckBox("Strict mode", this);
+  localLabelTableCheckBox = new QCheckBox("Local label table", this);
+  emulationResetCheckBox =
+      new QCheckBox("Start reset in emulation mode", this);
+  vbox->addWidget(stateInferenceCheckBox);
+  vbox->addWidget(strictModeCheckBox);
+  vbox->addWidget(localLabelTableCheckBox);
+  takenBranchesOnlyCheckBox =
+      new QCheckBox("Follow only taken branches", this);
+  vbox->addWidget(emulationResetCheckBox);
+  vbox->addWidget(takenBranchesOnlyCheckBox);
+  sizeSuffixesCheckBox =
+      new QCheckBox("Operand size suffixes in the assembly export", this);
+  vbox->addWidget(sizeSuffixesCheckBox);
+  nmiAllStatesCheckBox =
+      new QCheckBox("Run the NMI handler with every size of A and X", this);
+  vbox->addWidget(nmiAllStatesCheckBox);
+  return vbox;
+}
+
+auto SettingsDialog::createButtonBox() {
+  auto buttonBox = new QDialogButtonBox(
+      QDialogButtonBox::Ok | QDialogButtonBox::Cancel, this);
+
+  connect(buttonBox, &QDialogButtonBox::accepted, this,
+          &SettingsDialog::accept);
+  connect(buttonBox, &QDialogButtonBox::rejected, this, &QDialog::reject);
+
+  return buttonBox;
+}
+
+void SettingsDialog::setupLayout() {
+  auto vbox = new QVBoxLayout(this);
+  vbox->addLayout(createTextAreas());
+  vbox->addLayout(createCheckBoxes());
+  vbox->addWidget(createButtonBox());
+}
+
+void SettingsDialog::restoreFromSettings() {
+  subroutinePrefixText->setText(
+      QString::fromStdString(settings.subroutinePrefix));
+  localLabelPrefixText->setText(
+      QString::fromStdString(settings.localLabelPrefix));
+  stateInferenceCheckBox->setChecked(settings.stateInference);
+  strictModeCheckBox->setChecked(settings.strictMode);
+  localLabelTableCheckBox->setChecked(settings.localLabelTable);
+  emulationResetCheckBox->setChecked(settings.emulationReset);
+  takenBranchesOnlyCheckBox->setChecked(settings.takenBranchesOnly);
+  sizeSuffixesCheckBox->setChecked(settings.sizeSuffixes);
+  nmiAllStatesCheckBox->setChecked(settings.nmiAllStates);
+
+  QStringList opcodes;
+  for (auto opcode : settings.dataOpcodes) {
+    opcodes.append(qformat("%02X", opcode));
+  }
+  dataOpcodesText->setText(opcodes.join(" "));
+}
+
+void SettingsDialog::accept() {
+  settings.subroutinePrefix = subroutinePrefixText->text().toStdString();
+  settings.localLabelPrefix = localLabelPrefixText->text().toStdString();
+  settings.stateInference = stateInferenceCheckBox->isChecked();
+  settings.strictMode = strictModeCheckBox->isChecked();
+  settings.localLabelTable = localLabelTableCheckBox->isChecked();
+  settings.emulationReset = emulationResetCheckBox->isChecked();
+  settings.takenBranchesOnly = takenBranchesOnlyCheckBox->isChecked();
+  settings.sizeSuffixes = sizeSuffixesCheckBox->isChecked();
+  settings.nmiAllStates = nmiAllStatesCheckBox->isChecked();
+
+  settings.dataOpcodes.clear();
+  for (auto& opcode : dataOpcodesText->text().split(" ", Qt::SkipEmptyParts)) {
+    bool ok;
+    auto value = opcode.toUInt(&ok, 16);
+    if (ok && value <= 0xFF) {
+      settings.dataOpcodes.insert(value);
+    }
+  }
+
+  QDialog::accept();
+}
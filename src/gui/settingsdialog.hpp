@@ -0,0 +1,39 @@
+#pragma once
+
+#include <QDialog>
+
+#include "settings.hpp"
+
+class QCheckBox;
+class QLineEdit;
+
+class SettingsDialog : public QDialog {
+  Q_OBJECT
+
+ public:
+  SettingsDialog(Settings settings, QWidget* parent = nullptr);
+
+  Settings settings;
+
+ private slots:
+  void accept();
+
+ private:
+  auto createTextAreas();
+  auto createCheckBoxes();
+  auto createButtonBox();
+  void setupLayout();
+
+  void restoreFromSettings();
+
+  QLineEdit* subroutinePrefixText;
+  QLineEdit* localLabelPrefixText;
+  QCheckBox* stateInferenceCheckBox;
+  QCheckBox* strictModeCheckBox;
+  QCheckBox* localLabelTableCheckBox;
+  QCheckBox* emulationResetCheckBox;
+  QCheckBox* takenBranchesOnlyCheckBox;
+  QCheckBox* sizeSuffixesCheckBox;
+  QCheckBox* nmiAllStatesCheckBox;
+  QLineEdit* dataOpcodesText;
+};
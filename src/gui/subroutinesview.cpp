@@ -1,7 +1,11 @@
+#include <map>
+
 #include "gui/subroutinesview.hpp"
 
 #include "analysis.hpp"
+#include "disassembly.hpp"
 #include "gui/constants.hpp"
+#include "utils.hpp"
 
 SubroutinesView::SubroutinesView(QWidget* parent) : QListWidget(parent) {
   setFont(QFont(MONOSPACE_FONT));
@@ -13,10 +17,61 @@ void SubroutinesView::renderAnalysis(const Analysis* analysis) {
     auto item =
         new QListWidgetItem(QString::fromStdString(subroutine.label), this);
 
+    auto toolTip = QString::fromStdString(
+        "X: " + preservationName(subroutine.xPreservation) +
+        ", Y: " + preservationName(subroutine.yPreservation));
+    toolTip += qformat("\nCycles: ~%zu", subroutine.cycles());
+    // What the code of an entry point assumes about its guessed entry state.
+    if (subroutine.isEntryPoint && !subroutine.assumedState.isEmpty()) {
+      toolTip += QString::fromStdString(
+          "\nAssumes on entry: " +
+          Disassembly::stateChangeDescription(subroutine.assumedState));
+    }
+    QStringList subsystems;
+    for (auto subsystem : subroutine.touchedSubsystems()) {
+      subsystems.append(QString::fromStdString(subsystemName(subsystem)));
+    }
+    if (!subsystems.isEmpty()) {
+      toolTip += "\nTouches: " + subsystems.join(", ");
+    }
+    for (auto& stackTrace : subroutine.stackTraces) {
+      QStringList calls;
+      for (auto pc : stackTrace) {
+        calls.append(qformat("$%06X", pc));
+      }
+      toolTip += "\nCalled through: " + calls.join(" > ");
+    }
+    // Reasons the subroutine is responsible for, by address.
+    std::map<InstructionPC, StateChange> unknownStateChanges(
+        subroutine.unknownStateChanges.begin(),
+        subroutine.unknownStateChanges.end());
+    for (auto& [stopPC, stateChange] : unknownStateChanges) {
+      if (stateChange.unknownReason != UnknownReason::Unknown) {
+        toolTip += qformat("\nUnknown state at $%06X: ", stopPC) +
+                   QString::fromStdString(
+                       unknownReasonName(stateChange.unknownReason));
+      }
+    }
+    for (auto hangPoint : subroutine.hangPoints) {
+      toolTip += qformat("\nHangs at: $%06X", hangPoint);
+    }
+    item->setToolTip(toolTip);
+
+    // Subroutines containing a loop that is never left.
+    if (!subroutine.hangPoints.empty()) {
+      auto font = item->font();
+      font.setItalic(true);
+      item->setFont(font);
+    }
+
+    QColor color;
     if (subroutine.isEntryPoint) {
-      item->setForeground(ENTRYPOINT_COLOR);
+      color = themeColor(&Theme::entryPoint);
     } else if (subroutine.isResponsibleForUnknown()) {
-      item->setForeground(Qt::red);
+      color = themeColor(&Theme::unknown);
+    }
+    if (color.isValid()) {
+      item->setForeground(color);
     }
 
     addItem(item);
@@ -0,0 +1,141 @@
+#include <algorithm>
+
+#include "hangpoint.hpp"
+
+#include "analysis.hpp"
+#include "instruction.hpp"
+#include "subroutine.hpp"
+
+using namespace std;
+
+// Constructor.
+HangPointAnalysis::HangPointAnalysis(Analysis* analysis)
+    : analysis{analysis} {}
+
+// Find the hang points of every subroutine.
+void HangPointAnalysis::run() {
+  for (auto& [pc, subroutine] : analysis->subroutines) {
+    findHangPoints(subroutine);
+  }
+}
+
+// Return the instructions that can follow the given one inside its
+// subroutine. Return nothing if execution can leave the subroutine.
+optional<vector<InstructionPC>> HangPointAnalysis::successors(
+    const Subroutine& subroutine,
+    const Instruction* instruction) const {
+  // The analysis couldn't proceed from here.
+  if (subroutine.unknownStateChanges.count(instruction->pc)) {
+    return nullopt;
+  }
+
+  // Returns leave the subroutine. Calls might not come back, so a loop
+  // calling other code is not considered a hang.
+  auto type = instruction->type();
+  if (type == InstructionType::Return || type == InstructionType::Interrupt ||
+      type == InstructionType::Call) {
+    return nullopt;
+  }
+
+  vector<InstructionPC> pcs;
+  if (type == InstructionType::Branch || type == InstructionType::Jump) {
+    auto search = analysis->references.find(instruction->pc);
+    if (search != analysis->references.end()) {
+      for (auto& reference : search->second) {
+        if (reference.subroutinePC == subroutine.pc) {
+          pcs.push_back(reference.target);
+        }
+      }
+    }
+    // Indirect jump with unknown targets.
+    if (pcs.empty()) {
+      return nullopt;
+    }
+  }
+  // Everything but jumps can continue to the next instruction.
+  if (type != InstructionType::Jump) {
+    pcs.push_back(instruction->pc + instruction->size());
+  }
+
+  // Leaving the code explored as part of this subroutine.
+  for (auto pc : pcs) {
+    if (subroutine.instructions.count(pc) == 0) {
+      return nullopt;
+    }
+  }
+  return pcs;
+}
+
+// Find the hang points of a subroutine.
+void HangPointAnalysis::findHangPoints(Subroutine& subroutine) {
+  graph.clear();
+  order.clear();
+  lowLink.clear();
+  stack.clear();
+  onStack.clear();
+  components.clear();
+
+  for (auto& [pc, instruction] : subroutine.instructions) {
+    graph[pc] = successors(subroutine, instruction);
+  }
+  for (auto& [pc, instruction] : subroutine.instructions) {
+    if (order.count(pc) == 0) {
+      visit(pc);
+    }
+  }
+
+  for (auto& component : components) {
+    set<InstructionPC> members(component.begin(), component.end());
+
+    // A single instruction is a loop only if it leads to itself.
+    bool isLoop = component.size() > 1;
+    bool hasExit = false;
+    for (auto pc : component) {
+      auto& next = graph.at(pc);
+      if (!next.has_value()) {
+        hasExit = true;
+        break;
+      }
+      for (auto target : *next) {
+        isLoop |= target == pc;
+        hasExit |= members.count(target) == 0;
+      }
+    }
+
+    if (isLoop && !hasExit) {
+      subroutine.hangPoints.insert(*members.begin());
+    }
+  }
+}
+
+// Visit an instruction (Tarjan's strongly connected components).
+void HangPointAnalysis::visit(InstructionPC pc) {
+  int index = order.size();
+  order[pc] = lowLink[pc] = index;
+  stack.push_back(pc);
+  onStack.insert(pc);
+
+  if (auto& next = graph.at(pc)) {
+    for (auto target : *next) {
+      if (order.count(target) == 0) {
+        visit(target);
+        lowLink[pc] = min(lowLink[pc], lowLink[target]);
+      } else if (onStack.count(target)) {
+        lowLink[pc] = min(lowLink[pc], order[target]);
+      }
+    }
+  }
+
+  // Root of a component: pop it from the stack.
+  if (lowLink[pc] == order[pc]) {
+    vector<InstructionPC> component;
+    InstructionPC member;
+    do {
+      member = stack.back();
+      stack.pop_back();
+      onStack.erase(member);
+      component.push_back(member);
+    } while (member != pc);
+    components.push_back(component);
+  }
+}
@@ -0,0 +1,53 @@
+#pragma once
+
+#include <map>
+#include <optional>
+#include <set>
+#include <vector>
+
+#include "types.hpp"
+
+class Analysis;
+class Instruction;
+struct Subroutine;
+
+/**
+ * Find the loops that execution can never leave, such as `JMP` to itself or
+ * `- : WAI : BRA -`. These are usually intentional halts (e.g. in error
+ * handlers) rather than regular control flow.
+ */
+class HangPointAnalysis {
+ public:
+  // Constructor.
+  HangPointAnalysis(Analysis* analysis);
+
+  // Find the hang points of every subroutine.
+  void run();
+
+ private:
+  // Return the instructions that can follow the given one inside its
+  // subroutine. Return nothing if execution can leave the subroutine.
+  std::optional<std::vector<InstructionPC>> successors(
+      const Subroutine& subroutine,
+      const Instruction* instruction) const;
+
+  // Find the hang points of a subroutine.
+  void findHangPoints(Subroutine& subroutine);
+
+  // Visit an instruction (Tarjan's strongly connected components).
+  void visit(InstructionPC pc);
+
+  // Pointer to the analysis.
+  Analysis* analysis;
+
+  // Successors of each instruction of the current subroutine (none if
+  // execution can leave the subroutine from there).
+  std::map<InstructionPC, std::optional<std::vector<InstructionPC>>> graph;
+  // Visiting order and lowest reachable order of each instruction.
+  std::map<InstructionPC, int> order, lowLink;
+  // Instructions of the components being built.
+  std::vector<InstructionPC> stack;
+  std::set<InstructionPC> onStack;
+  // Strongly connected components found so far.
+  std::vector<std::vector<InstructionPC>> components;
+};
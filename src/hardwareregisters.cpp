@@ -0,0 +1,34 @@
+#include "hardwareregisters.hpp"
+
+using namespace std;
+
+// Return the subsystem a hardware register belongs to, if any.
+optional<Subsystem> hardwareSubsystem(u16 address) {
+  if (address >= 0x2100 && address <= 0x213F) {
+    return Subsystem::PPU;
+  } else if (address >= 0x2140 && address <= 0x217F) {
+    return Subsystem::APU;
+  } else if (address == 0x420B || address == 0x420C ||
+             (address >= 0x4300 && address <= 0x437F)) {
+    return Subsystem::DMA;
+  } else if (address == 0x4016 || address == 0x4017 ||
+             (address >= 0x4218 && address <= 0x421F)) {
+    return Subsystem::Controller;
+  }
+  return nullopt;
+}
+
+// Return a human-readable name for a subsystem.
+string subsystemName(Subsystem subsystem) {
+  switch (subsystem) {
+    case Subsystem::PPU:
+      return "PPU";
+    case Subsystem::APU:
+      return "APU";
+    case Subsystem::DMA:
+      return "DMA";
+    case Subsystem::Controller:
+      return "Controller";
+  }
+  __builtin_unreachable();
+}
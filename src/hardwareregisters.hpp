@@ -1,5 +1,6 @@
 #pragma once
 
+#include <optional>
 #include <string>
 #include <unordered_map>
 
@@ -71,3 +72,17 @@ inline const std::unordered_map<u24, std::string> HARDWARE_REGISTERS = {
     {0x4375, "DAS7L"},       {0x4376, "DAS7H"},       {0x4377, "DASB7"},
     {0x4378, "A2A7L"},       {0x4379, "A2A7H"},       {0x437A, "NTLR7"},
 };
+
+// Hardware subsystems controlled through the registers.
+enum class Subsystem {
+  PPU,         // Picture Processing Unit ($2100-$213F).
+  APU,         // Audio Processing Unit, through its ports ($2140-$217F).
+  DMA,         // DMA and HDMA ($420B-$420C, $4300-$437F).
+  Controller,  // Joypads ($4016-$4017, $4218-$421F).
+};
+
+// Return the subsystem a hardware register belongs to, if any.
+std::optional<Subsystem> hardwareSubsystem(u16 address);
+
+// Return a human-readable name for a subsystem.
+std::string subsystemName(Subsystem subsystem);
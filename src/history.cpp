@@ -0,0 +1,96 @@
+#include "history.hpp"
+
+using namespace std;
+
+// Record a new location, discarding the forward history.
+void NavigationHistory::push(PCPair pc) {
+  if (current() == pc) {
+    return;
+  }
+
+  if (!history.empty()) {
+    history.resize(index + 1);
+  }
+  history.push_back(pc);
+  index = history.size() - 1;
+}
+
+// Record a move from a location (if known) to another, so that going back
+// returns to where the move started.
+void NavigationHistory::visit(optional<PCPair> origin, PCPair destination) {
+  if (origin.has_value()) {
+    push(*origin);
+  }
+  push(destination);
+}
+
+// Move back in the history, returning the new location (if any).
+optional<PCPair> NavigationHistory::back() {
+  if (history.empty() || index == 0) {
+    return nullopt;
+  }
+  return history[--index];
+}
+
+// Move forward in the history, returning the new location (if any).
+optional<PCPair> NavigationHistory::forward() {
+  if (index + 1 >= history.size()) {
+    return nullopt;
+  }
+  return history[++index];
+}
+
+// Return the current location, if any.
+optional<PCPair> NavigationHistory::current() const {
+  if (history.empty()) {
+    return nullopt;
+  }
+  return history[index];
+}
+
+// Clear the history.
+void NavigationHistory::clear() {
+  history.clear();
+  index = 0;
+}
+
+// Record the state after an edit, discarding the redo history. Nothing is
+// recorded if the state didn't change.
+void EditHistory::push(const string& state) {
+  if (!history.empty() && history[index] == state) {
+    return;
+  }
+
+  if (!history.empty()) {
+    history.resize(index + 1);
+  }
+  history.push_back(state);
+  // Keep the initial state and the last MAX_UNDO edits.
+  if (history.size() > MAX_UNDO + 1) {
+    history.pop_front();
+  }
+  index = history.size() - 1;
+}
+
+// Move back to the state before the last edit, returning it (if any).
+optional<string> EditHistory::undo() {
+  if (history.empty() || index == 0) {
+    return nullopt;
+  }
+  return history[--index];
+}
+
+// Move forward to the state after the last undone edit, returning it (if
+// any).
+optional<string> EditHistory::redo() {
+  if (index + 1 >= history.size()) {
+    return nullopt;
+  }
+  return history[++index];
+}
+
+// Clear the history.
+void EditHistory::clear() {
+  history.clear();
+  index = 0;
+}
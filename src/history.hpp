@@ -0,0 +1,58 @@
+#pragma once
+
+#include <deque>
+#include <optional>
+#include <string>
+#include <vector>
+
+#include "instruction.hpp"
+
+// Browser-like history of visited locations.
+class NavigationHistory {
+ public:
+  // Record a new location, discarding the forward history.
+  void push(PCPair pc);
+  // Record a move from a location (if known) to another, so that going back
+  // returns to where the move started.
+  void visit(std::optional<PCPair> origin, PCPair destination);
+
+  // Move back in the history, returning the new location (if any).
+  std::optional<PCPair> back();
+  // Move forward in the history, returning the new location (if any).
+  std::optional<PCPair> forward();
+
+  // Return the current location, if any.
+  std::optional<PCPair> current() const;
+
+  // Clear the history.
+  void clear();
+
+ private:
+  std::vector<PCPair> history;  // Visited locations.
+  size_t index = 0;             // Index of the current location.
+};
+
+// Undo history of the user's edits, as states of the analysis saved after
+// each of them.
+class EditHistory {
+ public:
+  // Record the state after an edit, discarding the redo history. Nothing is
+  // recorded if the state didn't change.
+  void push(const std::string& state);
+
+  // Move back to the state before the last edit, returning it (if any).
+  std::optional<std::string> undo();
+  // Move forward to the state after the last undone edit, returning it (if
+  // any).
+  std::optional<std::string> redo();
+
+  // Clear the history.
+  void clear();
+
+  // Maximum number of edits that can be undone.
+  static const size_t MAX_UNDO = 50;
+
+ private:
+  std::deque<std::string> history;  // States after each edit.
+  size_t index = 0;                 // Index of the current state.
+};
@@ -1,3 +1,5 @@
+#include <cstdlib>
+
 #include "instruction.hpp"
 
 #include "analysis.hpp"
@@ -61,6 +63,7 @@ InstructionType Instruction::type() const {
 
     // Interrupt instructions.
     case Op::BRK:
+    case Op::COP:
       return InstructionType::Interrupt;
 
     // SEP/REP instructions.
@@ -124,12 +127,69 @@ bool Instruction::changesX() const {
          op == Op::TAX || op == Op::TSX || op == Op::TYX;
 }
 
+// Whether the instruction modifies Y.
+bool Instruction::changesY() const {
+  auto op = operation();
+  return op == Op::DEY || op == Op::INY || op == Op::LDY || op == Op::PLY ||
+         op == Op::TAY || op == Op::TXY;
+}
+
 // Whether the instruction modifies the stack pointer.
 bool Instruction::changesStackPointer() const {
   auto op = operation();
   return op == Op::TCS || op == Op::TXS;
 }
 
+// Whether the instruction modifies the interrupt disable flag.
+bool Instruction::changesInterruptFlag() const {
+  auto op = operation();
+  return op == Op::SEI || op == Op::CLI;
+}
+
+// Whether the instruction modifies the direct page register.
+bool Instruction::changesDirectPage() const {
+  auto op = operation();
+  return op == Op::TCD || op == Op::PLD;
+}
+
+// Whether the instruction modifies the carry or the emulation flag.
+bool Instruction::changesCarry() const {
+  auto op = operation();
+  return op == Op::CLC || op == Op::SEC || op == Op::XCE;
+}
+
+// Flags of the state register the instruction might modify.
+u8 Instruction::affectedFlags() const {
+  // SEP and REP only modify the flags in their argument.
+  if (isSepRep()) {
+    return *argument();
+  }
+  // BIT with an immediate argument only sets Z.
+  if (operation() == Op::BIT && addressMode() == AddressMode::ImmediateM) {
+    return FLAG_Z;
+  }
+  return AFFECTED_FLAGS[operation()];
+}
+
+// Whether the instruction accesses memory relative to the direct page.
+bool Instruction::isDirectPage() const {
+  switch (addressMode()) {
+    case AddressMode::DirectPage:
+    case AddressMode::DirectPageIndexedX:
+    case AddressMode::DirectPageIndexedY:
+    case AddressMode::DirectPageIndirect:
+    case AddressMode::DirectPageIndexedIndirect:
+    case AddressMode::DirectPageIndirectIndexed:
+    case AddressMode::DirectPageIndirectLong:
+    case AddressMode::DirectPageIndirectIndexedLong:
+    case AddressMode::PeiDirectPageIndirect:
+      return true;
+
+    default:
+      return false;
+  }
+}
+
 // Whether this is a control instruction.
 bool Instruction::isControl() const {
   switch (type()) {
@@ -148,11 +208,98 @@ bool Instruction::isSepRep() const {
   return type() == InstructionType::SepRep;
 }
 
+// Whether the argument is a 24-bit address.
+bool Instruction::isLongAddress() const {
+  auto mode = addressMode();
+  return mode == AddressMode::AbsoluteLong ||
+         mode == AddressMode::AbsoluteIndexedLong;
+}
+
+// Whether the instruction accesses memory in the bank pointed by DBR.
+bool Instruction::usesDataBank() const {
+  switch (addressMode()) {
+    case AddressMode::Absolute:
+    case AddressMode::AbsoluteIndexedX:
+    case AddressMode::AbsoluteIndexedY:
+      return !isControl();
+
+    default:
+      return false;
+  }
+}
+
 // Instruction size.
 size_t Instruction::size() const {
   return argumentSize() + 1;
 }
 
+// Estimated number of cycles taken by the instruction. Branches are
+// assumed not taken, block moves to move a single byte, and the low byte
+// of D to be zero. Page crossings are only counted with 16-bits indexes.
+size_t Instruction::cycles() const {
+  size_t cycles = BASE_CYCLES[opcode];
+  auto mode = addressMode();
+
+  switch (operation()) {
+    // Reads of 16-bits values take one more cycle, and so do page crossings.
+    case Op::ADC:
+    case Op::AND:
+    case Op::BIT:
+    case Op::CMP:
+    case Op::EOR:
+    case Op::LDA:
+    case Op::ORA:
+    case Op::SBC:
+      cycles += !state.m;
+      cycles += !state.x && (mode == AddressMode::AbsoluteIndexedX ||
+                             mode == AddressMode::AbsoluteIndexedY ||
+                             mode == AddressMode::DirectPageIndirectIndexed);
+      break;
+    case Op::LDX:
+    case Op::LDY:
+      cycles += !state.x;
+      cycles += !state.x && (mode == AddressMode::AbsoluteIndexedX ||
+                             mode == AddressMode::AbsoluteIndexedY);
+      break;
+
+    // Writes and stack operations of 16-bits values take one more cycle.
+    case Op::STA:
+    case Op::STZ:
+    case Op::PHA:
+    case Op::PLA:
+      cycles += !state.m;
+      break;
+    case Op::CPX:
+    case Op::CPY:
+    case Op::STX:
+    case Op::STY:
+    case Op::PHX:
+    case Op::PHY:
+    case Op::PLX:
+    case Op::PLY:
+      cycles += !state.x;
+      break;
+
+    // Read-modify-write of 16-bits values in memory take two more cycles.
+    case Op::ASL:
+    case Op::DEC:
+    case Op::INC:
+    case Op::LSR:
+    case Op::ROL:
+    case Op::ROR:
+    case Op::TRB:
+    case Op::TSB:
+      if (mode != AddressMode::ImpliedAccumulator) {
+        cycles += 2 * !state.m;
+      }
+      break;
+
+    default:
+      break;
+  }
+  return cycles;
+}
+
 // Instruction's argument size.
 size_t Instruction::argumentSize() const {
   if (auto size = ARGUMENT_SIZES[addressMode()]) {
@@ -198,6 +345,7 @@ optional<u24> Instruction::absoluteArgument() const {
     case AddressMode::ImmediateX:
     case AddressMode::Immediate8:
     case AddressMode::AbsoluteLong:
+    case AddressMode::AbsoluteIndexedLong:
       return arg;
 
     // Partially specified argument.
@@ -215,6 +363,74 @@ optional<u24> Instruction::absoluteArgument() const {
   };
 };
 
+// Address of the data accessed by the instruction, given the data bank.
+optional<u24> Instruction::dataTarget(optional<u8> dataBank) const {
+  if (usesDataBank()) {
+    if (!dataBank.has_value()) {
+      return nullopt;
+    }
+    return (*dataBank << 16) | *argument();
+  }
+  if (isLongAddress() && !isControl()) {
+    return argument();
+  }
+  return nullopt;
+}
+
+// Address of the data accessed by the instruction, if the data bank it
+// runs with is known.
+optional<u24> Instruction::dataAddress() const {
+  optional<u8> dataBank;
+  if (auto subroutine = this->subroutine()) {
+    auto search = subroutine->dataBanks.find(pc);
+    if (search != subroutine->dataBanks.end()) {
+      dataBank = search->second;
+    }
+  }
+  return dataTarget(dataBank);
+}
+
+// Hardware register accessed by the instruction, if any.
+optional<u16> Instruction::hardwareRegister() const {
+  optional<u24> address;
+  if (usesDataBank()) {
+    // Assume the registers are mapped when the data bank is unknown.
+    address = dataAddress().value_or(*argument());
+  } else if (isLongAddress() && !isControl()) {
+    address = argument();
+  }
+  // Registers are mirrored in banks $00-$3F and $80-$BF.
+  if (!address.has_value() || ((*address >> 16) & 0x7F) > 0x3F ||
+      !HARDWARE_REGISTERS.count(*address & 0xFFFF)) {
+    return nullopt;
+  }
+  return *address & 0xFFFF;
+}
+
+// WRAM address accessed by the instruction, if any. Direct page accesses
+// assume D = 0.
+optional<u24> Instruction::ramAddress() const {
+  if (isDirectPage()) {
+    return ROM::wramAddress(*argument());
+  } else if (usesDataBank()) {
+    // Assume the low WRAM is mapped when the data bank is unknown.
+    return ROM::wramAddress(dataAddress().value_or(*argument()));
+  } else if (isLongAddress() && !isControl()) {
+    return ROM::wramAddress(*argument());
+  }
+  return nullopt;
+}
+
+// Source and destination banks of a block move (MVN/MVP), if any.
+optional<pair<u8, u8>> Instruction::moveBanks() const {
+  if (addressMode() != AddressMode::Move) {
+    return nullopt;
+  }
+  // The destination bank is encoded first, the source bank second.
+  auto arg = *argument();
+  return pair<u8, u8>(arg >> 8, arg & 0xFF);
+}
+
 // Instruction argument as a label, if any.
 optional<Label> Instruction::argumentLabel() const {
   if (analysis != nullptr) {
@@ -223,6 +439,10 @@ optional<Label> Instruction::argumentLabel() const {
       if (isControl()) {
         return analysis->getLabel(*arg, subroutinePC);
       }
+      // Long data access to a labeled address.
+      if (isLongAddress()) {
+        return analysis->getLabel(*arg);
+      }
     }
   }
   return nullopt;
@@ -232,20 +452,68 @@ optional<Label> Instruction::argumentLabel() const {
 string Instruction::argumentString(bool aliased) const {
   if (aliased) {
     if (auto absArg = absoluteArgument()) {
+      auto index = addressMode() == AbsoluteIndexedLong ? ",x" : "";
+
       auto label = argumentLabel();
       if (label.has_value()) {
-        return label->asArgument();
+        return label->asArgument() + index;
+      }
+
+      // Unlabeled branch or jump target inside the subroutine (e.g. into a
+      // data block): show its distance from the instruction, in hex.
+      auto subroutine = this->subroutine();
+      if (subroutine != nullptr && isControl() &&
+          type() != InstructionType::Call && subroutine->spans(*absArg)) {
+        int offset = *absArg - pc;
+        return format(offset < 0 ? "$-%X" : "$+%X", abs(offset));
       }
 
       auto hwRegister = HARDWARE_REGISTERS.find(*absArg);
       if (hwRegister != HARDWARE_REGISTERS.end()) {
-        return "!" + hwRegister->second;
+        return "!" + hwRegister->second + index;
+      }
+
+      // Long access to a hardware register mirrored in another bank.
+      auto bank = *absArg >> 16;
+      if (isLongAddress() && (bank & 0x7F) <= 0x3F) {
+        hwRegister = HARDWARE_REGISTERS.find(*absArg & 0xFFFF);
+        if (hwRegister != HARDWARE_REGISTERS.end()) {
+          return format("!%s|$%02X0000", hwRegister->second.c_str(), bank) +
+                 index;
+        }
+      }
+    }
+
+    // Absolute access to a hardware register, through a data bank that maps
+    // them (assumed to be the case when the data bank is unknown).
+    if (usesDataBank()) {
+      auto address = dataAddress();
+      auto bank = address.has_value() ? *address >> 16 : 0x00;
+      auto hwRegister = HARDWARE_REGISTERS.find(*argument());
+      if ((bank & 0x7F) <= 0x3F && hwRegister != HARDWARE_REGISTERS.end()) {
+        auto mode = addressMode();
+        auto index = mode == AbsoluteIndexedX   ? ",x"
+                     : mode == AbsoluteIndexedY ? ",y"
+                                                : "";
+        return "!" + hwRegister->second + index;
       }
     }
   }
 
   auto arg = argument();
   auto sz = argumentSize();
+  auto value = arg.has_value() ? format("$%0" + to_string(sz * 2) + "X", *arg)
+                               : string();
+
+  // Named variable in WRAM.
+  if (aliased && analysis != nullptr) {
+    if (auto address = ramAddress()) {
+      auto search = analysis->ramLabels.find(*address);
+      if (search != analysis->ramLabels.end()) {
+        value = search->second;
+      }
+    }
+  }
 
   switch (addressMode()) {
     default:
@@ -256,7 +524,7 @@ string Instruction::argumentString(bool aliased) const {
     case ImmediateM:
     case ImmediateX:
     case Immediate8:
-      return format("#$%0" + to_string(sz * 2) + "X", *arg);
+      return "#" + value;
 
     case Relative:
     case RelativeLong:
@@ -264,35 +532,35 @@ string Instruction::argumentString(bool aliased) const {
     case Absolute:
     case AbsoluteLong:
     case StackAbsolute:
-      return format("$%0" + to_string(sz * 2) + "X", *arg);
+      return value;
 
     case DirectPageIndexedX:
     case AbsoluteIndexedX:
     case AbsoluteIndexedLong:
-      return format("$%0" + to_string(sz * 2) + "X,x", *arg);
+      return value + ",x";
 
     case DirectPageIndexedY:
     case AbsoluteIndexedY:
-      return format("$%0" + to_string(sz * 2) + "X,y", *arg);
+      return value + ",y";
 
     case DirectPageIndirect:
     case AbsoluteIndirect:
     case PeiDirectPageIndirect:
-      return format("($%0" + to_string(sz * 2) + "X)", *arg);
+      return "(" + value + ")";
 
     case DirectPageIndirectLong:
     case AbsoluteIndirectLong:
-      return format("[$%0" + to_string(sz * 2) + "X]", *arg);
+      return "[" + value + "]";
 
     case DirectPageIndexedIndirect:
     case AbsoluteIndexedIndirect:
-      return format("($%0" + to_string(sz * 2) + "X,x)", *arg);
+      return "(" + value + ",x)";
 
     case DirectPageIndirectIndexed:
-      return format("($%0" + to_string(sz * 2) + "X),y", *arg);
+      return "(" + value + "),y";
 
     case DirectPageIndirectIndexedLong:
-      return format("[$%0" + to_string(sz * 2) + "X],y", *arg);
+      return "[" + value + "],y";
 
     case StackRelative:
       return format("$%02X,s", *arg);
@@ -337,7 +605,8 @@ Subroutine* Instruction::subroutine() const {
   if (analysis == nullptr) {
     return nullptr;
   }
-  return &analysis->subroutines.at(subroutinePC);
+  auto search = analysis->subroutines.find(subroutinePC);
+  return search != analysis->subroutines.end() ? &search->second : nullptr;
 }
 
 // Return the instruction's comment.
@@ -372,5 +641,6 @@ size_t hash_value(const Instruction& instruction) {
   boost::hash_combine(seed, instruction.pc);
   boost::hash_combine(seed, instruction.subroutinePC);
   boost::hash_combine(seed, instruction.state.p);
+  boost::hash_combine(seed, instruction.state.e);
   return seed;
 }
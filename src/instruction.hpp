@@ -51,16 +51,46 @@ class Instruction {
   InstructionType type() const;     // Category of the instruction.
   bool changesA() const;            // Whether the instruction modifies A.
   bool changesX() const;            // Whether the instruction modifies X.
+  bool changesY() const;            // Whether the instruction modifies Y.
   // Whether the instruction modifies the stack pointer.
   bool changesStackPointer() const;
+  // Whether the instruction modifies the interrupt disable flag.
+  bool changesInterruptFlag() const;
+  // Whether the instruction modifies the direct page register.
+  bool changesDirectPage() const;
+  // Whether the instruction modifies the carry or the emulation flag.
+  bool changesCarry() const;
+  // Flags of the state register the instruction might modify.
+  u8 affectedFlags() const;
+  // Whether the instruction accesses memory relative to the direct page.
+  bool isDirectPage() const;
   bool isControl() const;       // Whether this is a control instruction.
   bool isSepRep() const;        // Whether this is a SEP/REP instruction.
+  bool isLongAddress() const;   // Whether the argument is a 24-bit address.
+  // Whether the instruction accesses memory in the bank pointed by DBR.
+  bool usesDataBank() const;
   size_t size() const;          // Instruction size.
+  // Estimated number of cycles taken by the instruction. Branches are
+  // assumed not taken, block moves to move a single byte, and the low byte
+  // of D to be zero. Page crossings are only counted with 16-bits indexes.
+  size_t cycles() const;
   size_t argumentSize() const;  // Instruction's argument size.
   // Instruction's argument, if any.
   std::optional<u24> argument() const;
   // Instruction's argument as an absolute value, if possible.
   std::optional<u24> absoluteArgument() const;
+  // Address of the data accessed by the instruction, given the data bank.
+  std::optional<u24> dataTarget(std::optional<u8> dataBank) const;
+  // Address of the data accessed by the instruction, if the data bank it
+  // runs with is known.
+  std::optional<u24> dataAddress() const;
+  // Source and destination banks of a block move (MVN/MVP), if any.
+  std::optional<std::pair<u8, u8>> moveBanks() const;
+  // Hardware register accessed by the instruction, if any.
+  std::optional<u16> hardwareRegister() const;
+  // WRAM address accessed by the instruction, if any. Direct page accesses
+  // assume D = 0.
+  std::optional<u24> ramAddress() const;
   // Instruction argument as a label, if any.
   std::optional<Label> argumentLabel() const;
   // Instruction's argument as a string.
@@ -0,0 +1,49 @@
+#include <algorithm>
+#include <cctype>
+
+#include "jumptable.hpp"
+
+using namespace std;
+
+// Parse a range of offsets in hexadecimal: `X` (X alone), `X..Y` (Y
+// excluded) or `X..=Y` (Y included). Return it as an inclusive range.
+optional<pair<u16, u16>> JumpTable::parseRange(const string& text) {
+  // Offsets have an optional `$` prefix.
+  auto parseOffset = [](string offset) -> optional<u16> {
+    if (!offset.empty() && offset[0] == '$') {
+      offset.erase(0, 1);
+    }
+    if (offset.empty() || offset.size() > 4 ||
+        !all_of(offset.begin(), offset.end(), ::isxdigit)) {
+      return nullopt;
+    }
+    return stoul(offset, nullptr, 16);
+  };
+
+  auto separator = text.find("..");
+  if (separator == string::npos) {
+    auto offset = parseOffset(text);
+    if (!offset.has_value()) {
+      return nullopt;
+    }
+    return pair{*offset, *offset};
+  }
+
+  auto inclusive = text.compare(separator, 3, "..=") == 0;
+  auto start = parseOffset(text.substr(0, separator));
+  auto end = parseOffset(text.substr(separator + (inclusive ? 3 : 2)));
+  if (!start.has_value() || !end.has_value()) {
+    return nullopt;
+  }
+  // Exclusive ranges must not be empty.
+  if (!inclusive) {
+    if (*end == 0) {
+      return nullopt;
+    }
+    *end -= 1;
+  }
+  if (*start > *end) {
+    return nullopt;
+  }
+  return pair{*start, *end};
+}
@@ -1,8 +1,12 @@
 #pragma once
 
 #include <boost/serialization/map.hpp>
+#include <boost/serialization/version.hpp>
+#include <cstddef>
 #include <map>
 #include <optional>
+#include <string>
+#include <utility>
 
 #include "boost_serialization_std_optional.hpp"
 #include "types.hpp"
@@ -12,6 +16,7 @@ enum class JumpTableStatus {
   Unknown,
   Partial,
   Complete,
+  Emulated,  // Resolved by the analysis, from the known values of X.
 };
 
 // Structure representing a jump table.
@@ -26,12 +31,26 @@ struct JumpTable {
     }
   };
 
+  // Parse a range of offsets in hexadecimal: `X` (X alone), `X..Y` (Y
+  // excluded) or `X..=Y` (Y included). Return it as an inclusive range.
+  static std::optional<std::pair<u16, u16>> parseRange(
+      const std::string& text);
+
   JumpTableStatus status;
   std::map<std::optional<u16>, InstructionPC> targets;
+  // Whether the table was guessed by the analysis, and should be reviewed.
+  bool guessed = false;
+
+  // Maximum number of entries of a guessed table.
+  static const std::size_t MAX_GUESSED_ENTRIES = 128;
 
   template <class Archive>
-  void serialize(Archive& ar, const unsigned int) {
+  void serialize(Archive& ar, const unsigned int version) {
     ar& status;
     ar& targets;
+    if (version >= 1) {
+      ar& guessed;
+    }
   }
 };
+BOOST_CLASS_VERSION(JumpTable, 1)
@@ -0,0 +1,274 @@
+#include "migration.hpp"
+
+#include "analysis.hpp"
+#include "instruction.hpp"
+#include "subroutine.hpp"
+
+using namespace std;
+
+// Constructor.
+Migration::Migration(const Analysis* oldAnalysis, Analysis* newAnalysis)
+    : oldAnalysis{oldAnalysis}, newAnalysis{newAnalysis} {}
+
+// Relocate the annotations of the old analysis into the new one.
+void Migration::run() {
+  issues.clear();
+  relocations.clear();
+
+  matchSubroutines();
+  rebaseAnnotations();
+}
+
+// Return the new address of an instruction of the old analysis, if known.
+optional<InstructionPC> Migration::relocate(InstructionPC pc) const {
+  auto search = relocations.find(pc);
+  if (search == relocations.end()) {
+    return nullopt;
+  }
+  return search->second;
+}
+
+// Return the byte pattern of a subroutine, and the address it starts at.
+pair<Migration::Pattern, InstructionPC> Migration::fingerprint(
+    const Subroutine& subroutine) const {
+  auto& instructions = subroutine.instructions;
+  if (instructions.empty()) {
+    return {{}, subroutine.pc};
+  }
+
+  auto start = instructions.begin()->first;
+  auto last = instructions.rbegin()->second;
+  Pattern pattern(last->pc + last->size() - start);
+
+  // Bytes between instructions (i.e. data) can be anything.
+  for (auto& [pc, instruction] : instructions) {
+    for (size_t i = 0; i < instruction->size(); i++) {
+      pattern[pc - start + i] = oldAnalysis->rom.readByte(pc + i);
+    }
+  }
+  // Operands pointing to code or data that might have moved are wildcarded.
+  for (auto& [pc, instruction] : instructions) {
+    if (isRelocatable(instruction)) {
+      for (size_t i = 1; i < instruction->size(); i++) {
+        pattern[pc - start + i] = nullopt;
+      }
+    }
+  }
+
+  return {pattern, start};
+}
+
+// Find all the occurrences of a pattern in a bank of the new ROM.
+vector<InstructionPC> Migration::search(const Pattern& pattern, u8 bank) const {
+  vector<InstructionPC> matches;
+  auto& rom = newAnalysis->rom;
+
+  for (u24 offset = 0; offset + pattern.size() <= 0x10000; offset++) {
+    auto address = (bank << 16) | offset;
+    if (!rom.isROM(address) || !rom.isROM(address + pattern.size() - 1)) {
+      continue;
+    }
+
+    bool match = true;
+    for (size_t i = 0; i < pattern.size() && match; i++) {
+      auto byte = pattern[i];
+      match = !byte.has_value() || *byte == rom.readByte(address + i);
+    }
+    if (match) {
+      matches.push_back(address);
+    }
+  }
+
+  return matches;
+}
+
+// Whether an instruction's operand is an address that might have moved.
+bool Migration::isRelocatable(const Instruction* instruction) {
+  switch (instruction->addressMode()) {
+    case AddressMode::Absolute:
+    case AddressMode::AbsoluteIndexedIndirect:
+    case AddressMode::AbsoluteIndexedLong:
+    case AddressMode::AbsoluteIndexedX:
+    case AddressMode::AbsoluteIndexedY:
+    case AddressMode::AbsoluteIndirect:
+    case AddressMode::AbsoluteIndirectLong:
+    case AddressMode::AbsoluteLong:
+    case AddressMode::StackAbsolute:
+      return true;
+
+    default:
+      return false;
+  }
+}
+
+// Match the subroutines of the old analysis against the new ROM.
+void Migration::matchSubroutines() {
+  for (auto& [subroutinePC, subroutine] : oldAnalysis->subroutines) {
+    auto [pattern, start] = fingerprint(subroutine);
+    if (pattern.empty()) {
+      report(MigrationProblem::Unmatched, "subroutine", subroutinePC);
+      continue;
+    }
+
+    auto matches = search(pattern, start >> 16);
+    if (matches.empty()) {
+      report(MigrationProblem::Unmatched, "subroutine", subroutinePC);
+      continue;
+    } else if (matches.size() > 1) {
+      report(MigrationProblem::Ambiguous, "subroutine", subroutinePC);
+      continue;
+    }
+
+    // Instructions shared with other subroutines must move consistently.
+    auto newStart = matches.front();
+    for (auto& [pc, instruction] : subroutine.instructions) {
+      auto newPC = newStart + (pc - start);
+      auto [relocation, inserted] = relocations.try_emplace(pc, newPC);
+      if (!inserted && relocation->second != newPC) {
+        report(MigrationProblem::Collision, "subroutine", subroutinePC);
+        break;
+      }
+    }
+  }
+}
+
+// Rebase the annotations using the relocations found.
+void Migration::rebaseAnnotations() {
+  for (auto entryPoint : oldAnalysis->entryPoints) {
+    auto pc = relocate(entryPoint.pc);
+    if (!pc.has_value()) {
+      // Entry points coming from the ROM header are already in place.
+      if (newAnalysis->entryPoints.count(entryPoint) == 0) {
+        report(MigrationProblem::Unmatched, "entry point", entryPoint.pc);
+      }
+      continue;
+    }
+
+    auto oldPC = entryPoint.pc;
+    entryPoint.pc = *pc;
+    auto [existing, inserted] = newAnalysis->entryPoints.insert(entryPoint);
+    if (!inserted && existing->label != entryPoint.label) {
+      report(MigrationProblem::Collision, "entry point", oldPC);
+    }
+  }
+
+  for (auto& [pc, comment] : oldAnalysis->comments) {
+    auto newPC = relocate(pc);
+    if (!newPC.has_value()) {
+      report(MigrationProblem::Unmatched, "comment", pc);
+      continue;
+    }
+
+    auto [existing, inserted] =
+        newAnalysis->comments.try_emplace(*newPC, comment);
+    if (!inserted && existing->second != comment) {
+      report(MigrationProblem::Collision, "comment", pc);
+    }
+  }
+
+  for (auto& [pcPair, label] : oldAnalysis->customLabels) {
+    auto newPC = relocate(pcPair.first);
+    auto newSubroutinePC = relocate(pcPair.second);
+    if (!newPC.has_value() || !newSubroutinePC.has_value()) {
+      report(MigrationProblem::Unmatched, "label", pcPair.first);
+      continue;
+    }
+
+    auto [existing, inserted] = newAnalysis->customLabels.try_emplace(
+        {*newPC, *newSubroutinePC}, label);
+    if (!inserted && existing->second != label) {
+      report(MigrationProblem::Collision, "label", pcPair.first);
+    }
+  }
+
+  for (auto& [pc, note] : oldAnalysis->bookmarks) {
+    auto newPC = relocate(pc);
+    if (!newPC.has_value()) {
+      report(MigrationProblem::Unmatched, "bookmark", pc);
+      continue;
+    }
+
+    auto [existing, inserted] =
+        newAnalysis->bookmarks.try_emplace(*newPC, note);
+    if (!inserted && existing->second != note) {
+      report(MigrationProblem::Collision, "bookmark", pc);
+    }
+  }
+
+  for (auto& [start, block] : oldAnalysis->dataBlocks) {
+    auto newStart = relocate(start);
+    if (!newStart.has_value()) {
+      report(MigrationProblem::Unmatched, "data block", start);
+      continue;
+    }
+
+    // The block keeps its size, and must not overlap the existing ones.
+    auto newEnd = *newStart + (block.end - block.start);
+    if (!newAnalysis->addDataBlock(*newStart, newEnd, block.type)) {
+      auto existing = newAnalysis->dataBlocks.find(*newStart);
+      if (existing == newAnalysis->dataBlocks.end() ||
+          existing->second.end != newEnd ||
+          existing->second.type != block.type) {
+        report(MigrationProblem::Collision, "data block", start);
+      }
+    }
+  }
+
+  for (auto& [pcPair, assertion] : oldAnalysis->assertions) {
+    auto newPC = relocate(pcPair.first);
+    auto newSubroutinePC = relocate(pcPair.second);
+    if (!newPC.has_value() || !newSubroutinePC.has_value()) {
+      report(MigrationProblem::Unmatched, "assertion", pcPair.first);
+      continue;
+    }
+
+    auto [existing, inserted] = newAnalysis->assertions.try_emplace(
+        {*newPC, *newSubroutinePC}, assertion);
+    auto& other = existing->second;
+    if (!inserted && (other.type != assertion.type ||
+                      !(other.stateChange == assertion.stateChange))) {
+      report(MigrationProblem::Collision, "assertion", pcPair.first);
+    }
+  }
+
+  for (auto& [pc, jumpTable] : oldAnalysis->jumpTables) {
+    // Unknown and emulated jump tables are rediscovered by the analysis.
+    if (jumpTable.status == JumpTableStatus::Unknown ||
+        jumpTable.status == JumpTableStatus::Emulated) {
+      continue;
+    }
+
+    JumpTable newJumpTable{jumpTable.status, {}, jumpTable.guessed};
+    auto newPC = relocate(pc);
+    for (auto& [index, target] : jumpTable.targets) {
+      auto newTarget = relocate(target);
+      if (!newTarget.has_value()) {
+        newPC = nullopt;
+        break;
+      }
+      newJumpTable.targets[index] = *newTarget;
+    }
+    if (!newPC.has_value()) {
+      report(MigrationProblem::Unmatched, "jump table", pc);
+      continue;
+    }
+
+    auto [existing, inserted] =
+        newAnalysis->jumpTables.try_emplace(*newPC, newJumpTable);
+    if (!inserted) {
+      if (existing->second.status != JumpTableStatus::Unknown &&
+          existing->second.targets != newJumpTable.targets) {
+        report(MigrationProblem::Collision, "jump table", pc);
+      } else {
+        existing->second = newJumpTable;
+      }
+    }
+  }
+}
+
+// Record an item that requires manual handling.
+void Migration::report(MigrationProblem problem,
+                       string item,
+                       InstructionPC pc) {
+  issues.push_back({problem, item, pc});
+}
@@ -0,0 +1,72 @@
+#pragma once
+
+#include <optional>
+#include <string>
+#include <unordered_map>
+#include <utility>
+#include <vector>
+
+#include "types.hpp"
+
+class Analysis;
+class Instruction;
+struct Subroutine;
+
+// Reasons why an item could not be migrated.
+enum class MigrationProblem {
+  Unmatched,  // The code could not be found in the new ROM.
+  Ambiguous,  // The code was found in multiple places.
+  Collision,  // The new location already holds a different item.
+};
+
+// Item of the old analysis that requires manual handling.
+struct MigrationIssue {
+  MigrationProblem problem;  // What went wrong.
+  std::string item;          // Kind of item (subroutine, label, ...).
+  InstructionPC pc;          // Address of the item in the old analysis.
+};
+
+/**
+ * Transfer the annotations of an analysis to a revision of its ROM,
+ * locating each subroutine in the new ROM through its byte pattern.
+ */
+class Migration {
+ public:
+  // Constructor.
+  Migration(const Analysis* oldAnalysis, Analysis* newAnalysis);
+
+  // Relocate the annotations of the old analysis into the new one.
+  void run();
+
+  // Return the new address of an instruction of the old analysis, if known.
+  std::optional<InstructionPC> relocate(InstructionPC pc) const;
+
+  // Items that require manual handling.
+  std::vector<MigrationIssue> issues;
+
+ private:
+  // Byte pattern of a subroutine (nullopt matches any byte).
+  typedef std::vector<std::optional<u8>> Pattern;
+
+  // Return the byte pattern of a subroutine, and the address it starts at.
+  std::pair<Pattern, InstructionPC> fingerprint(
+      const Subroutine& subroutine) const;
+  // Find all the occurrences of a pattern in a bank of the new ROM.
+  std::vector<InstructionPC> search(const Pattern& pattern, u8 bank) const;
+  // Whether an instruction's operand is an address that might have moved.
+  static bool isRelocatable(const Instruction* instruction);
+
+  // Match the subroutines of the old analysis against the new ROM.
+  void matchSubroutines();
+  // Rebase the annotations using the relocations found.
+  void rebaseAnnotations();
+
+  // Record an item that requires manual handling.
+  void report(MigrationProblem problem, std::string item, InstructionPC pc);
+
+  const Analysis* oldAnalysis;  // Analysis of the old ROM.
+  Analysis* newAnalysis;        // Analysis of the new ROM.
+
+  // Map from old to new addresses of the matched instructions.
+  std::unordered_map<InstructionPC, InstructionPC> relocations;
+};
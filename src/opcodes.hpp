@@ -3,6 +3,8 @@
 #include <optional>
 #include <string>
 
+#include "types.hpp"
+
 // Memory addressing modes.
 enum AddressMode {
   Implied,
@@ -144,6 +146,118 @@ inline const std::string OPCODE_NAMES[] = {
     "wai", "wdm", "xba", "xce",
 };
 
+// Flags of the state register (P).
+enum Flag : u8 {
+  FLAG_C = 0x01,  // Carry.
+  FLAG_Z = 0x02,  // Zero.
+  FLAG_I = 0x04,  // Interrupt disable.
+  FLAG_D = 0x08,  // Decimal.
+  FLAG_X = 0x10,  // Index size.
+  FLAG_M = 0x20,  // Accumulator size.
+  FLAG_V = 0x40,  // Overflow.
+  FLAG_N = 0x80,  // Negative.
+};
+
+// Flags of the state register each operation might modify. SEP and REP
+// can modify any flag depending on their argument, PLP and RTI restore all
+// of them, and XCE swaps the carry with the emulation flag (which forces
+// 8-bits registers). See Instruction::affectedFlags() for the flags
+// modified by a specific instruction.
+inline const u8 AFFECTED_FLAGS[] = {
+    FLAG_N | FLAG_V | FLAG_Z | FLAG_C,  // ADC
+    FLAG_N | FLAG_Z,                    // AND
+    FLAG_N | FLAG_Z | FLAG_C,           // ASL
+    0,                                  // BCC
+    0,                                  // BCS
+    0,                                  // BEQ
+    FLAG_N | FLAG_V | FLAG_Z,           // BIT
+    0,                                  // BMI
+    0,                                  // BNE
+    0,                                  // BPL
+    0,                                  // BRA
+    FLAG_D | FLAG_I,                    // BRK
+    0,                                  // BRL
+    0,                                  // BVC
+    0,                                  // BVS
+    FLAG_C,                             // CLC
+    FLAG_D,                             // CLD
+    FLAG_I,                             // CLI
+    FLAG_V,                             // CLV
+    FLAG_N | FLAG_Z | FLAG_C,           // CMP
+    FLAG_D | FLAG_I,                    // COP
+    FLAG_N | FLAG_Z | FLAG_C,           // CPX
+    FLAG_N | FLAG_Z | FLAG_C,           // CPY
+    FLAG_N | FLAG_Z,                    // DEC
+    FLAG_N | FLAG_Z,                    // DEX
+    FLAG_N | FLAG_Z,                    // DEY
+    FLAG_N | FLAG_Z,                    // EOR
+    FLAG_N | FLAG_Z,                    // INC
+    FLAG_N | FLAG_Z,                    // INX
+    FLAG_N | FLAG_Z,                    // INY
+    0,                                  // JML
+    0,                                  // JMP
+    0,                                  // JSL
+    0,                                  // JSR
+    FLAG_N | FLAG_Z,                    // LDA
+    FLAG_N | FLAG_Z,                    // LDX
+    FLAG_N | FLAG_Z,                    // LDY
+    FLAG_N | FLAG_Z | FLAG_C,           // LSR
+    0,                                  // MVN
+    0,                                  // MVP
+    0,                                  // NOP
+    FLAG_N | FLAG_Z,                    // ORA
+    0,                                  // PEA
+    0,                                  // PEI
+    0,                                  // PER
+    0,                                  // PHA
+    0,                                  // PHB
+    0,                                  // PHD
+    0,                                  // PHK
+    0,                                  // PHP
+    0,                                  // PHX
+    0,                                  // PHY
+    FLAG_N | FLAG_Z,                    // PLA
+    FLAG_N | FLAG_Z,                    // PLB
+    FLAG_N | FLAG_Z,                    // PLD
+    0xFF,                               // PLP
+    FLAG_N | FLAG_Z,                    // PLX
+    FLAG_N | FLAG_Z,                    // PLY
+    0xFF,                               // REP
+    FLAG_N | FLAG_Z | FLAG_C,           // ROL
+    FLAG_N | FLAG_Z | FLAG_C,           // ROR
+    0xFF,                               // RTI
+    0,                                  // RTL
+    0,                                  // RTS
+    FLAG_N | FLAG_V | FLAG_Z | FLAG_C,  // SBC
+    FLAG_C,                             // SEC
+    FLAG_D,                             // SED
+    FLAG_I,                             // SEI
+    0xFF,                               // SEP
+    0,                                  // STA
+    0,                                  // STP
+    0,                                  // STX
+    0,                                  // STY
+    0,                                  // STZ
+    FLAG_N | FLAG_Z,                    // TAX
+    FLAG_N | FLAG_Z,                    // TAY
+    FLAG_N | FLAG_Z,                    // TCD
+    0,                                  // TCS
+    FLAG_N | FLAG_Z,                    // TDC
+    FLAG_Z,                             // TRB
+    FLAG_Z,                             // TSB
+    FLAG_N | FLAG_Z,                    // TSC
+    FLAG_N | FLAG_Z,                    // TSX
+    FLAG_N | FLAG_Z,                    // TXA
+    0,                                  // TXS
+    FLAG_N | FLAG_Z,                    // TXY
+    FLAG_N | FLAG_Z,                    // TYA
+    FLAG_N | FLAG_Z,                    // TYX
+    0,                                  // WAI
+    0,                                  // WDM
+    FLAG_N | FLAG_Z,                    // XBA
+    FLAG_M | FLAG_X | FLAG_C,           // XCE
+};
+
 // Size of the argument for each addressing mode.
 // {} means the size depends on the state register.
 inline const std::optional<int> ARGUMENT_SIZES[] = {
@@ -177,6 +291,28 @@ inline const std::optional<int> ARGUMENT_SIZES[] = {
     1,   // PeiDirectPageIndirect
 };
 
+// Base number of cycles of each opcode in native mode, with 8-bits A and
+// X, and the low byte of D set to zero. See Instruction::cycles() for the
+// penalties of the other cases.
+inline const u8 BASE_CYCLES[] = {
+    8, 6, 8, 4, 5, 3, 5, 6, 3, 2, 2, 4, 6, 4, 6, 5,  // $00-$0F
+    2, 5, 5, 7, 5, 4, 6, 6, 2, 4, 2, 2, 6, 4, 7, 5,  // $10-$1F
+    6, 6, 8, 4, 3, 3, 5, 6, 4, 2, 2, 5, 4, 4, 6, 5,  // $20-$2F
+    2, 5, 5, 7, 4, 4, 6, 6, 2, 4, 2, 2, 4, 4, 7, 5,  // $30-$3F
+    7, 6, 2, 4, 7, 3, 5, 6, 3, 2, 2, 3, 3, 4, 6, 5,  // $40-$4F
+    2, 5, 5, 7, 7, 4, 6, 6, 2, 4, 3, 2, 4, 4, 7, 5,  // $50-$5F
+    6, 6, 6, 4, 3, 3, 5, 6, 4, 2, 2, 6, 5, 4, 6, 5,  // $60-$6F
+    2, 5, 5, 7, 4, 4, 6, 6, 2, 4, 4, 2, 6, 4, 7, 5,  // $70-$7F
+    3, 6, 4, 4, 3, 3, 3, 6, 2, 2, 2, 3, 4, 4, 4, 5,  // $80-$8F
+    2, 6, 5, 7, 4, 4, 4, 6, 2, 5, 2, 2, 4, 5, 5, 5,  // $90-$9F
+    2, 6, 2, 4, 3, 3, 3, 6, 2, 2, 2, 4, 4, 4, 4, 5,  // $A0-$AF
+    2, 5, 5, 7, 4, 4, 4, 6, 2, 4, 2, 2, 4, 4, 4, 5,  // $B0-$BF
+    2, 6, 3, 4, 3, 3, 5, 6, 2, 2, 2, 3, 4, 4, 6, 5,  // $C0-$CF
+    2, 5, 5, 7, 6, 4, 6, 6, 2, 4, 3, 3, 6, 4, 7, 5,  // $D0-$DF
+    2, 6, 3, 4, 3, 3, 5, 6, 2, 2, 2, 3, 4, 4, 6, 5,  // $E0-$EF
+    2, 5, 5, 7, 5, 4, 6, 6, 2, 4, 4, 2, 8, 4, 7, 5,  // $F0-$FF
+};
+
 // All 65c816 opcodes expressed as a combination
 // of operations and addressing modes.
 inline const std::pair<Op, AddressMode> OPCODE_TABLE[] = {
@@ -0,0 +1,206 @@
+#include "preservation.hpp"
+
+#include "analysis.hpp"
+#include "instruction.hpp"
+#include "subroutine.hpp"
+
+using namespace std;
+
+// Return a human-readable name for a preservation verdict.
+string preservationName(Preservation preservation) {
+  switch (preservation) {
+    case Preservation::Unknown:
+      return "unknown";
+    case Preservation::Preserved:
+      return "preserved";
+    case Preservation::Clobbered:
+      return "clobbered";
+  }
+  __builtin_unreachable();
+}
+
+// Constructor.
+PreservationAnalysis::PreservationAnalysis(Analysis* analysis)
+    : analysis{analysis} {}
+
+// Compute the preservation of X and Y for every subroutine.
+void PreservationAnalysis::run() {
+  for (auto& [pc, subroutine] : analysis->subroutines) {
+    subroutine.xPreservation = preservation(pc, IndexRegister::X);
+    subroutine.yPreservation = preservation(pc, IndexRegister::Y);
+  }
+}
+
+// Return the preservation of a register by a subroutine.
+Preservation PreservationAnalysis::preservation(SubroutinePC pc,
+                                                IndexRegister reg) {
+  pair key{pc, reg};
+  auto search = verdicts.find(key);
+  if (search != verdicts.end()) {
+    return search->second;
+  }
+
+  // Recursive subroutine: we can't say anything about it.
+  if (!exploring.insert(key).second) {
+    return Preservation::Unknown;
+  }
+  auto verdict = explore(analysis->subroutines.at(pc), reg);
+  exploring.erase(key);
+
+  verdicts[key] = verdict;
+  return verdict;
+}
+
+// Explore all the paths of a subroutine.
+Preservation PreservationAnalysis::explore(const Subroutine& subroutine,
+                                           IndexRegister reg) {
+  bool clobbered = false;
+  bool unknown = false;
+
+  auto savesRegister = reg == IndexRegister::X ? Op::PHX : Op::PHY;
+  auto restoresRegister = reg == IndexRegister::X ? Op::PLX : Op::PLY;
+
+  set<pair<InstructionPC, PathState>> visited;
+  vector<pair<InstructionPC, PathState>> worklist = {
+      {subroutine.pc, {false, {}}}};
+
+  // Apply the verdict of a called subroutine to the current path.
+  auto applyCallee = [&](SubroutinePC pc, bool& modified) {
+    switch (preservation(pc, reg)) {
+      case Preservation::Clobbered:
+        modified = true;
+        break;
+      case Preservation::Unknown:
+        unknown = true;
+        break;
+      case Preservation::Preserved:
+        break;
+    }
+  };
+
+  while (!worklist.empty()) {
+    auto [pc, pathState] = worklist.back();
+    worklist.pop_back();
+    if (!visited.insert({pc, pathState}).second) {
+      continue;
+    }
+
+    // The emulation didn't reach this instruction.
+    auto search = subroutine.instructions.find(pc);
+    if (search == subroutine.instructions.end()) {
+      unknown = true;
+      continue;
+    }
+    if (subroutine.unknownStateChanges.count(pc)) {
+      unknown = true;
+    }
+
+    auto instruction = search->second;
+    auto op = instruction->operation();
+    auto& [modified, stack] = pathState;
+
+    // Track the content of the stack, pairing saves and restores.
+    if (op == savesRegister) {
+      stack.push_back(modified ? Token::Clobbered : Token::Saved);
+    } else if (op == restoresRegister ||
+               instruction->type() == InstructionType::Pop) {
+      if (stack.empty()) {
+        // Pulling values pushed by the caller.
+        unknown = true;
+        continue;
+      }
+      auto token = stack.back();
+      stack.pop_back();
+      if (op == restoresRegister) {
+        modified = token != Token::Saved;
+      }
+    } else if (instruction->type() == InstructionType::Push) {
+      stack.push_back(Token::Other);
+    } else if (modifies(instruction, reg)) {
+      modified = true;
+    }
+
+    if (stack.size() > MAX_STACK_DEPTH) {
+      unknown = true;
+      continue;
+    }
+
+    auto next = pc + instruction->size();
+    auto targets = analysis->followTargets(pc, subroutine.pc);
+
+    switch (instruction->type()) {
+      case InstructionType::Branch:
+        for (auto& [target, targetSubroutine] : targets) {
+          worklist.push_back({target, pathState});
+        }
+        if (op != Op::BRA && op != Op::BRL) {
+          worklist.push_back({next, pathState});
+        }
+        break;
+
+      case InstructionType::Call:
+        if (targets.empty()) {
+          unknown = true;
+        }
+        for (auto& [target, targetSubroutine] : targets) {
+          applyCallee(targetSubroutine, modified);
+        }
+        worklist.push_back({next, pathState});
+        break;
+
+      case InstructionType::Jump:
+        if (targets.empty()) {
+          unknown = true;
+        }
+        for (auto& [target, targetSubroutine] : targets) {
+          if (targetSubroutine == subroutine.pc) {
+            worklist.push_back({target, pathState});
+          } else {
+            // Tail call: the other subroutine returns on our behalf.
+            auto tailModified = modified;
+            applyCallee(targetSubroutine, tailModified);
+            clobbered |= tailModified;
+            unknown |= !stack.empty();
+          }
+        }
+        break;
+
+      case InstructionType::Return:
+        if (!stack.empty()) {
+          // Returning through a manipulated stack.
+          unknown = true;
+        } else if (modified) {
+          clobbered = true;
+        }
+        break;
+
+      case InstructionType::Interrupt:
+        unknown = true;
+        break;
+
+      default:
+        worklist.push_back({next, pathState});
+        break;
+    }
+  }
+
+  if (clobbered) {
+    return Preservation::Clobbered;
+  } else if (unknown) {
+    return Preservation::Unknown;
+  } else {
+    return Preservation::Preserved;
+  }
+}
+
+// Whether an instruction modifies the given register.
+bool PreservationAnalysis::modifies(const Instruction* instruction,
+                                    IndexRegister reg) {
+  auto op = instruction->operation();
+  // Block moves use both X and Y as pointers.
+  if (op == Op::MVN || op == Op::MVP) {
+    return true;
+  }
+  return reg == IndexRegister::X ? instruction->changesX()
+                                 : instruction->changesY();
+}
@@ -0,0 +1,73 @@
+#pragma once
+
+#include <map>
+#include <set>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "types.hpp"
+
+class Analysis;
+class Instruction;
+struct Subroutine;
+
+// Index registers whose preservation is tracked.
+enum class IndexRegister {
+  X,
+  Y,
+};
+
+// Whether a subroutine preserves the value of a register for its caller.
+enum class Preservation {
+  Unknown,    // Not all the paths could be analyzed.
+  Preserved,  // The register is untouched, or saved and restored.
+  Clobbered,  // At least one path returns with a modified register.
+};
+
+// Return a human-readable name for a preservation verdict.
+std::string preservationName(Preservation preservation);
+
+/**
+ * Determine which subroutines preserve the index registers across all the
+ * analyzed paths, pairing pushes and pulls through a model of the stack.
+ */
+class PreservationAnalysis {
+ public:
+  // Constructor.
+  PreservationAnalysis(Analysis* analysis);
+
+  // Compute the preservation of X and Y for every subroutine.
+  void run();
+
+  // Maximum number of stack entries tracked along a path.
+  static const std::size_t MAX_STACK_DEPTH = 32;
+
+ private:
+  // Entry of the stack model.
+  enum class Token {
+    Other,      // Unrelated value.
+    Saved,      // Register saved while unmodified.
+    Clobbered,  // Register saved after having been modified.
+  };
+
+  // Abstract state along a path: whether the register
+  // has been modified, and the content of the stack.
+  typedef std::pair<bool, std::vector<Token>> PathState;
+
+  // Return the preservation of a register by a subroutine.
+  Preservation preservation(SubroutinePC pc, IndexRegister reg);
+  // Explore all the paths of a subroutine.
+  Preservation explore(const Subroutine& subroutine, IndexRegister reg);
+
+  // Whether an instruction modifies the given register.
+  static bool modifies(const Instruction* instruction, IndexRegister reg);
+
+  // Pointer to the analysis.
+  Analysis* analysis;
+
+  // Verdicts computed so far.
+  std::map<std::pair<SubroutinePC, IndexRegister>, Preservation> verdicts;
+  // Subroutines being explored (used to detect recursion).
+  std::set<std::pair<SubroutinePC, IndexRegister>> exploring;
+};
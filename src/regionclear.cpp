@@ -0,0 +1,174 @@
+#include "regionclear.hpp"
+
+#include "analysis.hpp"
+#include "instruction.hpp"
+#include "subroutine.hpp"
+#include "utils.hpp"
+
+using namespace std;
+
+// Return a human-readable description of a region clear.
+string regionClearDescription(const RegionClear& clear) {
+  auto digits = to_string(clear.addressSize * 2);
+  auto region = format("$%0" + digits + "X..$%0" + digits + "X", clear.start,
+                       clear.end);
+  if (!clear.value.has_value()) {
+    return "Clears " + region;
+  }
+  return "Fills " + region + format(" with #$%02X", *clear.value);
+}
+
+// Constructor.
+RegionClearAnalysis::RegionClearAnalysis(Analysis* analysis)
+    : analysis{analysis} {}
+
+// Find the region clears of every subroutine.
+void RegionClearAnalysis::run() {
+  for (auto& [pc, subroutine] : analysis->subroutines) {
+    for (auto& [instructionPC, instruction] : subroutine.instructions) {
+      if (instruction->type() != InstructionType::Branch) {
+        continue;
+      }
+      if (auto clear = match(subroutine, instruction)) {
+        subroutine.regionClears[clear->first] = clear->second;
+      }
+    }
+  }
+}
+
+// Return the region cleared by the loop closed by a branch, if any.
+optional<pair<InstructionPC, RegionClear>> RegionClearAnalysis::match(
+    const Subroutine& subroutine,
+    const Instruction* branch) {
+  auto op = branch->operation();
+  auto target = branch->absoluteArgument();
+  if ((op != Op::BPL && op != Op::BNE) || !target.has_value() ||
+      *target >= branch->pc) {
+    return nullopt;
+  }
+
+  // The body of the loop must be a straight sequence of instructions.
+  vector<const Instruction*> body;
+  auto& instructions = subroutine.instructions;
+  auto pc = *target;
+  while (pc < branch->pc) {
+    auto search = instructions.find(pc);
+    if (search == instructions.end()) {
+      return nullopt;
+    }
+    body.push_back(search->second);
+    pc += search->second->size();
+  }
+  if (body.size() < 2 || pc != branch->pc) {
+    return nullopt;
+  }
+
+  // Find the store, and how the index changes at each iteration.
+  const Instruction* store = nullptr;
+  bool indexY = false;
+  int step = 0;
+  for (auto instruction : body) {
+    auto bodyOp = instruction->operation();
+    auto mode = instruction->addressMode();
+    if (bodyOp == Op::STZ || bodyOp == Op::STA) {
+      if (store != nullptr) {
+        return nullopt;
+      }
+      store = instruction;
+      indexY = mode == AddressMode::AbsoluteIndexedY;
+      if (mode != AddressMode::AbsoluteIndexedX &&
+          mode != AddressMode::AbsoluteIndexedLong &&
+          mode != AddressMode::DirectPageIndexedX && !indexY) {
+        return nullopt;
+      }
+    }
+  }
+  if (store == nullptr) {
+    return nullopt;
+  }
+  for (auto instruction : body) {
+    auto bodyOp = instruction->operation();
+    if (bodyOp == (indexY ? Op::DEY : Op::DEX)) {
+      step--;
+    } else if (bodyOp == (indexY ? Op::INY : Op::INX)) {
+      step++;
+    } else if (indexY ? instruction->changesY() : instruction->changesX()) {
+      return nullopt;
+    } else if (store->operation() == Op::STA && instruction->changesA()) {
+      return nullopt;
+    }
+  }
+  if (step == 0) {
+    return nullopt;
+  }
+
+  // The index must start from a constant.
+  auto init = previous(subroutine, *target);
+  if (init == nullptr || init->operation() != (indexY ? Op::LDY : Op::LDX) ||
+      init->addressMode() != AddressMode::ImmediateX) {
+    return nullopt;
+  }
+  int first = *init->argument();
+
+  // A constant must be loaded before STA.
+  optional<u16> value;
+  if (store->operation() == Op::STA) {
+    auto load = previous(subroutine, init->pc);
+    if (load == nullptr || load->operation() != Op::LDA ||
+        load->addressMode() != AddressMode::ImmediateM) {
+      return nullopt;
+    }
+    value = *load->argument();
+  }
+
+  // Compute the range of indices from the exit condition.
+  int low, high;
+  auto last = body.back();
+  if (step < 0) {
+    // Counting down: the flags come from the decrement.
+    auto op = last->operation();
+    if (op != (indexY ? Op::DEY : Op::DEX)) {
+      return nullopt;
+    }
+    int decrement = -step;
+    low = branch->operation() == Op::BPL ? first % decrement : decrement;
+    if (branch->operation() == Op::BNE && first % decrement != 0) {
+      return nullopt;
+    }
+    high = first;
+  } else {
+    // Counting up: the loop ends when the index reaches a limit.
+    if (branch->operation() != Op::BNE ||
+        last->operation() != (indexY ? Op::CPY : Op::CPX) ||
+        last->addressMode() != AddressMode::ImmediateX) {
+      return nullopt;
+    }
+    int limit = *last->argument();
+    if (limit <= first || (limit - first) % step != 0) {
+      return nullopt;
+    }
+    low = first;
+    high = limit - step;
+  }
+
+  auto address = *store->argument();
+  RegionClear clear;
+  clear.start = address + low;
+  clear.end = address + high + store->state.sizeA() - 1;
+  clear.addressSize = store->argumentSize();
+  clear.value = value;
+  return pair{store->pc, clear};
+}
+
+// Return the instruction ending right before the given address, if any.
+const Instruction* RegionClearAnalysis::previous(const Subroutine& subroutine,
+                                                 InstructionPC pc) {
+  for (auto size = 1; size <= 4; size++) {
+    auto search = subroutine.instructions.find(pc - size);
+    if (search != subroutine.instructions.end() &&
+        search->second->size() == (size_t)size) {
+      return search->second;
+    }
+  }
+  return nullptr;
+}
@@ -0,0 +1,49 @@
+#pragma once
+
+#include <cstddef>
+#include <optional>
+#include <string>
+#include <utility>
+
+#include "types.hpp"
+
+class Analysis;
+class Instruction;
+struct Subroutine;
+
+// Region of memory cleared, or filled with a constant, by a loop.
+struct RegionClear {
+  u24 start;                 // First address of the region.
+  u24 end;                   // Last address of the region (included).
+  std::size_t addressSize;   // Size of the addresses, in bytes.
+  std::optional<u16> value;  // Value stored (none if the loop uses STZ).
+};
+
+// Return a human-readable description of a region clear.
+std::string regionClearDescription(const RegionClear& clear);
+
+/**
+ * Recognize the loops that initialize a region of memory, such as
+ * `LDX #n : STZ addr,X : DEX : BPL`, and infer the region they cover.
+ */
+class RegionClearAnalysis {
+ public:
+  // Constructor.
+  RegionClearAnalysis(Analysis* analysis);
+
+  // Find the region clears of every subroutine.
+  void run();
+
+ private:
+  // Return the region cleared by the loop closed by a branch, if any.
+  static std::optional<std::pair<InstructionPC, RegionClear>> match(
+      const Subroutine& subroutine,
+      const Instruction* branch);
+
+  // Return the instruction ending right before the given address, if any.
+  static const Instruction* previous(const Subroutine& subroutine,
+                                     InstructionPC pc);
+
+  // Pointer to the analysis.
+  Analysis* analysis;
+};
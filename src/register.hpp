@@ -20,7 +20,7 @@ struct Register {
   void setWhole(std::optional<u16> value);
 
   CPU* cpu;              // Pointer to the CPU object.
-  bool isAccumulator;    // True if A, false if X.
+  bool isAccumulator;    // True if A, false if X or Y.
   std::optional<u8> lo;  // Lower 8 bits of the register.
   std::optional<u8> hi;  // Higher 8 bits of the register.
 };
@@ -1,4 +1,7 @@
+#include <algorithm>
+#include <cctype>
 #include <filesystem>
+#include <sstream>
 
 #include "rom.hpp"
 
@@ -12,14 +15,23 @@ ROM::ROM() {}
 // Construct a ROM from a file path.
 ROM::ROM(const string& path) : path{path} {
   data = readBinaryFile(path);
+
+  // Strip the header added by copier devices, if present.
+  if (data.size() % 0x400 == COPIER_HEADER_SIZE) {
+    auto end = data.begin() + COPIER_HEADER_SIZE;
+    copierHeader = vector<u8>(data.begin(), end);
+    data.erase(data.begin(), end);
+  }
+
   romType = discoverType();
   romType = discoverSubtype();
 };
 
-// Return the path of the save file containing the analysis of the ROM.
-string ROM::savePath() const {
+// Return the path of the save file containing the analysis of the ROM,
+// or a named analysis of it (`<rom>.<name>.glm`).
+string ROM::savePath(const string& name) const {
   std::filesystem::path p = path;
-  p.replace_extension(".glm");
+  p.replace_extension(name.empty() ? ".glm" : "." + name + ".glm");
   return p.string();
 }
 
@@ -42,10 +54,10 @@ u24 ROM::readAddress(u24 address) const {
   return (hi << 16) | lo;
 }
 
-// Read a sequence of bytes.
+// Read a sequence of bytes, stopping where the ROM ends.
 vector<u8> ROM::read(u24 address, size_t bytes) const {
-  vector<u8> buffer(bytes);
-  for (size_t i = 0; i < bytes; i++) {
+  vector<u8> buffer;
+  for (size_t i = 0; i < bytes && isROM(address + i); i++) {
     buffer.push_back(readByte(address + i));
   }
   return buffer;
@@ -56,21 +68,123 @@ bool ROM::isRAM(u24 address) {
   return (address <= 0x001FFF) || (0x7E0000 <= address && address <= 0x7FFFFF);
 }
 
+// Return the WRAM address ($7E0000-$7FFFFF) an address maps to, if any.
+optional<u24> ROM::wramAddress(u24 address) {
+  auto bank = address >> 16;
+  if (bank == 0x7E || bank == 0x7F) {
+    return address;
+  }
+  // The first $2000 bytes of WRAM are mirrored in the system banks.
+  if ((bank & 0x7F) <= 0x3F && (address & 0xFFFF) <= 0x1FFF) {
+    return 0x7E0000 | (address & 0xFFFF);
+  }
+  return nullopt;
+}
+
+// Return true if the address maps to the contents of the ROM.
+bool ROM::isROM(u24 address) const {
+  if (isRAM(address)) {
+    return false;
+  }
+
+  // Lower half of system banks: hardware registers and expansion.
+  auto bank = address >> 16;
+  if ((bank & 0x7F) <= 0x3F && (address & 0xFFFF) < 0x8000) {
+    return false;
+  }
+
+  return translate(address) < data.size();
+}
+
+// Return true if the address maps to the header (or the vectors).
+bool ROM::isHeader(u24 address) const {
+  // The header area starts with the expanded header, at $FFB0.
+  auto offset = address & 0xFFFF;
+  return isROM(address) && offset >= 0xFFB0 &&
+         translate(address) == translate(translateHeader(offset));
+}
+
 // Size of the ROM, as indicated by the header.
 size_t ROM::size() const {
-  return 0x400 << readByte(translateHeader(Header::SIZE));
+  return 0x400 << readHeaderByte(Header::SIZE);
 }
 
-// Size of the ROM, as measured by the size of the file.
+// Size of the ROM, as measured by the size of the file (minus the copier
+// header, if any).
 size_t ROM::realSize() const {
   return data.size();
 }
 
+// Whether the file starts with a 512-byte copier header.
+bool ROM::hasCopierHeader() const {
+  return copierHeader.has_value();
+}
+
+// Return the CRC-32 of the ROM's data (without the copier header).
+u32 ROM::checksum() const {
+  return crc32(data);
+}
+
+// Return the checksum stored in the header.
+u16 ROM::headerChecksum() const {
+  return readHeaderWord(Header::CHECKSUM);
+}
+
+// Compute the checksum the header should contain: the sum of the bytes of
+// the ROM, with the part above the largest power of two mirrored.
+u16 ROM::computeChecksum() const {
+  // Count the checksum and its complement as $0000 and $FFFF: any correct
+  // pair adds up to the same value.
+  auto field = translate(translateHeader(Header::COMPLEMENT));
+  auto byte = [&](size_t offset) -> u8 {
+    if (offset >= field && offset < field + 4) {
+      return offset < field + 2 ? 0xFF : 0x00;
+    }
+    return data[offset];
+  };
+
+  size_t base = 1;
+  while (base * 2 <= data.size()) {
+    base *= 2;
+  }
+  u32 sum = 0, rest = 0;
+  for (size_t offset = 0; offset < data.size(); offset++) {
+    (offset < base ? sum : rest) += byte(offset);
+  }
+  if (base < data.size()) {
+    sum += rest * (base / (data.size() - base));
+  }
+  return sum;
+}
+
+// Whether the header holds the correct checksum and its complement.
+bool ROM::hasValidChecksum() const {
+  auto checksum = computeChecksum();
+  return headerChecksum() == checksum &&
+         readHeaderWord(Header::COMPLEMENT) == (u16)~checksum;
+}
+
+// Write the correct checksum and its complement in the header. Return
+// false if the ROM is too small to contain a header.
+bool ROM::fixChecksum() {
+  auto offset = translate(translateHeader(Header::COMPLEMENT));
+  if (offset + 4 > data.size()) {
+    return false;
+  }
+  u16 checksum = computeChecksum();
+  u16 complement = ~checksum;
+  data[offset + 0] = complement & 0xFF;
+  data[offset + 1] = complement >> 8;
+  data[offset + 2] = checksum & 0xFF;
+  data[offset + 3] = checksum >> 8;
+  return true;
+}
+
 // Return the ROM's title.
 string ROM::title() const {
   string title;
   for (int i = 0; i < Header::TITLE_LEN; i++) {
-    char c = readByte(translateHeader(Header::TITLE + i));
+    char c = readHeaderByte(Header::TITLE + i);
     if (c == 0x00) {
       break;
     }
@@ -81,12 +195,12 @@ string ROM::title() const {
 
 // Return the reset vector (ROM's entry point).
 SubroutinePC ROM::resetVector() const {
-  return readWord(translateHeader(Header::RESET));
+  return readHeaderWord(Header::RESET);
 }
 
 // Return the NMI vector (VBLANK handler).
 SubroutinePC ROM::nmiVector() const {
-  return readWord(translateHeader(Header::NMI));
+  return readHeaderWord(Header::NMI);
 }
 
 // Translate an address from SNES to PC.
@@ -106,10 +220,11 @@ u24 ROM::translate(u24 address) const {
       }
 
     case ROMType::ExHiROM:
-      if ((address & 0xC00000) != 0xC00000) {
-        return (address & 0x3FFFFF) | 0x400000;
-      } else {
+      // Banks $80-$FF map the first 4MB of the ROM, banks $00-$7D the rest.
+      if (address & 0x800000) {
         return address & 0x3FFFFF;
+      } else {
+        return (address & 0x3FFFFF) | 0x400000;
       }
 
     case ROMType::SDD1:
@@ -118,11 +233,109 @@ u24 ROM::translate(u24 address) const {
       } else {
         return ((address & 0x7F0000) >> 1) | (address & 0x7FFF);
       }
+
+    case ROMType::SA1:
+      // At reset, the MMC maps the four 1MB chunks of the ROM to banks
+      // $00-$1F, $20-$3F, $80-$9F and $A0-$BF in order, and the whole ROM
+      // to banks $C0-$FF.
+      if (address >= 0xC00000) {
+        return address & 0x3FFFFF;
+      } else if (address & 0x800000) {
+        return (((address & 0x3F0000) >> 1) | (address & 0x7FFF)) + 0x200000;
+      } else {
+        return ((address & 0x3F0000) >> 1) | (address & 0x7FFF);
+      }
+  }
+
+  __builtin_unreachable();
+}
+
+// Translate an offset from PC to SNES, picking the canonical mirror.
+u24 ROM::address(u24 offset) const {
+  auto loROM = [](u24 offset) {
+    u24 address = ((offset << 1) & 0x7F0000) | 0x8000 | (offset & 0x7FFF);
+    // Banks $7E and $7F are RAM: use the upper mirror.
+    return address >= 0x7E0000 ? address | 0x800000 : address;
+  };
+
+  switch (romType) {
+    case ROMType::LoROM:
+      return loROM(offset);
+
+    case ROMType::HiROM:
+      return 0xC00000 | offset;
+
+    case ROMType::ExLoROM:
+      if (offset < 0x400000) {
+        return 0x800000 | loROM(offset);
+      } else {
+        return loROM(offset - 0x400000);
+      }
+
+    case ROMType::ExHiROM:
+      if (offset < 0x400000) {
+        return 0xC00000 | offset;
+      } else {
+        return 0x400000 | (offset & 0x3FFFFF);
+      }
+
+    case ROMType::SDD1:
+      return 0x800000 | loROM(offset);
+
+    case ROMType::SA1:
+      if (offset < 0x200000) {
+        return loROM(offset);
+      } else {
+        return 0x800000 | loROM(offset - 0x200000);
+      }
   }
 
   __builtin_unreachable();
 }
 
+// Return the addresses of the occurrences of a byte pattern, stopping after
+// `limit` of them.
+vector<u24> ROM::find(const BytePattern& pattern, size_t limit) const {
+  vector<u24> addresses;
+  for (size_t offset = 0;
+       offset + pattern.size() <= data.size() && addresses.size() < limit;
+       offset++) {
+    bool match = true;
+    for (size_t i = 0; i < pattern.size() && match; i++) {
+      match = !pattern[i].has_value() || *pattern[i] == data[offset + i];
+    }
+    if (match) {
+      addresses.push_back(address(offset));
+    }
+  }
+  return addresses;
+}
+
+// Parse a byte pattern written in hex, with `??` as wildcard (e.g.
+// `A9 ?? 8D 00 21` or `A9??8D0021`). Return nothing if the pattern is empty
+// or invalid.
+optional<BytePattern> ROM::parsePattern(const string& text) {
+  BytePattern pattern;
+  istringstream tokens(text);
+  string token;
+  while (tokens >> token) {
+    if (token.size() % 2 != 0) {
+      return nullopt;
+    }
+    for (size_t i = 0; i < token.size(); i += 2) {
+      auto byte = token.substr(i, 2);
+      if (byte == "??") {
+        pattern.push_back(nullopt);
+      } else if (isxdigit(byte[0]) && isxdigit(byte[1])) {
+        pattern.push_back(stoi(byte, nullptr, 16));
+      } else {
+        return nullopt;
+      }
+    }
+  }
+  return pattern.empty() ? nullopt : optional(pattern);
+}
+
 // Translate address inside the header.
 u24 ROM::translateHeader(u24 address) const {
   if (romType == ROMType::ExLoROM || romType == ROMType::SDD1) {
@@ -131,6 +344,17 @@ u24 ROM::translateHeader(u24 address) const {
   return address;
 }
 
+// Read a byte of the header, or 0 if the ROM is too small to contain it.
+u8 ROM::readHeaderByte(u24 address) const {
+  auto offset = translate(translateHeader(address));
+  return offset < data.size() ? data[offset] : 0x00;
+}
+
+// Read a word of the header, or 0 if the ROM is too small to contain it.
+u16 ROM::readHeaderWord(u24 address) const {
+  return (readHeaderByte(address + 1) << 8) | readHeaderByte(address);
+}
+
 // Discover the ROM type.
 ROMType ROM::discoverType() const {
   if (data.size() <= 0x8000) {
@@ -139,18 +363,25 @@ ROMType ROM::discoverType() const {
 
   int lorom = typeScore(ROMType::LoROM);
   int hirom = typeScore(ROMType::HiROM);
+  int exhirom = typeScore(ROMType::ExHiROM);
 
+  if (exhirom > 0 && exhirom >= max(lorom, hirom)) {
+    return ROMType::ExHiROM;
+  }
   return (hirom > lorom) ? ROMType::HiROM : ROMType::LoROM;
 }
 
 // Discover the ROM subtype.
 ROMType ROM::discoverSubtype() const {
-  u8 markup = readByte(Header::MARKUP);
+  u8 markup = readHeaderByte(Header::MARKUP);
+  u8 type = readHeaderByte(Header::TYPE);
 
   switch (romType) {
     case ROMType::LoROM:
       if (markup == 0x32) {
         return ROMType::SDD1;
+      } else if (markup == 0x23 && (type == 0x34 || type == 0x35)) {
+        return ROMType::SA1;
       } else if (markup & (1 << 1)) {
         return ROMType::ExLoROM;
       }
@@ -171,8 +402,16 @@ ROMType ROM::discoverSubtype() const {
 
 // Estimate the likelihood that the the ROM is of the given type.
 int ROM::typeScore(ROMType romType) const {
-  u24 titleAddress =
-      (romType == ROMType::LoROM) ? (Header::TITLE - 0x8000) : Header::TITLE;
+  u24 titleAddress = Header::TITLE;
+  if (romType == ROMType::LoROM) {
+    titleAddress -= 0x8000;
+  } else if (romType == ROMType::ExHiROM) {
+    // The header is in the upper 4MB of the ROM, mapped to bank $00.
+    titleAddress += 0x400000;
+  }
+  if (titleAddress + Header::TITLE_LEN > data.size()) {
+    return 0;
+  }
 
   int score = 0;
   for (int i = 0; i < Header::TITLE_LEN; i++) {
@@ -187,3 +426,22 @@ int ROM::typeScore(ROMType romType) const {
   }
   return score;
 }
+
+// Return the name of a ROM type.
+string romTypeName(ROMType type) {
+  switch (type) {
+    case ROMType::LoROM:
+      return "LoROM";
+    case ROMType::HiROM:
+      return "HiROM";
+    case ROMType::ExLoROM:
+      return "ExLoROM";
+    case ROMType::ExHiROM:
+      return "ExHiROM";
+    case ROMType::SDD1:
+      return "S-DD1";
+    case ROMType::SA1:
+      return "SA-1";
+  }
+  __builtin_unreachable();
+}
@@ -1,5 +1,7 @@
 #pragma once
 
+#include <cstdint>
+#include <optional>
 #include <string>
 #include <vector>
 
@@ -12,6 +14,7 @@ enum class ROMType {
   ExLoROM,
   ExHiROM,
   SDD1,
+  SA1,
 };
 
 // ROM's header.
@@ -21,10 +24,15 @@ enum Header {
   MARKUP = 0xFFD5,
   TYPE = 0xFFD6,
   SIZE = 0xFFD7,
+  COMPLEMENT = 0xFFDC,
+  CHECKSUM = 0xFFDE,
   NMI = 0xFFEA,
   RESET = 0xFFFC,
 };
 
+// Byte pattern to search for (nullopt matches any byte).
+typedef std::vector<std::optional<u8>> BytePattern;
+
 // Class representing a SNES ROM.
 class ROM {
  public:
@@ -33,24 +41,51 @@ class ROM {
   // Construct a ROM from a file path.
   ROM(const std::string& path);
 
-  // Return the path of the save file containing the analysis of the ROM.
-  std::string savePath() const;
+  // Return the path of the save file containing the analysis of the ROM,
+  // or a named analysis of it (`<rom>.<name>.glm`).
+  std::string savePath(const std::string& name = "") const;
 
   u8 readByte(u24 address) const;      // Read a byte.
   u16 readWord(u24 address) const;     // Read a word (16 bits).
   u24 readAddress(u24 address) const;  // Read an address (24 bits).
-  // Read a sequence of bytes.
+  // Read a sequence of bytes, stopping where the ROM ends.
   std::vector<u8> read(u24 address, size_t bytes) const;
 
   // Return true if the address is in RAM, false otherwise.
   static bool isRAM(u24 address);
+  // Return the WRAM address ($7E0000-$7FFFFF) an address maps to, if any.
+  static std::optional<u24> wramAddress(u24 address);
+
+  // Return true if the address maps to the contents of the ROM.
+  bool isROM(u24 address) const;
+
+  // Return true if the address maps to the header (or the vectors).
+  bool isHeader(u24 address) const;
 
   // Size of the ROM, as indicated by the header.
   size_t size() const;
 
-  // Size of the ROM, as measured by the size of the file.
+  // Size of the ROM, as measured by the size of the file (minus the copier
+  // header, if any).
   size_t realSize() const;
 
+  // Whether the file starts with a 512-byte copier header.
+  bool hasCopierHeader() const;
+
+  // Return the CRC-32 of the ROM's data (without the copier header).
+  u32 checksum() const;
+
+  // Return the checksum stored in the header.
+  u16 headerChecksum() const;
+  // Compute the checksum the header should contain: the sum of the bytes of
+  // the ROM, with the part above the largest power of two mirrored.
+  u16 computeChecksum() const;
+  // Whether the header holds the correct checksum and its complement.
+  bool hasValidChecksum() const;
+  // Write the correct checksum and its complement in the header. Return
+  // false if the ROM is too small to contain a header.
+  bool fixChecksum();
+
   // Return the ROM's title.
   std::string title() const;
 
@@ -60,17 +95,43 @@ class ROM {
   // Return the NMI vector (VBLANK handler).
   SubroutinePC nmiVector() const;
 
-  // Translate an address from SNES to PC.
+  // Translate an address from SNES to PC. SA-1 ROMs are translated with
+  // the mapping set at reset, before the game configures the MMC registers.
   u24 translate(u24 address) const;
 
+  // Translate an offset from PC to SNES, picking the canonical mirror.
+  u24 address(u24 offset) const;
+
+  // Return the addresses of the occurrences of a byte pattern, stopping after
+  // `limit` of them.
+  std::vector<u24> find(const BytePattern& pattern,
+                        size_t limit = SIZE_MAX) const;
+
+  // Parse a byte pattern written in hex, with `??` as wildcard (e.g.
+  // `A9 ?? 8D 00 21` or `A9??8D0021`). Return nothing if the pattern is empty
+  // or invalid.
+  static std::optional<BytePattern> parsePattern(const std::string& text);
+
   ROMType romType;       // ROM classification.
   std::string path;      // ROM's file path.
   std::vector<u8> data;  // ROM's data.
+  // Copier header stripped from the file, if any.
+  std::optional<std::vector<u8>> copierHeader;
+
+  // Size of the header added by copier devices.
+  static const size_t COPIER_HEADER_SIZE = 0x200;
+  // Maximum number of occurrences shown when searching for a byte pattern.
+  static const size_t MAX_FIND_RESULTS = 256;
 
  private:
   // Translate address inside the header.
   u24 translateHeader(u24 address) const;
 
+  // Read a byte of the header, or 0 if the ROM is too small to contain it.
+  u8 readHeaderByte(u24 address) const;
+  // Read a word of the header, or 0 if the ROM is too small to contain it.
+  u16 readHeaderWord(u24 address) const;
+
   // Discover the ROM type.
   ROMType discoverType() const;
 
@@ -80,3 +141,6 @@ class ROM {
   // Estimate the likelihood that the the ROM is of the given type.
   int typeScore(ROMType romType) const;
 };
+
+// Return the name of a ROM type.
+std::string romTypeName(ROMType type);
@@ -0,0 +1,78 @@
+#include <algorithm>
+#include <boost/archive/text_iarchive.hpp>
+#include <boost/archive/text_oarchive.hpp>
+#include <cstdlib>
+#include <fstream>
+
+#include "settings.hpp"
+
+using namespace std;
+namespace fs = std::filesystem;
+
+// Extension of profile files.
+static const string PROFILE_EXTENSION = ".glp";
+
+// Save the settings as a named profile.
+bool Settings::saveProfile(const string& name) const {
+  try {
+    fs::create_directories(profilesPath());
+    ofstream file(profilesPath() / (name + PROFILE_EXTENSION));
+    boost::archive::text_oarchive archive(file);
+    archive << *this;
+    return true;
+  } catch (...) {
+    return false;
+  }
+}
+
+// Load the settings from a named profile.
+bool Settings::loadProfile(const string& name) {
+  try {
+    ifstream file(profilesPath() / (name + PROFILE_EXTENSION));
+    boost::archive::text_iarchive archive(file);
+    Settings settings;
+    archive >> settings;
+    *this = settings;
+    return true;
+  } catch (...) {
+    return false;
+  }
+}
+
+// Return the names of all the saved profiles.
+vector<string> Settings::profiles() {
+  vector<string> names;
+
+  error_code error;
+  for (auto& entry : fs::directory_iterator(profilesPath(), error)) {
+    if (entry.path().extension() == PROFILE_EXTENSION) {
+      names.push_back(entry.path().stem().string());
+    }
+  }
+
+  sort(names.begin(), names.end());
+  return names;
+}
+
+// Return the directory where profiles are saved.
+fs::path Settings::profilesPath() {
+  if (auto dataHome = getenv("XDG_DATA_HOME")) {
+    return fs::path(dataHome) / "gilgamesh" / "profiles";
+  } else if (auto home = getenv("HOME")) {
+    return fs::path(home) / ".local" / "share" / "gilgamesh" / "profiles";
+  }
+  return "profiles";
+}
+
+// Comparison function.
+bool Settings::operator==(const Settings& other) const {
+  return subroutinePrefix == other.subroutinePrefix &&
+         localLabelPrefix == other.localLabelPrefix &&
+         stateInference == other.stateInference &&
+         strictMode == other.strictMode && dataOpcodes == other.dataOpcodes &&
+         localLabelTable == other.localLabelTable &&
+         emulationReset == other.emulationReset &&
+         takenBranchesOnly == other.takenBranchesOnly &&
+         sizeSuffixes == other.sizeSuffixes &&
+         nmiAllStates == other.nmiAllStates;
+}
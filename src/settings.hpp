@@ -0,0 +1,81 @@
+#pragma once
+
+#include <boost/serialization/set.hpp>
+#include <boost/serialization/string.hpp>
+#include <boost/serialization/version.hpp>
+#include <filesystem>
+#include <set>
+#include <string>
+#include <vector>
+
+#include "types.hpp"
+
+/**
+ * Analysis settings and heuristics.
+ * Can be saved as named profiles, shared across ROMs.
+ */
+struct Settings {
+  std::string subroutinePrefix = "sub_";  // Prefix of subroutine labels.
+  std::string localLabelPrefix = "loc_";  // Prefix of local labels.
+  // Infer the state required by a subroutine from its instructions. The
+  // inference is only reported for entry points, whose entry state is a guess.
+  bool stateInference = true;
+  // Stop the analysis when reaching opcodes that never appear in code.
+  bool strictMode = false;
+  // Opcodes treated as data in strict mode (BRK, COP, WDM, STP).
+  std::set<u8> dataOpcodes = {0x00, 0x02, 0x42, 0xDB};
+  // Summarize the local labels at the top of each subroutine.
+  bool localLabelTable = false;
+  // Start the reset handler in emulation mode (8-bits A and X), as the CPU
+  // does on power-on, instead of the state of its entry point.
+  bool emulationReset = true;
+  // Follow only the taken side of branches, tracing a single path through
+  // the code instead of covering all of it.
+  bool takenBranchesOnly = false;
+  // Add `.b`, `.w` and `.l` suffixes to the mnemonics in the assembly export,
+  // forcing the size of their operands.
+  bool sizeSuffixes = false;
+  // Run the NMI handler with every size of A and X, since it can interrupt
+  // code running in any state, instead of the state of its entry point only.
+  bool nmiAllStates = false;
+
+  // Save the settings as a named profile.
+  bool saveProfile(const std::string& name) const;
+  // Load the settings from a named profile.
+  bool loadProfile(const std::string& name);
+
+  // Return the names of all the saved profiles.
+  static std::vector<std::string> profiles();
+  // Return the directory where profiles are saved.
+  static std::filesystem::path profilesPath();
+
+  // Comparison function.
+  bool operator==(const Settings& other) const;
+
+  template <class Archive>
+  void serialize(Archive& ar, const unsigned int version) {
+    ar& subroutinePrefix;
+    ar& localLabelPrefix;
+    ar& stateInference;
+    if (version >= 1) {
+      ar& strictMode;
+      ar& dataOpcodes;
+    }
+    if (version >= 2) {
+      ar& localLabelTable;
+    }
+    if (version >= 3) {
+      ar& emulationReset;
+    }
+    if (version >= 4) {
+      ar& takenBranchesOnly;
+    }
+    if (version >= 5) {
+      ar& sizeSuffixes;
+    }
+    if (version >= 6) {
+      ar& nmiAllStates;
+    }
+  }
+};
+BOOST_CLASS_VERSION(Settings, 6)
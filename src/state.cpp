@@ -36,7 +36,38 @@ void State::reset(u8 mask) {
 
 // Comparison function.
 bool State::operator==(const State& other) const {
-  return p == other.p;
+  return p == other.p && e == other.e;
+}
+
+/*******************
+ *  UnknownReason  *
+ *******************/
+
+// Return a human-readable description of an unknown reason.
+string unknownReasonName(UnknownReason reason) {
+  switch (reason) {
+    case UnknownReason::Known:
+      return "known";
+    case UnknownReason::Unknown:
+      return "unknown callee";
+    case UnknownReason::SuspectInstruction:
+      return "suspect instruction";
+    case UnknownReason::MultipleReturnStates:
+      return "multiple return states";
+    case UnknownReason::IndirectJump:
+      return "indirect jump";
+    case UnknownReason::StackManipulation:
+      return "stack manipulation";
+    case UnknownReason::Recursion:
+      return "recursion";
+    case UnknownReason::MutableCode:
+      return "mutable code";
+    case UnknownReason::ReturnMismatch:
+      return "return mismatch";
+    case UnknownReason::EmulationMode:
+      return "unknown emulation mode";
+  }
+  __builtin_unreachable();
 }
 
 /*****************
@@ -1,7 +1,9 @@
 #pragma once
 
 #include <boost/container_hash/hash.hpp>
+#include <boost/serialization/version.hpp>
 #include <optional>
+#include <string>
 #include <unordered_map>
 #include <unordered_set>
 
@@ -25,6 +27,9 @@ struct State {
     };
     u8 p;  // State register.
   };
+  // Emulation flag (not part of P). In emulation mode A and X are always
+  // 8-bits.
+  bool e = false;
 
   // Constructors.
   State();
@@ -41,10 +46,14 @@ struct State {
   bool operator==(const State& other) const;
 
   template <class Archive>
-  void serialize(Archive& ar, const unsigned int) {
+  void serialize(Archive& ar, const unsigned int version) {
     ar& p;
+    if (version >= 1) {
+      ar& e;
+    }
   }
 };
+BOOST_CLASS_VERSION(State, 1)
 
 /**
  * Possible reasons why a state change is unknown.
@@ -58,8 +67,13 @@ enum class UnknownReason {
   StackManipulation,
   Recursion,
   MutableCode,
+  ReturnMismatch,
+  EmulationMode,
 };
 
+// Return a human-readable description of an unknown reason.
+std::string unknownReasonName(UnknownReason reason);
+
 /**
  * State change caused by the execution of a subroutine.
  */
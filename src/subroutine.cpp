@@ -13,6 +13,15 @@ void Subroutine::addInstruction(Instruction* instruction) {
   instructions[instruction->pc] = instruction;
 }
 
+// Add a sequence of calls through which the subroutine can be reached.
+// Return true if the sequence wasn't known already.
+bool Subroutine::addStackTrace(const StackTrace& stackTrace) {
+  if (stackTrace.empty() || stackTraces.size() >= MAX_STACK_TRACES) {
+    return false;
+  }
+  return stackTraces.insert(stackTrace).second;
+}
+
 // Add a state change.
 void Subroutine::addStateChange(SubroutinePC pc, StateChange stateChange) {
   if (stateChange.unknown()) {
@@ -55,12 +64,16 @@ bool Subroutine::savesStateInIncipit() const {
   return false;
 }
 
-// Return the state changes, simplified given the current state.
-StateChangeSet Subroutine::simplifiedStateChanges(State state) {
+// Return the state changes with which the subroutine returns or falls into
+// another one, simplified given the current state.
+StateChangeSet Subroutine::simplifiedStateChanges(State state) const {
   StateChangeSet stateChanges;
   for (auto& [pc, stateChange] : knownStateChanges) {
     stateChanges.insert(stateChange.simplify(state));
   }
+  for (auto& [pc, stateChange] : fallThroughStateChanges) {
+    stateChanges.insert(stateChange.simplify(state));
+  }
   return stateChanges;
 }
 
@@ -78,3 +91,65 @@ optional<StateChange> Subroutine::stateChangeForPC(InstructionPC pc) const {
 
   return nullopt;
 }
+
+// Whether the subroutine disables interrupts.
+bool Subroutine::hasCriticalSections() const {
+  return !criticalSections.empty() || !unclosedCriticalSections.empty() ||
+         !nestedCriticalSections.empty();
+}
+
+// Whether any of the critical sections looks suspicious.
+bool Subroutine::hasSuspiciousCriticalSections() const {
+  return !unclosedCriticalSections.empty() ||
+         !redundantInterruptEnables.empty();
+}
+
+// Whether the subroutine calls no other subroutine. Indirect calls count
+// as calls, even when their targets are unknown.
+bool Subroutine::isLeaf() const {
+  for (auto& [pc, instruction] : instructions) {
+    if (instruction->type() == InstructionType::Call) {
+      return false;
+    }
+  }
+  return true;
+}
+
+// Whether an address lies between the first and the last instruction of the
+// subroutine (included).
+bool Subroutine::spans(u24 address) const {
+  if (instructions.empty()) {
+    return false;
+  }
+  auto last = instructions.rbegin()->second;
+  return instructions.begin()->first <= address &&
+         address < last->pc + last->size();
+}
+
+// Return the hardware subsystems whose registers the subroutine accesses.
+set<Subsystem> Subroutine::touchedSubsystems() const {
+  set<Subsystem> subsystems;
+  for (auto& [pc, instruction] : instructions) {
+    if (auto address = instruction->hardwareRegister()) {
+      if (auto subsystem = hardwareSubsystem(*address)) {
+        subsystems.insert(*subsystem);
+      }
+    }
+  }
+  return subsystems;
+}
+
+// Estimated number of cycles taken by running each instruction of the
+// subroutine once.
+size_t Subroutine::cycles() const {
+  size_t cycles = 0;
+  for (auto& [pc, instruction] : instructions) {
+    cycles += instruction->cycles();
+  }
+  return cycles;
+}
+
+// Whether the subroutine preserves the value of an index register.
+Preservation Subroutine::preservation(IndexRegister reg) const {
+  return reg == IndexRegister::X ? xPreservation : yPreservation;
+}
@@ -1,14 +1,24 @@
 #pragma once
 
+#include <cstddef>
 #include <map>
 #include <optional>
+#include <set>
 #include <string>
+#include <utility>
+#include <vector>
 
+#include "hardwareregisters.hpp"
+#include "preservation.hpp"
+#include "regionclear.hpp"
 #include "state.hpp"
 #include "types.hpp"
 
 class Instruction;
 
+// Sequence of call instructions leading to a subroutine.
+typedef std::vector<InstructionPC> StackTrace;
+
 // Structure representing a subroutine.
 struct Subroutine {
   // Constructor.
@@ -30,16 +40,49 @@ struct Subroutine {
   // Whether the subroutine saves the CPU state at the beginning.
   bool savesStateInIncipit() const;
 
-  // Return the state changes, simplified given the current state.
-  StateChangeSet simplifiedStateChanges(State state);
+  // Return the state changes with which the subroutine returns or falls into
+  // another one, simplified given the current state.
+  StateChangeSet simplifiedStateChanges(State state) const;
 
   // Return the state change caused by an instruction at the given PC, if any.
   std::optional<StateChange> stateChangeForPC(InstructionPC pc) const;
 
+  // Whether the subroutine disables interrupts.
+  bool hasCriticalSections() const;
+  // Whether any of the critical sections looks suspicious.
+  bool hasSuspiciousCriticalSections() const;
+
+  // Whether the subroutine calls no other subroutine. Indirect calls count
+  // as calls, even when their targets are unknown.
+  bool isLeaf() const;
+  // Whether an address lies between the first and the last instruction of the
+  // subroutine (included).
+  bool spans(u24 address) const;
+
+  // Return the hardware subsystems whose registers the subroutine accesses.
+  std::set<Subsystem> touchedSubsystems() const;
+
+  // Estimated number of cycles taken by running each instruction of the
+  // subroutine once.
+  std::size_t cycles() const;
+
+  // Whether the subroutine preserves the value of an index register.
+  Preservation preservation(IndexRegister reg) const;
+
+  // Add a sequence of calls through which the subroutine can be reached.
+  // Return true if the sequence wasn't known already.
+  bool addStackTrace(const StackTrace& stackTrace);
+
+  // Maximum number of stack traces recorded for a subroutine.
+  static const std::size_t MAX_STACK_TRACES = 32;
+
   SubroutinePC pc;    // Program Counter.
   std::string label;  // Label.
   bool isEntryPoint;  // Whether this subroutine is an entry point.
 
+  // Sequences of calls through which the subroutine has been reached.
+  std::set<StackTrace> stackTraces;
+
   // Map from PC to instructions.
   std::map<InstructionPC, Instruction*> instructions;
 
@@ -48,4 +91,61 @@ struct Subroutine {
 
   // Unknown state changes.
   StateChangeMap unknownStateChanges;
+
+  // Critical sections (PC where interrupts get disabled, PC where
+  // they get enabled again).
+  std::set<std::pair<InstructionPC, InstructionPC>> criticalSections;
+  // Critical sections still open when returning (PC where interrupts
+  // get disabled, PC of the return instruction or of the last instruction
+  // before running into another subroutine).
+  std::set<std::pair<InstructionPC, InstructionPC>> unclosedCriticalSections;
+  // Instructions disabling interrupts when they're already disabled.
+  std::set<InstructionPC> nestedCriticalSections;
+  // Instructions enabling interrupts when they're already enabled.
+  std::set<InstructionPC> redundantInterruptEnables;
+  // Instructions reached inside a critical section (PC of the instruction,
+  // PC where interrupts got disabled).
+  std::set<std::pair<InstructionPC, InstructionPC>> criticalSectionVisits;
+  // Whether the subroutine jumps through a table indexed by a known X.
+  bool indexedJumps = false;
+  // Instructions reached with a known X, in subroutines with indexed jumps
+  // (PC of the instruction, value of X).
+  std::set<std::pair<InstructionPC, u16>> indexedJumpVisits;
+  // Maximum number of values of X the code of a subroutine is run with.
+  static const std::size_t MAX_INDEXED_JUMP_VISITS = 256;
+
+  // Direct page accesses performed before the direct page register is set.
+  std::set<InstructionPC> directPageBeforeInit;
+  // Whether the subroutine (or one of its callees) sets the direct page.
+  bool setsDirectPage = false;
+
+  // Value of the data bank register at the instructions relying on it.
+  std::map<InstructionPC, u8> dataBanks;
+  // Whether the subroutine (or one of its callees) sets the data bank.
+  bool setsDataBank = false;
+  // Source and destination banks of the block moves (MVN/MVP).
+  std::map<InstructionPC, std::pair<u8, u8>> blockMoves;
+
+  // Returns dispatching to an address pushed on the stack (e.g. PEA + RTS).
+  std::map<InstructionPC, InstructionPC> returnJumps;
+  // Instructions followed by the beginning of another subroutine, which
+  // execution falls into.
+  std::map<InstructionPC, SubroutinePC> fallThroughs;
+  // State changes of the subroutine when leaving it by falling into
+  // another one, by PC of the last instruction.
+  StateChangeMap fallThroughStateChanges;
+
+  // State that the instructions of an entry point assume on entry, inferred
+  // from their immediate operands. The entry state itself is only a guess.
+  StateChange assumedState;
+
+  // Whether X and Y are preserved across the subroutine.
+  Preservation xPreservation = Preservation::Unknown;
+  Preservation yPreservation = Preservation::Unknown;
+
+  // Loops initializing a region of memory (PC of the store instruction).
+  std::map<InstructionPC, RegionClear> regionClears;
+
+  // Loops that execution never leaves (PC of their first instruction).
+  std::set<InstructionPC> hangPoints;
 };
@@ -0,0 +1,152 @@
+#include "sweep.hpp"
+
+#include "analysis.hpp"
+#include "instruction.hpp"
+
+using namespace std;
+
+// Constructor.
+Sweep::Sweep(Analysis* analysis) : analysis{analysis} {
+  auto& rom = analysis->rom;
+
+  // Code reached by the analysis.
+  for (auto& [pc, instructionSet] : analysis->instructions) {
+    for (auto& instruction : instructionSet) {
+      for (size_t i = 0; i < instruction.size(); i++) {
+        if (rom.isROM(pc + i)) {
+          covered.insert(rom.translate(pc + i));
+        }
+      }
+    }
+  }
+  // Code found by previous sweeps, either waiting to be reviewed or rejected.
+  for (auto candidates :
+       {&analysis->sweepQuarantine, &analysis->sweepRejected}) {
+    for (auto& [pc, candidate] : *candidates) {
+      for (auto address = candidate.pc; address < candidate.end; address++) {
+        covered.insert(rom.translate(address));
+      }
+    }
+  }
+  // Regions declared as data.
+  for (auto& [start, block] : analysis->dataBlocks) {
+    for (auto address = block.start; address < block.end; address++) {
+      covered.insert(rom.translate(address));
+    }
+  }
+}
+
+// Try up to the given number of starts, resuming from where the previous
+// sweep stopped. Return true if the whole ROM has been swept.
+bool Sweep::run(size_t budget) {
+  auto& rom = analysis->rom;
+  auto& cursor = analysis->sweepCursor;
+
+  size_t tried = 0;
+  while (cursor < END && tried < budget) {
+    auto pc = cursor;
+    if (!isCanonical(pc) || isCovered(pc)) {
+      cursor++;
+      continue;
+    }
+
+    tried++;
+    auto candidate = evaluate(pc);
+    if (!candidate.has_value()) {
+      cursor++;
+      continue;
+    }
+
+    // Resume after the candidate, so that its bytes aren't tried again.
+    analysis->sweepQuarantine[pc] = *candidate;
+    for (auto address = candidate->pc; address < candidate->end; address++) {
+      covered.insert(rom.translate(address));
+    }
+    cursor = candidate->end;
+  }
+
+  return cursor >= END;
+}
+
+// Decode the code at the given address, if it passes the sanity checks.
+optional<SweepCandidate> Sweep::evaluate(u24 pc) const {
+  auto& rom = analysis->rom;
+  auto& dataOpcodes = analysis->settings.dataOpcodes;
+
+  SweepCandidate candidate{pc, pc, 0, 0};
+  State state;
+
+  while (candidate.instructions < MAX_INSTRUCTIONS) {
+    // The code must not run into other code or data, or out of the ROM.
+    if (!rom.isROM(pc) || isCovered(pc)) {
+      return nullopt;
+    }
+    auto opcode = rom.readByte(pc);
+    auto size = Instruction(pc, candidate.pc, opcode, 0, state).size();
+    u24 argument = 0;
+    for (size_t i = 1; i < size; i++) {
+      if (!rom.isROM(pc + i) || isCovered(pc + i)) {
+        return nullopt;
+      }
+      argument |= rom.readByte(pc + i) << (8 * (i - 1));
+    }
+    Instruction instruction(pc, candidate.pc, opcode, argument, state);
+    candidate.instructions++;
+    pc += instruction.size();
+
+    auto op = instruction.operation();
+    if (dataOpcodes.count(opcode)) {
+      candidate.suspicion += 2;
+    } else if (instruction.type() == InstructionType::Interrupt ||
+               op == Op::STP || op == Op::WAI) {
+      candidate.suspicion += 1;
+    }
+    if (candidate.suspicion > MAX_SUSPICION) {
+      return nullopt;
+    }
+
+    if (op == Op::SEP) {
+      state.set(*instruction.absoluteArgument());
+    } else if (op == Op::REP) {
+      state.reset(*instruction.absoluteArgument());
+    }
+
+    // The code must end with a return or an unconditional jump.
+    auto type = instruction.type();
+    if (type == InstructionType::Return || type == InstructionType::Jump ||
+        op == Op::BRA || op == Op::BRL) {
+      if (candidate.instructions < MIN_INSTRUCTIONS) {
+        return nullopt;
+      }
+      candidate.end = pc;
+      return candidate;
+    }
+  }
+
+  return nullopt;
+}
+
+// Whether the address is the first of the mirrors of a byte of the ROM.
+bool Sweep::isCanonical(u24 address) const {
+  auto& rom = analysis->rom;
+  if (!rom.isROM(address)) {
+    return false;
+  }
+
+  // Mirrors differ in the high bits of the bank, or in the half of the bank.
+  auto offset = rom.translate(address);
+  for (u24 mask : {0x008000, 0x400000, 0x408000, 0x800000, 0x808000, 0xC00000,
+                   0xC08000}) {
+    auto mirror = address ^ mask;
+    if (mirror < address && rom.isROM(mirror) &&
+        rom.translate(mirror) == offset) {
+      return false;
+    }
+  }
+  return true;
+}
+
+// Whether a byte of the ROM is already accounted for.
+bool Sweep::isCovered(u24 address) const {
+  return covered.count(analysis->rom.translate(address));
+}
@@ -0,0 +1,64 @@
+#pragma once
+
+#include <cstddef>
+#include <optional>
+#include <unordered_set>
+
+#include "types.hpp"
+
+class Analysis;
+
+// Code found by the sweep, waiting to be reviewed.
+struct SweepCandidate {
+  SubroutinePC pc;           // Where the code starts.
+  InstructionPC end;         // Address following the last instruction.
+  std::size_t instructions;  // Number of decoded instructions.
+  int suspicion;             // How much the code looks like data.
+
+  template <class Archive>
+  void serialize(Archive& ar, const unsigned int) {
+    ar& pc;
+    ar& end;
+    ar& instructions;
+    ar& suspicion;
+  }
+};
+
+/**
+ * Walk the portions of the ROM that the analysis hasn't reached, trying every
+ * plausible start of code. The survivors are quarantined in the analysis
+ * until they're accepted or rejected.
+ */
+class Sweep {
+ public:
+  // Constructor.
+  Sweep(Analysis* analysis);
+
+  // Try up to the given number of starts, resuming from where the previous
+  // sweep stopped. Return true if the whole ROM has been swept.
+  bool run(std::size_t budget);
+
+  // Decode the code at the given address, if it passes the sanity checks.
+  std::optional<SweepCandidate> evaluate(u24 pc) const;
+
+  // Address past the end of the sweepable space.
+  static const u32 END = 0x1000000;
+  // Maximum number of instructions decoded from a single start.
+  static const std::size_t MAX_INSTRUCTIONS = 256;
+  // Minimum number of instructions of a plausible subroutine.
+  static const std::size_t MIN_INSTRUCTIONS = 3;
+  // Maximum suspicion score of a plausible subroutine.
+  static const int MAX_SUSPICION = 1;
+
+ private:
+  // Whether the address is the first of the mirrors of a byte of the ROM.
+  bool isCanonical(u24 address) const;
+  // Whether a byte of the ROM is already accounted for.
+  bool isCovered(u24 address) const;
+
+  // Pointer to the analysis.
+  Analysis* analysis;
+
+  // ROM offsets of the bytes already accounted for.
+  std::unordered_set<u24> covered;
+};
@@ -0,0 +1,51 @@
+#include "theme.hpp"
+
+using namespace std;
+
+// Built-in themes. The colorblind theme uses the Okabe-Ito palette, which
+// stays distinguishable with all common forms of color blindness.
+static const vector<Theme> THEMES = {
+    {"default", "mediumpurple", "seagreen", "darkorange", "darkmagenta",
+     "royalblue", "gold", "orangered", "white", "blue", "darkred", "gray",
+     "#FFFF99"},
+    {"mono", "", "", "", "", "", "", "", "", "", "", "", ""},
+    {"colorblind", "#CC79A7", "#009E73", "#E69F00", "#56B4E9", "#0072B2",
+     "#F0E442", "#D55E00", "white", "#0072B2", "#000000", "gray", "#FAF5C0"},
+};
+
+// Theme in use.
+static const Theme* currentTheme = &THEMES[0];
+
+// Return the built-in theme with the given name, if any.
+const Theme* Theme::find(const string& name) {
+  for (auto& theme : THEMES) {
+    if (theme.name == name) {
+      return &theme;
+    }
+  }
+  return nullptr;
+}
+
+// Return the names of the built-in themes.
+vector<string> Theme::names() {
+  vector<string> names;
+  for (auto& theme : THEMES) {
+    names.push_back(theme.name);
+  }
+  return names;
+}
+
+// Return the theme in use.
+const Theme& Theme::current() {
+  return *currentTheme;
+}
+
+// Use the built-in theme with the given name. Return false if none exists.
+bool Theme::select(const string& name) {
+  auto theme = find(name);
+  if (theme == nullptr) {
+    return false;
+  }
+  currentTheme = theme;
+  return true;
+}
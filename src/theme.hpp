@@ -0,0 +1,34 @@
+#pragma once
+
+#include <string>
+#include <vector>
+
+/**
+ * Colors used to highlight the disassembly and the lists of the GUI.
+ * Colors are names or `#RRGGBB` values, empty when a role is not colored.
+ */
+struct Theme {
+  std::string name;               // Name of the theme.
+  std::string assertion;          // Asserted state changes.
+  std::string verifiedAssertion;  // Verified assertions.
+  std::string guessAssertion;     // Assertions that are guesses.
+  std::string entryPoint;         // Entry points.
+  std::string jumpTable;          // Complete jump tables.
+  std::string partialJumpTable;   // Partial jump tables.
+  std::string unknown;            // Unknown state changes and problems.
+  std::string highlightedText;    // Text over a colored background.
+  std::string opcode;             // Opcodes.
+  std::string label;              // Labels and aliases of arguments.
+  std::string comment;            // Comments.
+  std::string currentLine;        // Line under the cursor.
+
+  // Return the built-in theme with the given name, if any.
+  static const Theme* find(const std::string& name);
+  // Return the names of the built-in themes.
+  static std::vector<std::string> names();
+
+  // Return the theme in use.
+  static const Theme& current();
+  // Use the built-in theme with the given name. Return false if none exists.
+  static bool select(const std::string& name);
+};
@@ -16,3 +16,15 @@ vector<u8> readBinaryFile(const string& path) {
   fclose(file);
   return buffer;
 }
+
+// Compute the CRC-32 of a sequence of bytes.
+u32 crc32(const vector<u8>& data) {
+  u32 crc = 0xFFFFFFFF;
+  for (auto byte : data) {
+    crc ^= byte;
+    for (int i = 0; i < 8; i++) {
+      crc = (crc >> 1) ^ (0xEDB88320 & -(crc & 1));
+    }
+  }
+  return ~crc;
+}
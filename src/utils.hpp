@@ -9,6 +9,9 @@
 // Read a whole file into memory.
 std::vector<u8> readBinaryFile(const std::string& path);
 
+// Compute the CRC-32 of a sequence of bytes.
+u32 crc32(const std::vector<u8>& data);
+
 // Format a string (like C++20's std::format).
 template <typename... Args>
 std::string format(const std::string& format, const Args&... args) {
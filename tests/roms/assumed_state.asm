@@ -0,0 +1,19 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  ldx #$0000                    ; $008000
+  sep #$20                      ; $008003
+  lda #$12                      ; $008005
+.loop:
+  jmp .loop                     ; $008007
+
+org $FFEA
+  dw nmi
+
+org $800A
+nmi:
+  lda #$1234                    ; $00800A
+  sep #$20                      ; $00800D
+  rep #$20                      ; $00800F
+  rti                           ; $008011
@@ -0,0 +1,12 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  rep #$30                      ; $008000
+  lda #$000F                    ; $008002
+  ldx #$9000                    ; $008005
+  ldy #$0000                    ; $008008
+  mvn $00,$7E                   ; $00800B
+  sta $2000                     ; $00800E
+.loop:
+  jmp .loop                     ; $008011
@@ -0,0 +1,15 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  jsr middle                    ; $008000
+  jsr middle                    ; $008003
+.loop:
+  jmp .loop                     ; $008006
+
+middle:
+  jsr innermost                 ; $008009
+  rts                           ; $00800C
+
+innermost:
+  rts                           ; $00800D
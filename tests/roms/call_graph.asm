@@ -0,0 +1,21 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  jsr first                     ; $008000
+  jsr (.table,x)                ; $008003
+.loop:
+  jmp .loop                     ; $008006
+.table:
+  dw first                      ; $008009
+
+first:
+  jsr second                    ; $00800B
+  rts                           ; $00800E
+
+second:
+  jsr third                     ; $00800F
+  rts                           ; $008012
+
+third:
+  rts                           ; $008013
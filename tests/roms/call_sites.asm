@@ -0,0 +1,16 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  jsr first                     ; $008000
+  jsr (.table,x)                ; $008003
+.loop:
+  jmp .loop                     ; $008006
+.table:
+  dw second                     ; $008009
+
+first:
+  jmp second                    ; $00800B
+
+second:
+  rts                           ; $00800E
@@ -0,0 +1,35 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  jsr matched                   ; $008000
+  jsr unclosed                  ; $008003
+  jsr nested                    ; $008006
+  jsr redundant                 ; $008009
+.loop:
+  jmp .loop                     ; $00800C
+
+matched:
+  sei                           ; $00800F
+  cli                           ; $008010
+  rts                           ; $008011
+
+unclosed:
+  sei                           ; $008012
+  bcs .return                   ; $008013
+  cli                           ; $008015
+.return:
+  rts                           ; $008016
+
+nested:
+  sei                           ; $008017
+  php                           ; $008018
+  sei                           ; $008019
+  plp                           ; $00801A
+  cli                           ; $00801B
+  rts                           ; $00801C
+
+redundant:
+  cli                           ; $00801D
+  cli                           ; $00801E
+  rts                           ; $00801F
@@ -0,0 +1,22 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  sep #$20                      ; $008000
+  lda #$7E                      ; $008002
+  pha                           ; $008004
+  plb                           ; $008005
+  lda $2000                     ; $008006
+  phk                           ; $008009
+  plb                           ; $00800A
+  lda $8000                     ; $00800B
+  jsr set_bank                  ; $00800E
+  lda $1234                     ; $008011
+.loop:
+  jmp .loop                     ; $008014
+
+set_bank:
+  lda $00                       ; $008017
+  pha                           ; $008019
+  plb                           ; $00801A
+  rts                           ; $00801B
@@ -0,0 +1,16 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  jsr routine                   ; $008000
+  nop                           ; $008003
+
+text:
+  db "HI!", $00                 ; $008004
+pointers:
+  dw routine, $1234             ; $008008
+bytes:
+  db $01, $02, $03              ; $00800C
+
+routine:
+  rts                           ; $00800F
@@ -0,0 +1,18 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  sep #$30                      ; $008000
+  lda $9000                     ; $008002
+  sta $10                       ; $008005
+  lda $9000,x                   ; $008007
+  sta $2100                     ; $00800A
+  lda.l $00A000                 ; $00800D
+  sta $0010                     ; $008011
+  jsr update                    ; $008014
+  jml $7E1000                   ; $008017
+
+update:
+  lda #$01                      ; $00801B
+  sta $7E0010                   ; $00801D
+  rts                           ; $008021
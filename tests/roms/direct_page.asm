@@ -0,0 +1,23 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  sep #$30                      ; $008000
+  lda $10                       ; $008002
+  sta $12                       ; $008004
+  jsr helper                    ; $008006
+  rep #$20                      ; $008009
+  lda #$0000                    ; $00800B
+  tcd                           ; $00800E
+  lda $10                       ; $00800F
+  jsr other_helper              ; $008011
+.loop:
+  jmp .loop                     ; $008014
+
+helper:
+  ldx $20                       ; $008017
+  rts                           ; $008019
+
+other_helper:
+  lda $30                       ; $00801A
+  rts                           ; $00801C
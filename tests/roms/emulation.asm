@@ -0,0 +1,18 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  sei                           ; $008000
+  lda #$00                      ; $008001
+  clc                           ; $008003
+  xce                           ; $008004
+  ldx #$00                      ; $008005
+  rep #$30                      ; $008007
+  lda #$1234                    ; $008009
+  sec                           ; $00800C
+  xce                           ; $00800D
+  lda #$12                      ; $00800E
+  rep #$30                      ; $008010
+  lda #$12                      ; $008012
+.loop:
+  jmp .loop                     ; $008014
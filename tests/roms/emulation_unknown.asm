@@ -0,0 +1,15 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  jsr switch                    ; $008000
+.loop:
+  jmp .loop                     ; $008003
+
+switch:
+  jsr helper                    ; $008006
+  xce                           ; $008009
+  rts                           ; $00800A
+
+helper:
+  rts                           ; $00800B
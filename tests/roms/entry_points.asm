@@ -0,0 +1,13 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  jmp reset                     ; $008000
+
+handler_a:
+  lda #$1234                    ; $008003
+  rts                           ; $008006
+
+handler_b:
+  ldx #$00                      ; $008007
+  rts                           ; $008009
@@ -0,0 +1,17 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  jsr clear                     ; $008000
+  jsr clear_y                   ; $008003
+.loop:
+  jmp .loop                     ; $008006
+
+clear:
+  rep #$30                      ; $008009
+  ldx #$0000                    ; $00800B
+
+clear_y:
+  ldy #$0000                    ; $00800E
+  sep #$20                      ; $008011
+  rts                           ; $008013
@@ -0,0 +1,18 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  rep #$30                      ; $008000
+  lda $00                       ; $008002
+  asl                           ; $008004
+  tax                           ; $008005
+  jmp (.table,x)                ; $008006
+.table:
+  dw .first                     ; $008009
+  dw .second                    ; $00800B
+.first:
+  lda #$0001                    ; $00800D
+  bra .first                    ; $008010
+.second:
+  lda #$0002                    ; $008012
+  bra .second                   ; $008015
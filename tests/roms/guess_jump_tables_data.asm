@@ -0,0 +1,21 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  rep #$30                      ; $008000
+  lda $00                       ; $008002
+  asl                           ; $008004
+  tax                           ; $008005
+  jmp (.table,x)                ; $008006
+.table:
+  dw .first                     ; $008009
+  dw .second                    ; $00800B
+.values:
+  dw reset                      ; $00800D
+  dw .table                     ; $00800F
+.first:
+  lda .values                   ; $008011
+  bra .first                    ; $008014
+.second:
+  lda .values+2                 ; $008016
+  bra .second                   ; $008019
@@ -0,0 +1,13 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  lda $10                       ; $008000
+  beq .poll                     ; $008002
+.halt:
+  wai                           ; $008004
+  bra .halt                     ; $008005
+.poll:
+  lda $11                       ; $008007
+  beq .poll                     ; $008009
+  rts                           ; $00800B
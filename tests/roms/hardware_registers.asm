@@ -0,0 +1,13 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  sep #$20                      ; $008000
+  lda #$0F                      ; $008002
+  sta $2100                     ; $008004
+  lda #$7E                      ; $008007
+  pha                           ; $008009
+  plb                           ; $00800A
+  sta $2100                     ; $00800B
+.loop:
+  jmp .loop                     ; $00800E
@@ -0,0 +1,20 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  rep #$30                      ; $008000
+  ldx #$0000                    ; $008002
+  inx                           ; $008005
+  inx                           ; $008006
+  jmp (.jumptable,x)            ; $008007
+.jumptable:
+  dw $8100                      ; $00800A
+  dw $8200                      ; $00800C
+
+org $8100
+x0:
+  rts                           ; $008100
+
+org $8200
+x1:
+  rts                           ; $008200
@@ -0,0 +1,25 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  rep #$30                      ; $008000
+  ldx #$0000                    ; $008002
+  jsr dispatch                  ; $008005
+  ldx #$0002                    ; $008008
+  jsr dispatch                  ; $00800B
+.loop:
+  jmp .loop                     ; $00800E
+
+dispatch:
+  jmp (.jumptable,x)            ; $008011
+.jumptable:
+  dw $8100                      ; $008014
+  dw $8200                      ; $008016
+
+org $8100
+x0:
+  rts                           ; $008100
+
+org $8200
+x1:
+  rts                           ; $008200
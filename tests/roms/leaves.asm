@@ -0,0 +1,17 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  jsr copy                      ; $008000
+  jsr dispatch                  ; $008003
+.loop:
+  jmp .loop                     ; $008006
+
+copy:
+  lda $10                       ; $008009
+  sta $20                       ; $00800B
+  rts                           ; $00800D
+
+dispatch:
+  jsr ($9000,x)                 ; $00800E
+  rts                           ; $008011
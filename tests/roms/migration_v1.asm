@@ -0,0 +1,25 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  sep #$30                      ; $008000
+  jsr init                      ; $008002
+  jsr print                     ; $008005
+.loop:
+  jmp .loop                     ; $008008
+
+init:
+  lda #$80                      ; $00800B
+  sta $2100                     ; $00800D
+  rts                           ; $008010
+
+print:
+  ldx #$00                      ; $008011
+.next:
+  lda $9000,x                   ; $008013
+  beq .done                     ; $008016
+  sta $2118                     ; $008018
+  inx                           ; $00801B
+  bra .next                     ; $00801C
+.done:
+  rts                           ; $00801E
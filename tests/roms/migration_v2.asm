@@ -0,0 +1,26 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  sep #$30                      ; $008000
+  nop                           ; $008002
+  jsr init                      ; $008003
+  jsr print                     ; $008006
+.loop:
+  jmp .loop                     ; $008009
+
+init:
+  lda #$80                      ; $00800C
+  sta $2100                     ; $00800E
+  rts                           ; $008011
+
+print:
+  ldx #$00                      ; $008012
+.next:
+  lda $9000,x                   ; $008014
+  beq .done                     ; $008017
+  sta $2118                     ; $008019
+  inx                           ; $00801C
+  bra .next                     ; $00801D
+.done:
+  rts                           ; $00801F
@@ -0,0 +1,16 @@
+incsrc lorom.asm
+
+org $FFEA
+  dw nmi
+
+org $8000
+reset:
+  jmp reset                     ; $008000
+
+nmi:
+  php                           ; $008003
+  rep #$30                      ; $008004
+  lda #$1234                    ; $008006
+  ldx #$5678                    ; $008009
+  plp                           ; $00800C
+  rti                           ; $00800D
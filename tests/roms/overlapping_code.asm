@@ -0,0 +1,13 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  rep #$20                      ; $008000
+  jsr handler                   ; $008002
+.loop:
+  jmp .loop                     ; $008005
+
+handler:
+  lda #$60                      ; $008008 (LDA #$6060 with 16-bits A)
+  rts                           ; $00800A
+  rts                           ; $00800B
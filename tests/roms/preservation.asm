@@ -0,0 +1,32 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  sep #$30                      ; $008000
+  jsr preserves                 ; $008002
+  jsr calls_clobbers            ; $008005
+  jsr conditionally_clobbers    ; $008008
+.loop:
+  jmp .loop                     ; $00800B
+
+preserves:
+  phx                           ; $00800E
+  beq .skip                     ; $00800F
+  ldx #$00                      ; $008011
+.skip:
+  plx                           ; $008013
+  rts                           ; $008014
+
+clobbers:
+  ldx #$00                      ; $008015
+  rts                           ; $008017
+
+conditionally_clobbers:
+  bne .skip                     ; $008018
+  ldy #$00                      ; $00801A
+.skip:
+  rts                           ; $00801C
+
+calls_clobbers:
+  jsr clobbers                  ; $00801D
+  rts                           ; $008020
@@ -0,0 +1,15 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  jsr target                    ; $008000
+  pea target                    ; $008003
+  pea target-1                  ; $008006
+  pea $2100                     ; $008009
+  pea $1234                     ; $00800C
+  pei ($10)                     ; $00800F
+.loop:
+  jmp .loop                     ; $008011
+
+target:
+  rts                           ; $008014
@@ -0,0 +1,19 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  rep #$30                      ; $008000
+  lda #$1234                    ; $008002
+  pha                           ; $008005
+  lda #$0000                    ; $008006
+  pla                           ; $008009
+  ldy #$0042                    ; $00800A
+  phy                           ; $00800D
+  ldy #$0000                    ; $00800E
+  ply                           ; $008011
+  pha                           ; $008012
+  sep #$20                      ; $008013
+  pla                           ; $008015
+  phx                           ; $008016
+  pla                           ; $008017
+  rts                           ; $008018
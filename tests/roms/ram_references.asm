@@ -0,0 +1,16 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  sep #$20                      ; $008000
+  lda #$0F                      ; $008002
+  sta $2100                     ; $008004
+  sta $10                       ; $008007
+  jsr update                    ; $008009
+  jml $7E1000                   ; $00800C
+
+update:
+  inc $0010                     ; $008010
+  lda $7E0010                   ; $008013
+  sta $7F2000                   ; $008017
+  rts                           ; $00801B
@@ -0,0 +1,14 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  jsr countdown                 ; $008000
+.loop:
+  jmp .loop                     ; $008003
+
+countdown:
+  dex                           ; $008006
+  beq .done                     ; $008007
+  jsr countdown                 ; $008009
+.done:
+  rts                           ; $00800C
@@ -0,0 +1,21 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  sep #$30                      ; $008000
+  ldx #$1F                      ; $008002
+.clear:
+  stz $0200,x                   ; $008004
+  dex                           ; $008007
+  bpl .clear                    ; $008008
+
+  lda #$FF                      ; $00800A
+  ldx #$00                      ; $00800C
+.fill:
+  sta $7E2000,x                 ; $00800E
+  inx                           ; $008012
+  cpx #$10                      ; $008013
+  bne .fill                     ; $008015
+
+.loop:
+  jmp .loop                     ; $008017
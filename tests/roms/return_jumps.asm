@@ -0,0 +1,20 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  jsr dispatch                  ; $008000
+.loop:
+  jmp .loop                     ; $008003
+
+dispatch:
+  pea target-1                  ; $008006
+  rts                           ; $008009
+
+target:
+  rep #$30                      ; $00800A
+  lda.w #target2-1              ; $00800C
+  pha                           ; $00800F
+  rts                           ; $008010
+
+target2:
+  rts                           ; $008011
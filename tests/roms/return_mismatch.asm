@@ -0,0 +1,14 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  jsr short_call                ; $008000
+  jsl long_call                 ; $008003
+.loop:
+  jmp .loop                     ; $008007
+
+short_call:
+  rtl                           ; $00800A
+
+long_call:
+  rts                           ; $00800B
@@ -0,0 +1,43 @@
+arch 65816
+lorom
+
+;; Empty ROM. At reset, the SA-1 maps the first 32KB like a LoROM.
+org $008000
+  fill $8000
+
+org $008000
+reset:
+  sei                           ; $008000
+  clc                           ; $008001
+  xce                           ; $008002
+.loop:
+  jmp .loop                     ; $008003
+
+
+;; Minimal ROM header.
+org $00FFC0
+title:
+  db "TEST"
+
+;; SA-1 mapping.
+org $00FFD5
+map_mode:
+  db $23
+
+;; SA-1 chipset, with battery-backed RAM.
+org $00FFD6
+rom_type:
+  db $35
+
+;; 2048 bytes.
+org $00FFD7
+rom_size:
+  db $01
+
+org $00FFEA
+nmi_vector:
+  dw $0000
+
+org $00FFFC
+reset_vector:
+  dw reset
@@ -0,0 +1,9 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  sep #$30                      ; $008000
+  lda #$00                      ; $008002
+  db $42, $00                   ; $008004 (WDM)
+.loop:
+  jmp .loop                     ; $008006
@@ -0,0 +1,23 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  sep #$20                      ; $008000
+  jsr graphics                  ; $008002
+  jsr input                     ; $008005
+.loop:
+  jmp .loop                     ; $008008
+
+graphics:
+  lda #$80                      ; $00800B
+  sta $2100                     ; $00800D
+  lda #$01                      ; $008010
+  sta $420B                     ; $008012
+  sta $7E2100                   ; $008015
+  rts                           ; $008019
+
+input:
+  lda $4218                     ; $00801A
+  sta $80420B                   ; $00801D
+  sta $0000                     ; $008021
+  rts                           ; $008024
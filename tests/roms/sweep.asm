@@ -0,0 +1,22 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  sep #$30                      ; $008000
+.loop:
+  jmp .loop                     ; $008002
+
+org $8010
+orphan:
+  rep #$20                      ; $008010
+  lda #$1234                    ; $008012
+  sta $00                       ; $008015
+  rts                           ; $008017
+
+org $8020
+lone_return:
+  db $60                        ; $008020 (RTS)
+
+org $8030
+table:
+  db $A9, $00, $00, $60         ; $008030 (LDA, BRK)
@@ -0,0 +1,17 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  rep #$30                      ; $008000
+  cop #$01                      ; $008002
+  sep #$20                      ; $008004
+  lda #$12                      ; $008006
+.loop:
+  jmp .loop                     ; $008008
+
+org $8100
+cop_handler:
+  php                           ; $008100
+  sep #$30                      ; $008101
+  plp                           ; $008103
+  rti                           ; $008104
@@ -0,0 +1,11 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  beq .skip                     ; $008000
+  jsr helper                    ; $008002
+.skip:
+  jmp .skip                     ; $008005
+
+helper:
+  rts                           ; $008008
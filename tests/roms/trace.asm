@@ -0,0 +1,14 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  sep #$30                      ; $008000
+  bcc .skip                     ; $008002
+  jml $7E0000                   ; $008004
+.skip:
+  jsr sub                       ; $008008
+  rts                           ; $00800B
+
+sub:
+  rep #$20                      ; $00800C
+  rts                           ; $00800E
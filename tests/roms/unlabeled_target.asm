@@ -0,0 +1,15 @@
+incsrc lorom.asm
+
+org $8000
+reset:
+  bra .start                    ; $008000
+.data:
+  db $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF
+  db $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF
+.start:
+  lda $10                       ; $008012
+  beq .data                     ; $008014
+  jmp $9000                     ; $008016
+
+org $9000
+  db $FF                        ; $009000
@@ -1,10 +1,14 @@
 #include <catch2/catch.hpp>
+#include <filesystem>
+#include <fstream>
+#include <set>
 
 #include "asar.hpp"
 
 #include "analysis.hpp"
 #include "assertion.hpp"
 #include "cpu.hpp"
+#include "disassembly.hpp"
 
 using namespace std;
 
@@ -12,8 +16,8 @@ TEST_CASE("Assertions work correctly", "[analysis]") {
   Analysis analysis(*assemble("assertions"));
   analysis.run();
 
-  // Test there are two subroutines (+ NMI).
-  REQUIRE(analysis.subroutines.size() - 1 == 2);
+  // Test there are two subroutines.
+  REQUIRE(analysis.subroutines.size() == 2);
 
   // Test there's a `reset` subroutine with the correct number of instructions.
   auto& resetSubroutine = analysis.subroutines.at(0x8000);
@@ -51,6 +55,205 @@ TEST_CASE("Assertions work correctly", "[analysis]") {
   REQUIRE(resetSubroutine.unknownStateChanges.empty());
 }
 
+TEST_CASE("Assertions have a confidence tier", "[analysis]") {
+  Analysis analysis(*assemble("assertions"));
+  analysis.run();
+
+  // Assertions are probable unless stated otherwise.
+  analysis.addAssertion(Assertion(AssertionType::Instruction), 0x8000, 0x8000);
+  analysis.addAssertion(
+      Assertion(AssertionType::Subroutine, StateChange(),
+                AssertionConfidence::Guess),
+      0x8005, 0x8005);
+  REQUIRE(analysis.getAssertion(0x8000, 0x8000)->confidence ==
+          AssertionConfidence::Probable);
+  REQUIRE(analysis.getAssertion(0x8005, 0x8005)->confidence ==
+          AssertionConfidence::Guess);
+
+  // Assertions can be filtered by confidence.
+  REQUIRE(analysis.assertionPCs().size() == 2);
+  REQUIRE(analysis.assertionPCs(AssertionConfidence::Guess) ==
+          vector<PCPair>{{0x8005, 0x8005}});
+  REQUIRE(analysis.assertionPCs(AssertionConfidence::Verified).empty());
+
+  // The confidence can be changed after the fact.
+  REQUIRE(analysis.setAssertionConfidence(0x8005, 0x8005,
+                                          AssertionConfidence::Verified));
+  REQUIRE(!analysis.setAssertionConfidence(0x8001, 0x8000,
+                                           AssertionConfidence::Verified));
+  REQUIRE(analysis.assertionPCs(AssertionConfidence::Verified) ==
+          vector<PCPair>{{0x8005, 0x8005}});
+
+  // The confidence survives saving and loading.
+  analysis.save();
+  Analysis loaded(*assemble("assertions"));
+  REQUIRE(loaded.load());
+  REQUIRE(loaded.getAssertion(0x8000, 0x8000)->confidence ==
+          AssertionConfidence::Probable);
+  REQUIRE(loaded.getAssertion(0x8005, 0x8005)->confidence ==
+          AssertionConfidence::Verified);
+
+  remove(loaded.rom.savePath().c_str());
+}
+
+TEST_CASE("RAM variables can be named", "[analysis]") {
+  Analysis analysis(*assemble("state_change"));
+
+  // Mirrors of the low WRAM are named by their address in bank $7E.
+  REQUIRE(analysis.labelRAM("player_x", 0x0020));
+  REQUIRE(analysis.ramLabels == map<u24, string>{{0x7E0020, "player_x"}});
+
+  // Names must be valid identifiers, unique, and in WRAM.
+  REQUIRE_FALSE(analysis.labelRAM("player_x", 0x7E0022));
+  REQUIRE_FALSE(analysis.labelRAM("player.y", 0x7E0022));
+  REQUIRE_FALSE(analysis.labelRAM("2player", 0x7E0022));
+  REQUIRE_FALSE(analysis.labelRAM("rom_data", 0x808000));
+  REQUIRE(analysis.labelRAM("player_y", 0x7E0022));
+
+  // Renaming a variable frees its old name.
+  REQUIRE(analysis.labelRAM("player_x_pos", 0x7E0020));
+  REQUIRE(analysis.labelRAM("player_x", 0x7E0024));
+
+  // The names survive saving and loading.
+  analysis.save();
+  Analysis loaded(*assemble("state_change"));
+  REQUIRE(loaded.load());
+  REQUIRE(loaded.ramLabels == analysis.ramLabels);
+
+  // An empty name removes the variable's name.
+  REQUIRE(loaded.labelRAM("", 0x7E0022));
+  REQUIRE(loaded.ramLabels.size() == 2);
+
+  remove(loaded.rom.savePath().c_str());
+}
+
+TEST_CASE("Bookmarks survive saving and loading", "[analysis]") {
+  Analysis analysis(*assemble("state_change"));
+  analysis.addBookmark(0x8000, "Start");
+  analysis.addBookmark(0x7E0010);
+  REQUIRE(analysis.removeBookmark(0x7E0010));
+  REQUIRE_FALSE(analysis.removeBookmark(0x7E0010));
+  analysis.addBookmark(0x800E, "State change");
+
+  analysis.save();
+  Analysis loaded(*assemble("state_change"));
+  REQUIRE(loaded.load());
+  REQUIRE(loaded.bookmarks == map<u24, string>{{0x8000, "Start"},
+                                               {0x800E, "State change"}});
+
+  remove(loaded.rom.savePath().c_str());
+}
+
+TEST_CASE("Annotations survive saving and loading", "[analysis]") {
+  Analysis analysis(*assemble("data_blocks"));
+  analysis.run();
+  REQUIRE(analysis.addDataBlock(0x8004, 0x8008, DataType::Text));
+  REQUIRE(analysis.addWatch(0x7E0010, "Player X"));
+  REQUIRE(analysis.addWatch(0x000020));
+  REQUIRE_FALSE(analysis.addWatch(0x8000));
+  REQUIRE(analysis.removeWatch(0x7E0020));
+  analysis.setReviewFlag(0x8000);
+  analysis.setReviewFlag(0x8002);
+  analysis.setReviewFlag(0x8002, false);
+
+  analysis.save();
+  Analysis loaded(*assemble("data_blocks"));
+  REQUIRE(loaded.load());
+  REQUIRE(loaded.dataBlocks.size() == 1);
+  REQUIRE(loaded.dataBlocks.at(0x8004).end == 0x8008);
+  REQUIRE(loaded.dataBlocks.at(0x8004).type == DataType::Text);
+  REQUIRE(loaded.watches == map<u24, string>{{0x7E0010, "Player X"}});
+  REQUIRE(loaded.reviewFlags == set<InstructionPC>{0x8000});
+
+  remove(loaded.rom.savePath().c_str());
+}
+
+TEST_CASE("Analyses saved by older versions can be loaded", "[analysis]") {
+  Analysis analysis(*assemble("state_change"));
+  analysis.addBookmark(0x8000, "Start");
+  // Older versions saved the analysis without a header.
+  ofstream(analysis.rom.savePath()) << analysis.saveState();
+
+  Analysis loaded(*assemble("state_change"));
+  REQUIRE(loaded.load());
+  REQUIRE(loaded.bookmarks.at(0x8000) == "Start");
+
+  auto saves = loaded.savedAnalyses();
+  REQUIRE(saves.size() == 1);
+  REQUIRE(saves[0].name.empty());
+  REQUIRE_FALSE(saves[0].timestamp.has_value());
+
+  remove(loaded.rom.savePath().c_str());
+}
+
+TEST_CASE("Saved states restore the annotations", "[analysis]") {
+  Analysis analysis(*assemble("state_change"));
+  analysis.run();
+  auto state = analysis.saveState();
+
+  analysis.addBookmark(0x8000, "Start");
+  analysis.addEntryPoint("extra", 0x800E);
+  analysis.run();
+  REQUIRE(analysis.subroutines.at(0x800E).label == "extra");
+
+  analysis.restoreState(state);
+  analysis.run();
+  REQUIRE(analysis.bookmarks.empty());
+  REQUIRE(analysis.subroutines.at(0x800E).label != "extra");
+}
+
+TEST_CASE("Saved states restore the jump tables", "[analysis]") {
+  Analysis analysis(*assemble("jump_tables"));
+  analysis.run();
+  auto state = analysis.saveState();
+
+  analysis.defineJumpTable(0x8000, {0, 2});
+  analysis.run();
+  REQUIRE(analysis.subroutines.count(0x8100));
+
+  analysis.restoreState(state);
+  analysis.run();
+  REQUIRE_FALSE(analysis.subroutines.count(0x8100));
+}
+
+TEST_CASE("Saved analyses detect changes to the ROM", "[analysis]") {
+  Analysis analysis(*assemble("state_change"));
+  analysis.save();
+
+  Analysis loaded(*assemble("state_change"));
+  REQUIRE(loaded.load());
+  REQUIRE_FALSE(loaded.romChanged());
+
+  // Pretend the analysis was saved before patching a different ROM.
+  Analysis patched(*assemble("call_chain"));
+  filesystem::copy_file(analysis.rom.savePath(), patched.rom.savePath(),
+                        filesystem::copy_options::overwrite_existing);
+  REQUIRE(patched.load());
+  REQUIRE(patched.romChanged());
+
+  remove(analysis.rom.savePath().c_str());
+  remove(patched.rom.savePath().c_str());
+}
+
+TEST_CASE("Annotations survive a reanalysis", "[analysis]") {
+  Analysis analysis(*assemble("state_change"));
+  analysis.run();
+
+  analysis.renameLabel("init", 0x800E);
+  analysis.comments[0x8002] = "Initialize.";
+  analysis.addAssertion(Assertion(AssertionType::Instruction), 0x8005, 0x8000);
+
+  // Running again rebuilds the results from scratch...
+  analysis.subroutines.at(0x800E).instructions.clear();
+  analysis.run();
+  REQUIRE(analysis.subroutines.at(0x800E).instructions.size() == 2);
+
+  // ...but keeps the annotations.
+  REQUIRE(analysis.subroutines.at(0x800E).label == "init");
+  REQUIRE(analysis.anyInstruction(0x8002)->comment() == "Initialize.");
+  REQUIRE(analysis.getAssertion(0x8005, 0x8000).has_value());
+}
+
 TEST_CASE("Instructions that change A/X work correctly", "[analysis]") {
   Analysis analysis(*assemble("change_registers"));
   analysis.run();
@@ -81,10 +284,11 @@ TEST_CASE("Instructions that change A/X work correctly", "[analysis]") {
 
 TEST_CASE("State inference correctly simplifies state changes", "[analysis]") {
   Analysis analysis(*assemble("elidable_state_change"));
+  analysis.settings.emulationReset = false;
   analysis.run();
 
-  // Test there are two subroutines (+ NMI).
-  REQUIRE(analysis.subroutines.size() - 1 == 2);
+  // Test there are two subroutines.
+  REQUIRE(analysis.subroutines.size() == 2);
 
   // Test there's a `reset` subroutine with the correct number of instructions.
   auto& resetSubroutine = analysis.subroutines.at(0x8000);
@@ -102,12 +306,108 @@ TEST_CASE("State inference correctly simplifies state changes", "[analysis]") {
   REQUIRE(stateChange.isEmpty());
 }
 
+TEST_CASE("Entry points report the state they assume", "[analysis]") {
+  Analysis analysis(*assemble("assumed_state"));
+  analysis.settings.emulationReset = false;
+  analysis.run();
+
+  // The LDA comes after a SEP, so it says nothing about the entry state.
+  auto& subroutine = analysis.subroutines.at(0x8000);
+  REQUIRE(subroutine.assumedState == StateChange(nullopt, false));
+
+  auto markdown = Disassembly(&analysis).subroutineMarkdown(0x8000);
+  REQUIRE(markdown.find("- **Assumed entry state:** X: 16-bits\n") !=
+          string::npos);
+
+  // The assumption is only a suggestion: unlike in other subroutines, it
+  // doesn't make the SEP/REP pair of the NMI handler a no-op.
+  auto& nmi = analysis.subroutines.at(0x800A);
+  REQUIRE(nmi.assumedState == StateChange(false, nullopt));
+  REQUIRE(nmi.knownStateChanges.at(0x8011) == StateChange(false, nullopt));
+}
+
+TEST_CASE("The CPU state at an instruction can be recovered", "[analysis]") {
+  Analysis analysis(*assemble("assumed_state"));
+  analysis.settings.emulationReset = false;
+  analysis.run();
+
+  // The LDA comes right after SEP #$20.
+  auto snapshot = analysis.cpuSnapshot(0x8005, 0x8000);
+  REQUIRE(snapshot.has_value());
+  REQUIRE(snapshot->state.m == true);
+  REQUIRE(snapshot->state.x == false);
+  REQUIRE(snapshot->x == 0x0000);
+  REQUIRE_FALSE(snapshot->a.has_value());
+  REQUIRE(snapshot->dataBank == 0x00);
+
+  // The value loaded by the LDA is known at the next instruction.
+  REQUIRE(analysis.cpuSnapshot(0x8007, 0x8000)->a == 0x12);
+
+  // Instructions that are never reached have no state.
+  REQUIRE_FALSE(analysis.cpuSnapshot(0x8008, 0x8000).has_value());
+
+  auto description = Disassembly::snapshotDescription(*snapshot);
+  REQUIRE(description.find("A: 8-bits, X: 16-bits\n") == 0);
+  REQUIRE(description.find("X: $0000\n") != string::npos);
+}
+
+TEST_CASE("The CPU state is recovered by replaying a subroutine",
+          "[analysis]") {
+  Analysis analysis(*assemble("trace"));
+  analysis.settings.emulationReset = false;
+  analysis.run();
+  auto instructionCount = analysis.instructions.size();
+
+  // Inside a called subroutine, nothing is known about the registers.
+  auto snapshot = analysis.cpuSnapshot(0x800E, 0x800C);
+  REQUIRE(snapshot.has_value());
+  REQUIRE(snapshot->state.m == false);
+  REQUIRE(snapshot->directPage == DirectPageStatus::Unknown);
+  REQUIRE_FALSE(snapshot->dataBank.has_value());
+
+  // The caller takes the state change of the callee from the analysis.
+  REQUIRE(analysis.cpuSnapshot(0x800B, 0x8000)->state.m == false);
+  REQUIRE(analysis.instructions.size() == instructionCount);
+}
+
+TEST_CASE("Registers survive a round trip through the stack", "[analysis]") {
+  Analysis analysis(*assemble("push_pull"));
+  analysis.settings.emulationReset = false;
+  analysis.run();
+
+  // PHA / LDA / PLA restores A, PHY / LDY / PLY restores Y.
+  REQUIRE(analysis.cpuSnapshot(0x800A, 0x8000)->a == 0x1234);
+  REQUIRE(analysis.cpuSnapshot(0x8012, 0x8000)->y == 0x0042);
+
+  // A 16-bit push popped in 8-bit mode gives an unknown value.
+  REQUIRE_FALSE(analysis.cpuSnapshot(0x8016, 0x8000)->a.has_value());
+
+  // Pulling into A what PHX pushed is a stack manipulation.
+  auto& subroutine = analysis.subroutines.at(0x8000);
+  REQUIRE(subroutine.unknownStateChanges.at(0x8017).unknownReason ==
+          UnknownReason::StackManipulation);
+  REQUIRE_FALSE(analysis.cpuSnapshot(0x8018, 0x8000).has_value());
+}
+
+TEST_CASE("Loops with no way out are tagged as hang points", "[analysis]") {
+  Analysis infiniteLoop(*assemble("infinite_loop"));
+  infiniteLoop.run();
+  REQUIRE(infiniteLoop.subroutines.at(0x8000).hangPoints ==
+          set<InstructionPC>{0x8000});
+
+  // The polling loop can exit, the WAI loop can't.
+  Analysis analysis(*assemble("hang_points"));
+  analysis.run();
+  REQUIRE(analysis.subroutines.at(0x8000).hangPoints ==
+          set<InstructionPC>{0x8004});
+}
+
 TEST_CASE("Loops do not cause the analysis to hang", "[analysis]") {
   Analysis analysis(*assemble("infinite_loop"));
   analysis.run();
 
-  // Check there is a single subroutine (+ NMI) with one instruction.
-  REQUIRE(analysis.subroutines.size() - 1 == 1);
+  // Check there is a single subroutine with one instruction.
+  REQUIRE(analysis.subroutines.size() == 1);
   REQUIRE(analysis.subroutines.at(0x8000).pc == 0x8000);
   REQUIRE(analysis.subroutines.at(0x8000).instructions.size() == 1);
 
@@ -145,18 +445,476 @@ TEST_CASE("Jump tables are handled correctly", "[analysis]") {
   // Verify that the subroutines pointed by
   // the jumptable have been explored.
   {
-    REQUIRE(analysis.subroutines.size() - 1 == 3);
+    REQUIRE(analysis.subroutines.size() == 3);
     REQUIRE(analysis.subroutines.count(0x8100) == 1);
     REQUIRE(analysis.subroutines.count(0x8200) == 1);
   }
 }
 
+TEST_CASE("Jumps through a table indexed by a known X are followed",
+          "[analysis]") {
+  Analysis analysis(*assemble("indexed_jump"));
+  analysis.settings.emulationReset = false;
+  analysis.run();
+
+  // X is 2 at the jump, so only the second entry of the table is taken.
+  auto& resetSubroutine = analysis.subroutines.at(0x8000);
+  REQUIRE_FALSE(
+      resetSubroutine.isUnknownBecauseOf(UnknownReason::IndirectJump));
+  REQUIRE(resetSubroutine.instructions.count(0x8200) == 1);
+  REQUIRE(resetSubroutine.instructions.count(0x8100) == 0);
+  REQUIRE(analysis.followTargets(0x8007, 0x8000) ==
+          vector<PCPair>{{0x8200, 0x8000}});
+
+  // The jump is listed as resolved, with the entry taken.
+  REQUIRE(analysis.indirectJumps(InstructionType::Jump) ==
+          vector<InstructionPC>{0x8007});
+  auto& jumpTable = analysis.jumpTables.at(0x8007);
+  REQUIRE(jumpTable.status == JumpTableStatus::Emulated);
+  REQUIRE(jumpTable.targets == map<optional<u16>, InstructionPC>{{2, 0x8200}});
+}
+
+TEST_CASE("Jumps indexed by X take an entry for each known X", "[analysis]") {
+  Analysis analysis(*assemble("indexed_jumps"));
+  analysis.settings.emulationReset = false;
+  analysis.run();
+
+  // `dispatch` is called with X = 0, then with X = 2.
+  auto& dispatch = analysis.subroutines.at(0x8011);
+  REQUIRE(dispatch.instructions.count(0x8100));
+  REQUIRE(dispatch.instructions.count(0x8200));
+  REQUIRE(analysis.jumpTables.at(0x8011).targets ==
+          map<optional<u16>, InstructionPC>{{0, 0x8100}, {2, 0x8200}});
+
+  // Running the analysis again doesn't keep stale entries.
+  analysis.run();
+  REQUIRE(analysis.jumpTables.at(0x8011).targets.size() == 2);
+  REQUIRE(analysis.jumpTables.at(0x8011).status == JumpTableStatus::Emulated);
+}
+
+TEST_CASE("Indirect jumps are grouped by kind", "[analysis]") {
+  Analysis analysis(*assemble("jump_tables"));
+  analysis.run();
+
+  // The only indirect control flow is an unresolved call.
+  REQUIRE(analysis.indirectJumps(InstructionType::Call) ==
+          vector<InstructionPC>{0x8000});
+  REQUIRE(analysis.indirectJumps(InstructionType::Jump).empty());
+  REQUIRE(analysis.jumpTables.at(0x8000).status == JumpTableStatus::Unknown);
+  REQUIRE(analysis.jumpTables.at(0x8000).targets.empty());
+
+  // Once asserted, the call is resolved with two targets.
+  analysis.defineJumpTable(0x8000, {0, 2});
+  analysis.run();
+  REQUIRE(analysis.indirectJumps(InstructionType::Call) ==
+          vector<InstructionPC>{0x8000});
+  REQUIRE(analysis.jumpTables.at(0x8000).status == JumpTableStatus::Partial);
+  REQUIRE(analysis.jumpTables.at(0x8000).targets.size() == 2);
+}
+
+TEST_CASE("Returns to pushed addresses are followed", "[analysis]") {
+  Analysis analysis(*assemble("return_jumps"));
+  analysis.settings.emulationReset = false;
+  analysis.run();
+
+  // Both PEA + RTS and PHA + RTS jump to the pushed address plus one.
+  REQUIRE(analysis.returnJumps() ==
+          vector<pair<PCPair, InstructionPC>>{{{0x8009, 0x8006}, 0x800A},
+                                              {{0x8010, 0x8006}, 0x8011}});
+  REQUIRE(analysis.followTargets(0x8009, 0x8006) ==
+          vector<PCPair>{{0x800A, 0x8006}});
+
+  // The stack is balanced again by the time the subroutine returns.
+  auto& subroutine = analysis.subroutines.at(0x8006);
+  REQUIRE(subroutine.instructions.count(0x8011));
+  REQUIRE(subroutine.unknownStateChanges.empty());
+  REQUIRE(subroutine.knownStateChanges.size() == 1);
+}
+
+TEST_CASE("Returns not matching their call are suspect", "[analysis]") {
+  Analysis analysis(*assemble("return_mismatch"));
+  // The JSL is only reached from its own entry point.
+  analysis.addEntryPoint("long_caller", 0x8003);
+  analysis.run();
+
+  auto reason = [&](SubroutinePC pc, InstructionPC stopPC) {
+    return analysis.subroutines.at(pc).unknownStateChanges.at(stopPC)
+        .unknownReason;
+  };
+  // JSR returning with RTL, and JSL returning with RTS.
+  REQUIRE(reason(0x800A, 0x800A) == UnknownReason::ReturnMismatch);
+  REQUIRE(reason(0x800B, 0x800B) == UnknownReason::ReturnMismatch);
+  REQUIRE(analysis.returnJumps().empty());
+}
+
+TEST_CASE("Statistics summarize the coverage of the analysis",
+          "[analysis]") {
+  Analysis analysis(*assemble("jump_tables"));
+  analysis.run();
+
+  // Only the unresolved call has been decoded.
+  auto statistics = analysis.statistics();
+  REQUIRE(statistics.codeBytes == 3);
+  REQUIRE(statistics.romSize == analysis.rom.realSize());
+  REQUIRE(statistics.coverage() == 300.0 / statistics.romSize);
+  REQUIRE(statistics.subroutines == 1);
+  REQUIRE(statistics.unknownSubroutines == 1);
+  REQUIRE(statistics.unknownReasons ==
+          map<UnknownReason, size_t>{{UnknownReason::IndirectJump, 1}});
+  REQUIRE(statistics.unresolvedJumps == 1);
+  REQUIRE(statistics.report().find("  indirect jump: 1\n") != string::npos);
+
+  // Resolving the call reaches the two targets.
+  analysis.defineJumpTable(0x8000, {0, 2});
+  analysis.run();
+  statistics = analysis.statistics();
+  REQUIRE(statistics.codeBytes == 8);
+  REQUIRE(statistics.subroutines == 3);
+  REQUIRE(statistics.unknownSubroutines == 0);
+  REQUIRE(statistics.unknownReasons.empty());
+  REQUIRE(statistics.unresolvedJumps == 0);
+}
+
+TEST_CASE("Control flow can be followed to its targets", "[analysis]") {
+  Analysis analysis(*assemble("state_change"));
+  analysis.run();
+
+  // A call leads to the called subroutine.
+  REQUIRE(analysis.followTargets(0x8002, 0x8000) ==
+          vector<PCPair>{{0x800E, 0x800E}});
+  // A jump stays inside its subroutine.
+  REQUIRE(analysis.followTargets(0x800B, 0x8000) ==
+          vector<PCPair>{{0x800B, 0x8000}});
+  // Other instructions have nothing to follow.
+  REQUIRE(analysis.followTargets(0x8005, 0x8000).empty());
+}
+
+TEST_CASE("Multiple targets can be followed", "[analysis]") {
+  Analysis analysis(*assemble("jump_tables"));
+  analysis.run();
+  REQUIRE(analysis.followTargets(0x8000, 0x8000).empty());
+
+  analysis.defineJumpTable(0x8000, {0, 2});
+  analysis.run();
+  REQUIRE(analysis.followTargets(0x8000, 0x8000) ==
+          vector<PCPair>{{0x8100, 0x8100}, {0x8200, 0x8200}});
+}
+
+TEST_CASE("Called subroutines record their stack traces", "[analysis]") {
+  Analysis analysis(*assemble("state_change"));
+  analysis.run();
+
+  REQUIRE(analysis.subroutines.at(0x8000).stackTraces.empty());
+  REQUIRE(analysis.subroutines.at(0x800E).stackTraces ==
+          set<StackTrace>{{0x8002}});
+
+  // Nested calls.
+  Analysis nestedAnalysis(*assemble("preservation"));
+  nestedAnalysis.run();
+  REQUIRE(nestedAnalysis.subroutines.at(0x8015).stackTraces ==
+          set<StackTrace>{{0x8005, 0x801D}});
+}
+
+TEST_CASE("Stack traces follow every call chain", "[analysis]") {
+  Analysis analysis(*assemble("call_chain"));
+  analysis.run();
+
+  // Entry points are not called by anything.
+  REQUIRE(analysis.subroutines.at(0x8000).stackTraces.empty());
+  REQUIRE(analysis.subroutines.at(0x8009).stackTraces ==
+          set<StackTrace>{{0x8000}, {0x8003}});
+  // The second call to `middle` doesn't emulate it again,
+  // but `innermost` still records both the paths.
+  REQUIRE(analysis.subroutines.at(0x800D).stackTraces ==
+          set<StackTrace>{{0x8000, 0x8009}, {0x8003, 0x8009}});
+}
+
+TEST_CASE("Analyses without unresolved control flow are complete",
+          "[analysis]") {
+  Analysis analysis(*assemble("jump_tables"));
+  analysis.run();
+
+  // The jump table has no known bounds.
+  REQUIRE(!analysis.isComplete());
+  REQUIRE(analysis.unresolvedCount() == 1);
+
+  analysis.defineJumpTable(0x8000, {0, 2});
+  analysis.run();
+  REQUIRE(analysis.isComplete());
+  REQUIRE(analysis.unresolvedCount() == 0);
+}
+
+TEST_CASE("Entry points can be loaded from a file", "[analysis]") {
+  Analysis analysis(*assemble("entry_points"));
+  analysis.run();
+
+  auto path = filesystem::temp_directory_path() / "gilgamesh_entry_points.txt";
+  ofstream(path) << "# Interrupt handlers.\n"
+                    "$8003 handler_a m=0,x=1\n"
+                    "\n"
+                    "8007 handler_b m=1,x=1  # Second handler.\n"
+                    "$8000 reset_again\n"
+                    "zzzz bad\n"
+                    "$800A bad m=2\n";
+  auto issues = analysis.loadEntryPoints(path.string());
+  filesystem::remove(path);
+
+  // Malformed lines are reported, and don't stop the others.
+  REQUIRE(issues.size() == 3);
+  REQUIRE(issues[0].line == 5);
+  REQUIRE(issues[0].message == "$008000 already analyzed");
+  REQUIRE(issues[1].line == 6);
+  REQUIRE(issues[1].message == "invalid address");
+  REQUIRE(issues[2].line == 7);
+  REQUIRE(issues[2].message == "invalid state");
+
+  analysis.run();
+  REQUIRE(analysis.subroutines.at(0x8003).label == "handler_a");
+  REQUIRE(analysis.subroutines.at(0x8003).instructions.size() == 2);
+  REQUIRE(analysis.subroutines.at(0x8007).label == "handler_b");
+  REQUIRE(analysis.subroutines.at(0x8007).instructions.size() == 2);
+
+  // Missing files are reported too.
+  REQUIRE(analysis.loadEntryPoints(path.string()).size() == 1);
+}
+
+TEST_CASE("Entry points must be in the ROM", "[analysis]") {
+  Analysis lorom(*assemble("entry_points"));
+  REQUIRE_FALSE(lorom.entryPointError(0x8003).has_value());
+  REQUIRE_FALSE(lorom.entryPointError(0x808003).has_value());
+  REQUIRE(lorom.entryPointError(0x7E2000) == "$7E2000 is in RAM");
+  REQUIRE(lorom.entryPointError(0x001000) == "$001000 is in RAM");
+  REQUIRE(lorom.entryPointError(0x002100) ==
+          "$002100 is not mapped to the ROM");
+  REQUIRE(lorom.entryPointError(0xC08000) ==
+          "$C08000 is not mapped to the ROM");
+
+  // Both the system banks and the upper banks map a HiROM.
+  Analysis hirom(*assemble("hirom"));
+  REQUIRE_FALSE(hirom.entryPointError(0x008000).has_value());
+  REQUIRE_FALSE(hirom.entryPointError(0xC08000).has_value());
+}
+
+TEST_CASE("Entry points can assert DBR and D", "[analysis]") {
+  auto entryState = Analysis::parseEntryState("m=1,x=1,db=$7F,dp=0100");
+  REQUIRE(entryState.has_value());
+  REQUIRE(entryState->state.p == State(true, true).p);
+  REQUIRE(entryState->dataBank == 0x7F);
+  REQUIRE(entryState->directPage == 0x0100);
+  REQUIRE_FALSE(Analysis::parseEntryState("db=7E0").has_value());
+  REQUIRE_FALSE(Analysis::parseEntryState("dp=zz").has_value());
+  REQUIRE_FALSE(Analysis::parseEntryState("m=2").has_value());
+
+  Analysis analysis(*assemble("data_bank"));
+  analysis.settings.emulationReset = false;
+  analysis.addEntryPoint("unknown_bank", 0x800B, State(true, false));
+  analysis.addEntryPoint("known_bank", 0x8011, State(true, false), 0x7F);
+  analysis.run();
+
+  // Without an assertion, DBR is unknown on entry.
+  REQUIRE(analysis.subroutines.at(0x800B).dataBanks.empty());
+  // The asserted DBR resolves absolute accesses.
+  REQUIRE(analysis.subroutines.at(0x8011).dataBanks.at(0x8011) == 0x7F);
+}
+
+TEST_CASE("Code decoded in different ways is detected", "[analysis]") {
+  Analysis analysis(*assemble("overlapping_code"));
+  analysis.settings.emulationReset = false;
+  analysis.run();
+  REQUIRE(analysis.overlappingInstructions().empty());
+
+  // Entering the handler with an 8-bits accumulator splits the LDA in two.
+  analysis.addEntryPoint("handler_8bit", 0x8008, State(true, true));
+  analysis.run();
+  REQUIRE(analysis.overlappingInstructions() ==
+          vector<pair<InstructionPC, InstructionPC>>{{0x8008, 0x8008},
+                                                     {0x8008, 0x800A}});
+}
+
+TEST_CASE("Absolute accesses are resolved through DBR", "[analysis]") {
+  Analysis analysis(*assemble("data_bank"));
+  analysis.run();
+
+  auto& dataBanks = analysis.subroutines.at(0x8000).dataBanks;
+  auto target = [&](InstructionPC pc) {
+    auto search = dataBanks.find(pc);
+    auto dataBank = search != dataBanks.end() ? optional(search->second)
+                                              : nullopt;
+    return analysis.anyInstruction(pc)->dataTarget(dataBank);
+  };
+
+  REQUIRE(target(0x8006) == 0x7E2000);
+  REQUIRE(analysis.subroutines.at(0x8000).instructions.at(0x8006)
+              ->dataAddress() == 0x7E2000);
+  REQUIRE(target(0x800B) == 0x008000);
+  // The callee leaves an unknown value in DBR.
+  REQUIRE(target(0x8011) == nullopt);
+  REQUIRE(analysis.subroutines.at(0x8017).setsDataBank);
+}
+
+TEST_CASE("Block moves leave the destination bank in DBR", "[analysis]") {
+  Analysis analysis(*assemble("block_move"));
+  analysis.settings.emulationReset = false;
+  analysis.run();
+
+  // The store after the MVN writes to the destination bank.
+  auto sta = analysis.subroutines.at(0x8000).instructions.at(0x800E);
+  REQUIRE(sta->dataAddress() == 0x7E2000);
+  REQUIRE(analysis.dataReferences().count(0x7E2000));
+
+  // A is counted down to $FFFF, X and Y are advanced by an unknown amount.
+  auto snapshot = analysis.cpuSnapshot(0x800E, 0x8000);
+  REQUIRE(snapshot->a == 0xFFFF);
+  REQUIRE_FALSE(snapshot->x.has_value());
+  REQUIRE_FALSE(snapshot->y.has_value());
+  REQUIRE(snapshot->dataBank == 0x7E);
+
+  REQUIRE(Disassembly::instructionComment(analysis.anyInstruction(0x800B)) ==
+          "Moves from bank $00 to bank $7E");
+}
+
+TEST_CASE("Data blocks are not decoded as code", "[analysis]") {
+  Analysis analysis(*assemble("data_blocks"));
+  analysis.run();
+  REQUIRE(analysis.anyInstruction(0x8004) != nullptr);
+
+  REQUIRE(analysis.addDataBlock(0x8004, 0x8008, DataType::Text));
+  REQUIRE(analysis.addDataBlock(0x8008, 0x800C, DataType::Pointers));
+  REQUIRE(analysis.addDataBlock(0x800C, 0x800F, DataType::Bytes));
+  analysis.run();
+  REQUIRE(analysis.anyInstruction(0x8004) == nullptr);
+  REQUIRE(analysis.subroutines.at(0x8000).unknownStateChanges.count(0x8004));
+
+  SECTION("Invalid data blocks are rejected") {
+    REQUIRE_FALSE(analysis.addDataBlock(0x8006, 0x800A, DataType::Bytes));
+    REQUIRE_FALSE(analysis.addDataBlock(0x8000, 0x8005, DataType::Bytes));
+    REQUIRE_FALSE(analysis.addDataBlock(0x8003, 0x8003, DataType::Bytes));
+    REQUIRE_FALSE(analysis.addDataBlock(0x7E0000, 0x7E0010, DataType::Bytes));
+    REQUIRE(analysis.dataBlocks.size() == 3);
+  }
+
+  SECTION("Data blocks can be removed") {
+    REQUIRE(analysis.removeDataBlock(0x8004));
+    REQUIRE_FALSE(analysis.removeDataBlock(0x8004));
+    REQUIRE(analysis.dataBlockAt(0x8005) == nullptr);
+    REQUIRE(analysis.dataBlockAt(0x8009)->start == 0x8008);
+  }
+
+  SECTION("Data blocks survive saving and loading") {
+    analysis.save();
+    Analysis loaded(*assemble("data_blocks"));
+    REQUIRE(loaded.load());
+    REQUIRE(loaded.dataBlocks.size() == 3);
+    REQUIRE(loaded.dataBlockAt(0x800E)->type == DataType::Bytes);
+    remove(loaded.rom.savePath().c_str());
+  }
+}
+
+TEST_CASE("Unexplored regions of the ROM are listed", "[analysis]") {
+  Analysis analysis(*assemble("region_clears"));
+  analysis.run();
+
+  // Everything after the code, up to the header.
+  auto ranges = analysis.unexploredRanges();
+  REQUIRE(ranges.size() == 1);
+  REQUIRE(ranges[0].start == 0x801A);
+  REQUIRE(ranges[0].end == 0xFFAF);
+  REQUIRE(ranges[0].size == 0x7F96);
+
+  // Declared data counts as explored.
+  analysis.addDataBlock(0x9000, 0x9100, DataType::Bytes);
+  ranges = analysis.unexploredRanges();
+  REQUIRE(ranges.size() == 2);
+  REQUIRE(ranges[0].end == 0x8FFF);
+  REQUIRE(ranges[1].start == 0x9100);
+}
+
+TEST_CASE("References to an address can be listed", "[analysis]") {
+  Analysis analysis(*assemble("call_chain"));
+  analysis.run();
+
+  // `middle` is called twice by `reset`.
+  REQUIRE(analysis.referencesTo(0x8009) ==
+          vector<PCPair>{{0x8000, 0x8000}, {0x8003, 0x8000}});
+  // The loop jumps to itself.
+  REQUIRE(analysis.referencesTo(0x8006) == vector<PCPair>{{0x8006, 0x8000}});
+  REQUIRE(analysis.referencesTo(0x800C).empty());
+
+  // Labels resolve to the address they name.
+  REQUIRE(analysis.labelPC("reset") == PCPair{0x8000, 0x8000});
+  REQUIRE(analysis.labelPC("sub_008009") == PCPair{0x8009, 0x8009});
+  REQUIRE(analysis.labelPC("reset.loc_008006") == PCPair{0x8006, 0x8000});
+  REQUIRE(!analysis.labelPC("nowhere").has_value());
+
+  // Addresses can be given as labels or in hexadecimal.
+  REQUIRE(analysis.resolveAddress("reset.loc_008006") == 0x8006);
+  REQUIRE(analysis.resolveAddress("$8009") == 0x8009);
+  REQUIRE(analysis.resolveAddress("01FFFF") == 0x01FFFF);
+  REQUIRE(!analysis.resolveAddress("nowhere").has_value());
+  REQUIRE(!analysis.resolveAddress("$").has_value());
+  REQUIRE(!analysis.resolveAddress("1234567").has_value());
+
+  // Running the analysis again doesn't duplicate references.
+  analysis.run();
+  REQUIRE(analysis.referencesTo(0x8009).size() == 2);
+
+  // Jump table entries are references too.
+  Analysis jumpTables(*assemble("jump_tables"));
+  jumpTables.run();
+  jumpTables.defineJumpTable(0x8000, {0, 2});
+  jumpTables.run();
+  REQUIRE(jumpTables.referencesTo(0x8200) == vector<PCPair>{{0x8000, 0x8000}});
+}
+
+TEST_CASE("Instructions can be searched", "[analysis]") {
+  Analysis analysis(*assemble("state_change"));
+  analysis.settings.emulationReset = false;
+  analysis.run();
+
+  REQUIRE(analysis.findInstructions(Op::JSR, 0x800E) ==
+          vector<PCPair>{{0x8002, 0x8000}});
+  REQUIRE(analysis.findInstructions(Op::JSR, 0x9000).empty());
+  REQUIRE(analysis.findInstructions(Op::REP, nullopt) ==
+          vector<PCPair>{{0x800E, 0x800E}});
+
+  // Addresses are located in the subroutine starting there, or in the one
+  // with the lowest address containing them.
+  REQUIRE(analysis.locate(0x800E) == PCPair{0x800E, 0x800E});
+  REQUIRE(analysis.locate(0x8009) == PCPair{0x8008, 0x8000});
+  REQUIRE_FALSE(analysis.locate(0x9000).has_value());
+
+  // Operand bytes belong to their instruction, unexplored bytes to none.
+  REQUIRE(analysis.instructionsAt(0x8007) == vector<PCPair>{{0x8005, 0x8000}});
+  REQUIRE(analysis.instructionsAt(0x9000).empty());
+}
+
+TEST_CASE("Shortest call paths are found", "[analysis]") {
+  Analysis analysis(*assemble("call_graph"));
+  analysis.run();
+
+  auto path = analysis.shortestPath(0x8000, 0x8013);
+  REQUIRE(path.subroutines == vector<SubroutinePC>{0x8000, 0x800B, 0x800F,
+                                                   0x8013});
+  // The indirect call in `reset` could hide a shorter path.
+  REQUIRE(path.gaps == vector<InstructionPC>{0x8003});
+
+  // Calls only go one way.
+  auto reversePath = analysis.shortestPath(0x8013, 0x8000);
+  REQUIRE(reversePath.subroutines.empty());
+  REQUIRE(reversePath.gaps.empty());
+
+  // A subroutine trivially reaches itself.
+  REQUIRE(analysis.shortestPath(0x800B, 0x800B).subroutines ==
+          vector<SubroutinePC>{0x800B});
+}
+
 TEST_CASE("PHP and PLP correctly preserve state", "[analysis]") {
   Analysis analysis(*assemble("php_plp"));
+  analysis.settings.emulationReset = false;
   analysis.run();
 
-  // Test there are two subroutines (+ NMI).
-  REQUIRE(analysis.subroutines.size() - 1 == 2);
+  // Test there are two subroutines.
+  REQUIRE(analysis.subroutines.size() == 2);
 
   // Test there's a `reset` subroutine with the correct number of instructions.
   auto& resetSubroutine = analysis.subroutines.at(0x8000);
@@ -181,8 +939,8 @@ TEST_CASE("Overlapping StateChanges are simplified when propagating",
   Analysis analysis(*assemble("simplified_state_changes"));
   analysis.run();
 
-  // Test there are two subroutines (+ NMI).
-  REQUIRE(analysis.subroutines.size() - 1 == 2);
+  // Test there are two subroutines.
+  REQUIRE(analysis.subroutines.size() == 2);
 
   // Test there's a `reset` subroutine with the correct number of instructions.
   auto& resetSubroutine = analysis.subroutines.at(0x8000);
@@ -202,10 +960,11 @@ TEST_CASE("Overlapping StateChanges are simplified when propagating",
 TEST_CASE("StateChange is propagated correctly between subroutines",
           "[analysis]") {
   Analysis analysis(*assemble("state_change"));
+  analysis.settings.emulationReset = false;
   analysis.run();
 
-  // Test there are two subroutines (+ NMI).
-  REQUIRE(analysis.subroutines.size() - 1 == 2);
+  // Test there are two subroutines.
+  REQUIRE(analysis.subroutines.size() == 2);
 
   // Check the subroutines have the right name and number of instructions.
   auto& resetSubroutine = analysis.subroutines.at(0x8000);
@@ -230,11 +989,21 @@ TEST_CASE("StateChange is propagated correctly between subroutines",
   REQUIRE(ldx->argument() == 0x1234);
 }
 
+TEST_CASE("Unused vectors are not entry points", "[analysis]") {
+  // The NMI vector of the test ROMs is $0000.
+  Analysis analysis(*assemble("state_change"));
+  analysis.run();
+
+  REQUIRE(analysis.entryPoints.size() == 1);
+  REQUIRE(analysis.subroutines.count(0x0000) == 0);
+  REQUIRE(!analysis.getLabel(0x0000).has_value());
+}
+
 TEST_CASE("Entry points can be added and analyzed", "[analysis]") {
   Analysis analysis(*assemble("unknown_call_jump"));
   analysis.run();
 
-  // Test there are two subroutines (including NMI).
+  // Test there are two subroutines (reset and NMI).
   REQUIRE(analysis.subroutines.size() == 2);
 
   // Check the subroutines have the right name and number of instructions.
@@ -256,3 +1025,328 @@ TEST_CASE("Entry points can be added and analyzed", "[analysis]") {
   REQUIRE(loopSubroutine.label == "loop");
   REQUIRE(loopSubroutine.instructions.size() == 1);
 }
+
+TEST_CASE("Critical sections are detected correctly", "[analysis]") {
+  Analysis analysis(*assemble("critical_sections"));
+  analysis.run();
+
+  // SEI matched by a CLI.
+  auto& matched = analysis.subroutines.at(0x800F);
+  REQUIRE(matched.criticalSections.size() == 1);
+  REQUIRE(matched.criticalSections.count({0x800F, 0x8010}));
+  REQUIRE(!matched.hasSuspiciousCriticalSections());
+
+  // SEI not matched by a CLI on one of the paths.
+  auto& unclosed = analysis.subroutines.at(0x8012);
+  REQUIRE(unclosed.criticalSections.count({0x8012, 0x8015}));
+  REQUIRE(unclosed.unclosedCriticalSections.size() == 1);
+  REQUIRE(unclosed.unclosedCriticalSections.count({0x8012, 0x8016}));
+  REQUIRE(unclosed.hasSuspiciousCriticalSections());
+  // The interrupt disable flag doesn't split the code it reaches.
+  REQUIRE(analysis.instructions.at(0x8016).size() == 1);
+  REQUIRE(analysis.instructions.at(0x801C).size() == 1);
+
+  // SEI nested inside a PHP/PLP pair in an outer critical section.
+  auto& nested = analysis.subroutines.at(0x8017);
+  REQUIRE(nested.criticalSections.size() == 1);
+  REQUIRE(nested.criticalSections.count({0x8017, 0x801B}));
+  REQUIRE(nested.nestedCriticalSections.count(0x8019));
+  REQUIRE(!nested.hasSuspiciousCriticalSections());
+
+  // CLI when interrupts are known to be enabled.
+  auto& redundant = analysis.subroutines.at(0x801D);
+  REQUIRE(redundant.redundantInterruptEnables.size() == 1);
+  REQUIRE(redundant.redundantInterruptEnables.count(0x801E));
+  REQUIRE(redundant.hasSuspiciousCriticalSections());
+}
+
+TEST_CASE("Direct page accesses before D is set are detected", "[analysis]") {
+  Analysis analysis(*assemble("direct_page"));
+  analysis.settings.emulationReset = false;
+  analysis.run();
+
+  // Accesses in the reset handler before the TCD.
+  auto& reset = analysis.subroutines.at(0x8000);
+  REQUIRE(reset.directPageBeforeInit == set<InstructionPC>{0x8002, 0x8004});
+  REQUIRE(reset.setsDirectPage);
+
+  // Subroutines called before and after the TCD.
+  auto& helper = analysis.subroutines.at(0x8017);
+  REQUIRE(helper.directPageBeforeInit == set<InstructionPC>{0x8017});
+  auto& otherHelper = analysis.subroutines.at(0x801A);
+  REQUIRE(otherHelper.directPageBeforeInit.empty());
+
+  // Flagged instructions are marked in the disassembly.
+  auto lda = analysis.anyInstruction(0x8002);
+  REQUIRE(Disassembly::instructionComment(lda) == "D not set yet");
+}
+
+TEST_CASE("Leaf subroutines are recognized", "[analysis]") {
+  Analysis analysis(*assemble("leaves"));
+  analysis.run();
+
+  // Only loads and stores.
+  REQUIRE(analysis.subroutines.at(0x8009).isLeaf());
+  // Indirect calls make a subroutine non-leaf, even if the target is unknown.
+  REQUIRE(!analysis.subroutines.at(0x800E).isLeaf());
+  REQUIRE(!analysis.subroutines.at(0x8000).isLeaf());
+}
+
+TEST_CASE("Subroutines are tagged by the subsystems they touch",
+          "[analysis]") {
+  Analysis analysis(*assemble("subsystems"));
+  analysis.run();
+
+  // $2100 is a PPU register, $420B starts DMA. $7E2100 is plain RAM.
+  REQUIRE(analysis.subroutines.at(0x800B).touchedSubsystems() ==
+          set<Subsystem>{Subsystem::PPU, Subsystem::DMA});
+  // Long accesses to registers mirrored in other banks count too.
+  REQUIRE(analysis.subroutines.at(0x801A).touchedSubsystems() ==
+          set<Subsystem>{Subsystem::DMA, Subsystem::Controller});
+  // Subsystems touched by callees are not included.
+  REQUIRE(analysis.subroutines.at(0x8000).touchedSubsystems().empty());
+}
+
+TEST_CASE("Running into another subroutine is a fall-through",
+          "[analysis]") {
+  Analysis analysis(*assemble("fall_through"));
+  analysis.settings.emulationReset = false;
+  analysis.run();
+
+  // `clear` stops where `clear_y` begins, instead of duplicating it.
+  auto& clear = analysis.subroutines.at(0x8009);
+  auto& clearY = analysis.subroutines.at(0x800E);
+  REQUIRE(clear.instructions.size() == 2);
+  REQUIRE(clearY.instructions.size() == 3);
+  for (auto& instruction : analysis.instructions.at(0x800E)) {
+    REQUIRE(instruction.subroutinePC == 0x800E);
+  }
+
+  REQUIRE(clear.fallThroughs == map<InstructionPC, SubroutinePC>{
+                                    {0x800B, 0x800E}});
+  REQUIRE(analysis.followTargets(0x800B, 0x8009) ==
+          vector<PCPair>{{0x800E, 0x800E}});
+
+  // The state change of `clear_y` is propagated to `clear`, on the edge
+  // where it falls through rather than as a return.
+  REQUIRE(clear.knownStateChanges.empty());
+  REQUIRE(clear.fallThroughStateChanges.size() == 1);
+  auto stateChange = clear.fallThroughStateChanges.at(0x800B);
+  REQUIRE(stateChange.m == true);
+  REQUIRE(stateChange.x == false);
+  REQUIRE_FALSE(analysis.anyInstruction(0x800B)->stateChange().has_value());
+
+  // And on to the callers of `clear`.
+  auto jsr = analysis.subroutines.at(0x8000).instructions.at(0x8003);
+  REQUIRE(jsr->state.m == true);
+  REQUIRE(jsr->state.x == false);
+
+  // `clear_y` is only known to be a subroutine after the first pass, which
+  // analyzed it as part of `clear`: a second pass is enough.
+  REQUIRE(analysis.passes == 2);
+
+  auto ldx = analysis.anyInstruction(0x800B);
+  REQUIRE(Disassembly::instructionComment(ldx) ==
+          "Falls through to sub_00800E");
+}
+
+TEST_CASE("Accesses to RAM and registers are grouped by address",
+          "[analysis]") {
+  Analysis analysis(*assemble("ram_references"));
+  analysis.run();
+
+  // Direct page, absolute and long accesses to the same variable are
+  // grouped together, jumps into RAM are included.
+  REQUIRE(analysis.ramReferences() ==
+          map<u24, vector<PCPair>>{
+              {0x2100, {{0x8004, 0x8000}}},
+              {0x7E0010,
+               {{0x8007, 0x8000}, {0x8010, 0x8010}, {0x8013, 0x8010}}},
+              {0x7E1000, {{0x800C, 0x8000}}},
+              {0x7F2000, {{0x8017, 0x8010}}},
+          });
+}
+
+TEST_CASE("Data accesses are grouped by address", "[analysis]") {
+  Analysis analysis(*assemble("data_references"));
+  analysis.run();
+
+  // Reads from the ROM are included, jumps into RAM are not.
+  REQUIRE(analysis.dataReferences() ==
+          map<u24, vector<PCPair>>{
+              {0x2100, {{0x800A, 0x8000}}},
+              {0x009000, {{0x8002, 0x8000}, {0x8007, 0x8000}}},
+              {0x00A000, {{0x800D, 0x8000}}},
+              {0x7E0010,
+               {{0x8005, 0x8000}, {0x8011, 0x8000}, {0x801D, 0x801B}}},
+          });
+}
+
+TEST_CASE("Reset starts in emulation mode", "[analysis]") {
+  Analysis analysis(*assemble("emulation"));
+  analysis.run();
+
+  auto& reset = analysis.subroutines.at(0x8000);
+  auto size = [&](InstructionPC pc) {
+    return reset.instructions.at(pc)->size();
+  };
+
+  // 8-bits until CLC/XCE switches to native mode, and REP takes effect.
+  REQUIRE(size(0x8001) == 2);
+  REQUIRE(size(0x8005) == 2);
+  REQUIRE(size(0x8009) == 3);
+  // SEC/XCE goes back to emulation mode, where REP can't change the sizes.
+  REQUIRE(size(0x800E) == 2);
+  REQUIRE(size(0x8012) == 2);
+  REQUIRE(reset.instructions.count(0x8014));
+
+  // The emulation flag is part of the state of each instruction.
+  REQUIRE(reset.instructions.at(0x8001)->state.e);
+  REQUIRE(!reset.instructions.at(0x8009)->state.e);
+  REQUIRE(reset.instructions.at(0x800E)->state.e);
+}
+
+TEST_CASE("XCE with an unknown carry stops the analysis", "[analysis]") {
+  Analysis analysis(*assemble("emulation_unknown"));
+  analysis.run();
+
+  // The carry is lost after calling `helper`, so XCE is to blame.
+  auto& switchSubroutine = analysis.subroutines.at(0x8006);
+  REQUIRE(switchSubroutine.unknownStateChanges.at(0x8009).unknownReason ==
+          UnknownReason::EmulationMode);
+  REQUIRE(switchSubroutine.isResponsibleForUnknown());
+  REQUIRE_FALSE(analysis.subroutines.at(0x8000).isResponsibleForUnknown());
+}
+
+TEST_CASE("Branches can be followed on the taken side only", "[analysis]") {
+  Analysis analysis(*assemble("taken_branches"));
+  analysis.run();
+  REQUIRE(analysis.subroutines.size() == 2);
+  REQUIRE(analysis.subroutines.at(0x8000).instructions.size() == 3);
+
+  // The call is only reached when the branch is not taken.
+  analysis.settings.takenBranchesOnly = true;
+  analysis.run();
+  REQUIRE(analysis.subroutines.size() == 1);
+  REQUIRE(analysis.subroutines.at(0x8000).instructions.size() == 2);
+  REQUIRE_FALSE(analysis.subroutines.at(0x8000).instructions.count(0x8002));
+}
+
+TEST_CASE("Calls between subroutines are listed", "[analysis]") {
+  Analysis analysis(*assemble("call_graph"));
+  analysis.run();
+  REQUIRE(analysis.callEdges() ==
+          vector<pair<SubroutinePC, SubroutinePC>>{
+              {0x8000, 0x800B}, {0x800B, 0x800F}, {0x800F, 0x8013}});
+
+  // Recursive calls are edges too.
+  Analysis recursion(*assemble("recursion"));
+  recursion.run();
+  REQUIRE(recursion.callEdges() ==
+          vector<pair<SubroutinePC, SubroutinePC>>{{0x8000, 0x8006},
+                                                   {0x8006, 0x8006}});
+}
+
+TEST_CASE("Call sites include tail jumps and jump tables", "[analysis]") {
+  Analysis analysis(*assemble("call_sites"));
+  analysis.run();
+  analysis.defineJumpTable(0x8003, {0, 0});
+  analysis.run();
+
+  // Jumps inside a subroutine are not call sites.
+  REQUIRE(analysis.callSites() == vector<CallSite>{
+                                      {0x8000, 0x800B, 0x8000, false},
+                                      {0x8000, 0x800E, 0x8003, true},
+                                      {0x800B, 0x800E, 0x800B, false},
+                                  });
+}
+
+TEST_CASE("Labels are renamed without assuming the analysis", "[analysis]") {
+  Analysis analysis(*assemble("migration_v1"));
+  analysis.run();
+
+  REQUIRE(analysis.labelPC("sub_008011") == PCPair{0x8011, 0x8011});
+  REQUIRE(analysis.labelPC("sub_008011.loc_008013") ==
+          PCPair{0x8013, 0x8011});
+  REQUIRE_FALSE(analysis.labelPC("sub_008011.loc_009999").has_value());
+  REQUIRE_FALSE(analysis.labelPC("print").has_value());
+
+  // A label looked up before the results are cleared refers to nothing.
+  auto pc = analysis.labelPC("sub_008011.loc_008013");
+  analysis.subroutines.erase(0x8011);
+  REQUIRE_FALSE(analysis.renameLabel("next", pc->first, pc->second));
+  REQUIRE_FALSE(analysis.renameLabel("init", 0x9000));
+  REQUIRE(analysis.customLabels.empty());
+
+  analysis.run();
+  REQUIRE_FALSE(analysis.renameLabel("1st", 0x800B));
+  REQUIRE(analysis.renameLabel("init", 0x800B));
+  analysis.run();
+  REQUIRE(analysis.labelPC("init") == PCPair{0x800B, 0x800B});
+}
+
+TEST_CASE("The NMI handler can run with every size of A and X",
+          "[analysis]") {
+  Analysis analysis(*assemble("nmi_states"));
+  analysis.run();
+  REQUIRE(analysis.instructions.at(0x8003).size() == 1);
+
+  analysis.settings.nmiAllStates = true;
+  analysis.run();
+
+  // Every state is explored until the handler sets the sizes itself.
+  REQUIRE(analysis.instructions.at(0x8003).size() == 4);
+  REQUIRE(analysis.instructions.at(0x8004).size() == 4);
+  REQUIRE(analysis.instructions.at(0x8006).size() == 1);
+
+  auto& nmi = analysis.subroutines.at(0x8003);
+  REQUIRE(nmi.instructions.at(0x8006)->size() == 3);
+  REQUIRE(nmi.instructions.at(0x8009)->size() == 3);
+  REQUIRE(nmi.instructions.at(0x8003)->state == State());
+  REQUIRE(nmi.unknownStateChanges.empty());
+}
+
+TEST_CASE("Jump tables following their jump are guessed", "[analysis]") {
+  Analysis analysis(*assemble("guess_jump_tables"));
+  analysis.run();
+  REQUIRE(analysis.jumpTables.at(0x8006).status == JumpTableStatus::Unknown);
+
+  // The table ends where the words stop pointing to ROM.
+  REQUIRE(analysis.guessJumpTables() == vector<InstructionPC>{0x8006});
+  auto& jumpTable = analysis.jumpTables.at(0x8006);
+  REQUIRE(jumpTable.status == JumpTableStatus::Partial);
+  REQUIRE(jumpTable.guessed);
+  REQUIRE(jumpTable.targets == map<optional<u16>, InstructionPC>{
+                                   {0, 0x800D}, {2, 0x8012}});
+
+  analysis.run();
+  auto& reset = analysis.subroutines.at(0x8000);
+  REQUIRE(reset.instructions.count(0x800D));
+  REQUIRE(reset.instructions.count(0x8012));
+  REQUIRE(analysis.guessJumpTables().empty());
+
+  // Guesses survive saving, and are reviewed by defining the table.
+  Analysis restored(*assemble("guess_jump_tables"));
+  restored.restoreState(analysis.saveState());
+  REQUIRE(restored.jumpTables.at(0x8006).guessed);
+  analysis.defineJumpTable(0x8006, {0, 2}, JumpTableStatus::Complete);
+  REQUIRE_FALSE(analysis.jumpTables.at(0x8006).guessed);
+}
+
+TEST_CASE("Guessed jump tables stop at data", "[analysis]") {
+  Analysis analysis(*assemble("guess_jump_tables_data"));
+  analysis.run();
+
+  // The words following the table point to code, but they're declared data.
+  analysis.addDataBlock(0x800D, 0x8011, DataType::Words);
+  REQUIRE(analysis.guessJumpTables() == vector<InstructionPC>{0x8006});
+  REQUIRE(analysis.jumpTables.at(0x8006).targets ==
+          map<optional<u16>, InstructionPC>{{0, 0x8011}, {2, 0x8016}});
+
+  // A label also ends the table.
+  analysis.undefineJumpTable(0x8006);
+  analysis.removeDataBlock(0x800D);
+  analysis.customLabels[{0x800D, 0x8000}] = "values";
+  REQUIRE(analysis.guessJumpTables() == vector<InstructionPC>{0x8006});
+  REQUIRE(analysis.jumpTables.at(0x8006).targets.size() == 2);
+}
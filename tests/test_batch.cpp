@@ -0,0 +1,427 @@
+#include <catch2/catch.hpp>
+#include <filesystem>
+#include <fstream>
+#include <sstream>
+
+#include "asar.hpp"
+
+#include "analysis.hpp"
+#include "batch.hpp"
+#include "disassembly.hpp"
+#include "rom.hpp"
+#include "utils.hpp"
+
+using namespace std;
+
+TEST_CASE("Scripts are run without interaction", "[batch]") {
+  Analysis analysis(*assemble("entry_points"));
+  ostringstream output, errors;
+
+  istringstream script(
+      "# Interrupt handlers.\n"
+      "entrypoint $8003 handler_a m=0,x=1\n"
+      "label-ram frame_counter 7E0010\n"
+      "\n"
+      "analyze\n"
+      "export disassembly  # To the output.\n");
+  REQUIRE(Batch(&analysis, output, errors).run(script));
+  REQUIRE(errors.str().empty());
+
+  REQUIRE(analysis.subroutines.at(0x8003).label == "handler_a");
+  REQUIRE(analysis.ramLabels.at(0x7E0010) == "frame_counter");
+  REQUIRE(output.str() ==
+          "2 subroutines analyzed\n" + Disassembly(&analysis).all());
+}
+
+TEST_CASE("Scripts stop at the first failing command", "[batch]") {
+  Analysis analysis(*assemble("entry_points"));
+  ostringstream output, errors;
+
+  istringstream script(
+      "entrypoint zzzz bad\n"
+      "analyze\n");
+  REQUIRE_FALSE(Batch(&analysis, output, errors).run(script));
+  REQUIRE(errors.str() == "line 1: invalid address\n");
+  REQUIRE(output.str().empty());
+
+  istringstream ram("entrypoint $7E2000 in_ram\n");
+  REQUIRE_FALSE(Batch(&analysis, output, errors).run(ram));
+  REQUIRE(errors.str().find("line 1: $7E2000 is in RAM") != string::npos);
+
+  istringstream unknown("\nexport pdf\n");
+  REQUIRE_FALSE(Batch(&analysis, output, errors).run(unknown));
+  REQUIRE(errors.str().find("line 2: invalid export format: pdf") !=
+          string::npos);
+
+  istringstream graph("export callgraph calls.svg svg\n");
+  REQUIRE_FALSE(Batch(&analysis, output, errors).run(graph));
+  REQUIRE(errors.str().find("line 1: invalid call graph format: svg") !=
+          string::npos);
+}
+
+TEST_CASE("Single commands return their output", "[batch]") {
+  Analysis analysis(*assemble("entry_points"));
+  ostringstream output, errors;
+  Batch batch(&analysis, output, errors);
+
+  auto result = batch.runCommand("analyze  # Comment.");
+  REQUIRE(result.output == "1 subroutines analyzed\n");
+  REQUIRE_FALSE(result.error.has_value());
+  REQUIRE(batch.runCommand("export disassembly").output ==
+          Disassembly(&analysis).all());
+
+  // Errors are returned, and nothing is written to the streams.
+  result = batch.runCommand("export pdf");
+  REQUIRE(result.error == "invalid export format: pdf");
+  REQUIRE(result.output.empty());
+  REQUIRE(batch.runCommand("frobnicate").error ==
+          "invalid command: frobnicate");
+  REQUIRE_FALSE(batch.runCommand("").error.has_value());
+  REQUIRE(output.str().empty());
+  REQUIRE(errors.str().empty());
+
+  // Edits can be undone.
+  REQUIRE(batch.runCommand("bookmark 8000 Start").output.empty());
+  REQUIRE(batch.runCommand("bookmarks").output ==
+          "$008000  Start  ; jmp reset\n");
+  REQUIRE_FALSE(batch.runCommand("undo").error.has_value());
+  REQUIRE(analysis.bookmarks.empty());
+}
+
+TEST_CASE("Addresses can be bookmarked", "[batch]") {
+  Analysis analysis(*assemble("entry_points"));
+  ostringstream output, errors;
+
+  istringstream script(
+      "analyze\n"
+      "bookmark $8000 Main loop\n"
+      "bookmark 8003\n"
+      "bookmark 7E0010 Frame counter\n"
+      "unbookmark 8003\n"
+      "bookmarks\n");
+  REQUIRE(Batch(&analysis, output, errors).run(script));
+  REQUIRE(errors.str().empty());
+
+  REQUIRE(output.str() ==
+          "1 subroutines analyzed\n"
+          "$008000  Main loop  ; jmp reset\n"
+          "$7E0010  Frame counter\n");
+
+  istringstream missing("unbookmark 8003\n");
+  REQUIRE_FALSE(Batch(&analysis, output, errors).run(missing));
+  REQUIRE(errors.str() == "line 1: no bookmark at $008003\n");
+}
+
+TEST_CASE("Regions can be declared as data", "[batch]") {
+  Analysis analysis(*assemble("data_blocks"));
+  ostringstream output, errors;
+
+  istringstream script(
+      "analyze\n"
+      "datablock 8004 8008 text\n"
+      "datablock $8008 800F\n"
+      "analyze\n"
+      "undatablock 8008\n"
+      "datablocks\n");
+  REQUIRE(Batch(&analysis, output, errors).run(script));
+  REQUIRE(errors.str().empty());
+  REQUIRE(output.str() ==
+          "2 subroutines analyzed\n"
+          "2 subroutines analyzed\n"
+          "$008004..$008008  text\n");
+
+  // The CPU stops at the data.
+  auto& reset = analysis.subroutines.at(0x8000);
+  REQUIRE(reset.instructions.count(0x8003));
+  REQUIRE_FALSE(reset.instructions.count(0x8004));
+  REQUIRE(reset.isUnknownBecauseOf(UnknownReason::SuspectInstruction));
+
+  istringstream overlap("datablock 8006 800A words\n");
+  REQUIRE_FALSE(Batch(&analysis, output, errors).run(overlap));
+  REQUIRE(errors.str() == "line 1: invalid data block $008006..$00800A\n");
+
+  Batch batch(&analysis, output, errors);
+  REQUIRE(batch.runCommand("datablock 8008 800C floats").error ==
+          "invalid data type: floats");
+  REQUIRE(batch.runCommand("undatablock 8008").error ==
+          "no data block at $008008");
+}
+
+TEST_CASE("Edits can be undone and redone", "[batch]") {
+  Analysis analysis(*assemble("entry_points"));
+  ostringstream output, errors;
+
+  istringstream script(
+      "entrypoint $8003 handler_a\n"
+      "bookmark $8000 Start\n"
+      "undo\n"
+      "undo\n"
+      "redo\n");
+  Batch batch(&analysis, output, errors);
+  REQUIRE(batch.run(script));
+
+  // Only the entry point is back, and the analysis is rerun.
+  REQUIRE(analysis.bookmarks.empty());
+  REQUIRE(analysis.subroutines.at(0x8003).label == "handler_a");
+
+  istringstream tooFar("undo\nundo\n");
+  REQUIRE_FALSE(batch.run(tooFar));
+  REQUIRE(errors.str() == "line 2: nothing to undo\n");
+}
+
+TEST_CASE("Subroutine cycles are estimated", "[batch]") {
+  Analysis analysis(*assemble("state_change"));
+  analysis.settings.emulationReset = false;
+  ostringstream output, errors;
+
+  istringstream script("analyze\ncycles $8000\ncycles 800E\ncycles 8002\n");
+  REQUIRE_FALSE(Batch(&analysis, output, errors).run(script));
+  REQUIRE(output.str() ==
+          "2 subroutines analyzed\n"
+          "reset: ~18 cycles\n"
+          "sub_00800E: ~9 cycles\n");
+  REQUIRE(errors.str() == "line 4: no subroutine at $008002\n");
+}
+
+TEST_CASE("The ROM is described", "[batch]") {
+  auto lorom = assemble("lorom");
+  Analysis analysis(*lorom);
+  ostringstream output, errors;
+
+  istringstream script("rom\n");
+  REQUIRE(Batch(&analysis, output, errors).run(script));
+  REQUIRE(output.str() ==
+          "Title: TEST\n"
+          "Type: LoROM\n"
+          "Size: 32 KB\n"
+          "Copier header: no\n");
+
+  auto path = filesystem::temp_directory_path() / "gilgamesh_smc.sfc";
+  ofstream file(path, ios::binary);
+  file << string(ROM::COPIER_HEADER_SIZE, '\0');
+  file.write((const char*)lorom->data.data(), lorom->data.size());
+  file.close();
+
+  Analysis headered(path.string());
+  filesystem::remove(path);
+
+  ostringstream headeredOutput;
+  istringstream again("rom\n");
+  REQUIRE(Batch(&headered, headeredOutput, errors).run(again));
+  REQUIRE(headeredOutput.str() ==
+          "Title: TEST\n"
+          "Type: LoROM\n"
+          "Size: 32 KB\n"
+          "Copier header: yes\n");
+}
+
+TEST_CASE("The checksum of the ROM can be fixed", "[batch]") {
+  Analysis analysis(*assemble("lorom"));
+  ostringstream output, errors;
+  auto expected = analysis.rom.computeChecksum();
+
+  istringstream script("rom checksum\nrom fixchecksum\nrom checksum\n");
+  REQUIRE(Batch(&analysis, output, errors).run(script));
+  REQUIRE(output.str() ==
+          format("Checksum: $0000 (invalid, expected $%04X)\n"
+                 "Checksum: $%04X (fixed)\n"
+                 "Checksum: $%04X (valid)\n",
+                 expected, expected, expected));
+}
+
+TEST_CASE("Analyses can be saved under a name", "[batch]") {
+  Analysis analysis(*assemble("state_change"));
+  ostringstream output, errors;
+
+  istringstream script(
+      "analyze\n"
+      "save\n"
+      "entrypoint 8005 extra\n"
+      "analyze\n"
+      "save experiment\n"
+      "saves\n"
+      "load\n");
+  REQUIRE(Batch(&analysis, output, errors).run(script));
+  REQUIRE(errors.str().empty());
+  REQUIRE(analysis.entryPoints.size() == 1);
+
+  auto text = output.str();
+  auto defaultSave = text.find("\n(default)  ");
+  auto namedSave = text.find("\nexperiment  ");
+  REQUIRE(defaultSave != string::npos);
+  REQUIRE(namedSave > defaultSave);
+  REQUIRE(text.find("  2 subroutines\n", defaultSave) < namedSave);
+  REQUIRE(text.find("  3 subroutines\n", namedSave) != string::npos);
+
+  istringstream named("load experiment\n");
+  REQUIRE(Batch(&analysis, output, errors).run(named));
+  REQUIRE(analysis.entryPoints.size() == 2);
+
+  istringstream invalid("save ../experiment\n");
+  REQUIRE_FALSE(Batch(&analysis, output, errors).run(invalid));
+  REQUIRE(errors.str() == "line 1: invalid save name: ../experiment\n");
+
+  remove(analysis.rom.savePath().c_str());
+  remove(analysis.rom.savePath("experiment").c_str());
+}
+
+TEST_CASE("Data accesses are listed", "[batch]") {
+  Analysis analysis(*assemble("data_references"));
+  ostringstream output, errors;
+
+  istringstream script("label-ram counter 7E0010\nanalyze\ndata\n");
+  REQUIRE(Batch(&analysis, output, errors).run(script));
+  REQUIRE(output.str() ==
+          "2 subroutines analyzed\n"
+          "$002100  1  !INIDISP\n"
+          "$009000  2\n"
+          "$00A000  1\n"
+          "$7E0010  3  counter\n");
+}
+
+TEST_CASE("The emulation can be traced", "[batch]") {
+  Analysis analysis(*assemble("trace"));
+  analysis.settings.emulationReset = false;
+  ostringstream output, errors;
+
+  istringstream script(
+      "analyze\n"
+      "trace 8000 m=0,x=0\n"
+      "trace 800C m=1,x=1 1\n");
+  REQUIRE(Batch(&analysis, output, errors).run(script));
+  REQUIRE(errors.str().empty());
+  REQUIRE(output.str() ==
+          "2 subroutines analyzed\n"
+          "$008000  sep #$30         m=0,x=0 -> m=1,x=1\n"
+          "$008002  bcc .loc_008008  m=1,x=1 -> m=1,x=1  "
+          "not taken, then taken to $008008\n"
+          "$008004  jml $7E0000      m=1,x=1 -> m=1,x=1\n"
+          "$7E0000  stopped: mutable code\n"
+          "$008008  jsr sub_00800C   m=1,x=1 -> m=0,x=1\n"
+          "$00800C  rep #$20         m=1,x=1 -> m=0,x=1\n"
+          "$00800E  rts              m=0,x=1 -> m=0,x=1\n"
+          "$00800E  stopped: return\n"
+          "$00800B  rts              m=0,x=1 -> m=0,x=1\n"
+          "$00800B  stopped: stack manipulation\n"
+          "$00800C  rep #$20         m=1,x=1 -> m=0,x=1\n"
+          "instruction limit (1) reached\n");
+
+  // The analysis is left untouched.
+  REQUIRE(analysis.subroutines.size() == 2);
+  REQUIRE(analysis.subroutines.at(0x8000).label == "reset");
+  REQUIRE(analysis.subroutines.at(0x800C).label == "sub_00800C");
+}
+
+TEST_CASE("A whole session can be scripted", "[batch]") {
+  Analysis analysis(*assemble("data_references"));
+  ostringstream output, errors;
+
+  istringstream script(
+      "rom\n"
+      "label-ram counter 7E0010\n"
+      "bookmark 8014 Call to update\n"
+      "analyze\n"
+      "stats\n"
+      "cycles 801B\n"
+      "data\n"
+      "bookmarks\n"
+      "trace 801B m=1,x=1\n"
+      "undo\n"
+      "redo\n"
+      "export disassembly\n");
+  REQUIRE(Batch(&analysis, output, errors).run(script));
+  REQUIRE(errors.str().empty());
+
+  auto text = output.str();
+  for (auto expected :
+       {"Type: LoROM\n", "2 subroutines analyzed\n", "Subroutines: 2\n",
+        "sub_00801B: ~", "$7E0010  3  counter\n",
+        "$008014  Call to update  ; jsr", "$00801D  sta counter",
+        "stopped: stack manipulation\n"}) {
+    REQUIRE(text.find(expected) != string::npos);
+  }
+  REQUIRE(analysis.bookmarks.count(0x8014));
+  REQUIRE(text.substr(text.size() - Disassembly(&analysis).all().size()) ==
+          Disassembly(&analysis).all());
+}
+
+TEST_CASE("Jump tables indexed by X can be defined", "[batch]") {
+  Analysis analysis(*assemble("jump_tables"));
+  ostringstream output, errors;
+
+  istringstream script("analyze\njumptable 8000 2\nanalyze\n");
+  REQUIRE(Batch(&analysis, output, errors).run(script));
+  REQUIRE(analysis.subroutines.count(0x8100));
+  REQUIRE(analysis.subroutines.count(0x8200));
+  REQUIRE(analysis.jumpTables.at(0x8000).status ==
+          JumpTableStatus::Complete);
+
+  istringstream direct("jumptable 8003 2\n");
+  REQUIRE_FALSE(Batch(&analysis, output, errors).run(direct));
+  REQUIRE(errors.str() ==
+          "line 1: no jump or call indexed by X at $008003\n");
+
+  // The table can't run past the end of the bank, or of the ROM.
+  Batch batch(&analysis, output, errors);
+  REQUIRE(batch.runCommand("jumptable 8000 16382").error ==
+          "jump table crosses the end of the bank");
+  REQUIRE_FALSE(batch.runCommand("jumptable 8000 16381").error.has_value());
+}
+
+TEST_CASE("COP can be emulated as a system call", "[batch]") {
+  Analysis analysis(*assemble("system_call"));
+  analysis.settings.emulationReset = false;
+  ostringstream output, errors;
+
+  istringstream script("analyze\n");
+  REQUIRE(Batch(&analysis, output, errors).run(script));
+  auto& reset = analysis.subroutines.at(0x8000);
+  REQUIRE(reset.isUnknownBecauseOf(UnknownReason::SuspectInstruction));
+
+  istringstream handler("interrupt cop 8100\nanalyze\n");
+  REQUIRE(Batch(&analysis, output, errors).run(handler));
+  REQUIRE(analysis.subroutines.count(0x8100));
+  auto lda = analysis.subroutines.at(0x8000).instructions.at(0x8006);
+  REQUIRE(lda->state.m == true);
+  REQUIRE(lda->state.x == false);
+  REQUIRE(Disassembly::instructionComment(analysis.anyInstruction(0x8002)) ==
+          "Handled by sub_008100");
+
+  istringstream invalid("interrupt nmi 8100\n");
+  REQUIRE_FALSE(Batch(&analysis, output, errors).run(invalid));
+  REQUIRE(errors.str() == "line 1: invalid interrupt: nmi\n");
+}
+
+TEST_CASE("Labels can be renamed", "[batch]") {
+  Analysis analysis(*assemble("migration_v1"));
+  ostringstream output, errors;
+
+  istringstream script(
+      "analyze\n"
+      "rename sub_008011 print\n"
+      "rename sub_008011.loc_008013 next\n"
+      "analyze\n");
+  REQUIRE(Batch(&analysis, output, errors).run(script));
+  REQUIRE(analysis.labelPC("print.next") == PCPair{0x8013, 0x8011});
+
+  istringstream unknown("rename sub_008011 output\n");
+  REQUIRE_FALSE(Batch(&analysis, output, errors).run(unknown));
+  istringstream invalid("rename print 1st\n");
+  REQUIRE_FALSE(Batch(&analysis, output, errors).run(invalid));
+  REQUIRE(errors.str() ==
+          "line 1: unknown label: sub_008011\n"
+          "line 1: invalid label: 1st\n");
+}
+
+TEST_CASE("Jump tables can be guessed", "[batch]") {
+  Analysis analysis(*assemble("guess_jump_tables"));
+  ostringstream output, errors;
+
+  istringstream script("analyze\nguess-jumptables\nanalyze\n");
+  REQUIRE(Batch(&analysis, output, errors).run(script));
+  REQUIRE(output.str() ==
+          "1 subroutines analyzed\n"
+          "$008006  2 entries\n"
+          "1 subroutines analyzed\n");
+  REQUIRE(analysis.subroutines.at(0x8000).instructions.count(0x8012));
+}
@@ -0,0 +1,20 @@
+#include <catch2/catch.hpp>
+#include <sstream>
+
+#include "asar.hpp"
+
+#include "commandline.hpp"
+#include "rom.hpp"
+#include "settings.hpp"
+
+using namespace std;
+
+TEST_CASE("The disassembly of a ROM can be dumped", "[commandline]") {
+  auto rom = assemble("migration_v1");
+  ostringstream output, errors;
+  dumpDisassembly(rom->path, Settings(), output, errors);
+
+  REQUIRE(errors.str().empty());
+  REQUIRE(output.str().find("reset:") != string::npos);
+  REQUIRE(output.str().find("  sep #$30") != string::npos);
+}
@@ -1,4 +1,5 @@
 #include <catch2/catch.hpp>
+#include <list>
 
 #include "analysis.hpp"
 #include "cpu.hpp"
@@ -16,8 +17,10 @@ pair<CPU, Analysis*> setupCPU(u8 p, bool stop = true) {
 }
 
 void runInstruction(CPU& cpu, u8 opcode, u24 argument) {
-  Instruction instruction(cpu.pc, cpu.subroutinePC, opcode, argument, cpu.state,
-                          cpu.analysis);
+  // Stack entries refer to the pushing instruction, so keep it alive.
+  static list<Instruction> instructions;
+  auto& instruction = instructions.emplace_back(
+      cpu.pc, cpu.subroutinePC, opcode, argument, cpu.state, cpu.analysis);
   cpu.execute(&instruction);
 }
 
@@ -84,3 +87,68 @@ TEST_CASE("CPU emulates SEP/REP correctly", "[cpu]") {
 
   delete analysis;
 }
+
+TEST_CASE("CPU emulates TSX correctly", "[cpu]") {
+  auto [cpu, analysis] = setupCPU(0b00000000);
+  runInstruction(cpu, 0xA9, 0x1FF0);  // LDA.
+  runInstruction(cpu, 0x1B, 0x00);    // TCS.
+  runInstruction(cpu, 0xBA, 0x00);    // TSX.
+
+  REQUIRE(cpu.stack.pointer == 0x1FF0);
+  REQUIRE(cpu.X.getWhole() == 0x1FF0);
+
+  delete analysis;
+}
+
+TEST_CASE("CPU emulates TXS correctly", "[cpu]") {
+  auto [cpu, analysis] = setupCPU(0b00000000);
+  runInstruction(cpu, 0xA2, 0x01FF);  // LDX.
+  runInstruction(cpu, 0x9A, 0x00);    // TXS.
+
+  REQUIRE(cpu.stack.pointer == 0x01FF);
+
+  delete analysis;
+}
+
+TEST_CASE("CPU restores A from the stack", "[cpu]") {
+  auto [cpu, analysis] = setupCPU(0b00100000);
+
+  runInstruction(cpu, 0xA9, 0x30);  // LDA.
+  runInstruction(cpu, 0x48, 0x00);  // PHA.
+
+  runInstruction(cpu, 0xA9, 0x00);  // LDA.
+  REQUIRE(cpu.A.get() == 0x00);
+  runInstruction(cpu, 0x68, 0x00);  // PLA.
+  REQUIRE(cpu.A.get() == 0x30);
+  REQUIRE(cpu.stack.pointer == 0x100);
+
+  delete analysis;
+}
+
+TEST_CASE("CPU tracks the data bank register", "[cpu]") {
+  auto [cpu, analysis] = setupCPU(0b00100000);
+  cpu.dataBank = nullopt;
+
+  runInstruction(cpu, 0x4B, 0x00);  // PHK.
+  runInstruction(cpu, 0xAB, 0x00);  // PLB.
+  REQUIRE(cpu.dataBank == 0x00);
+
+  runInstruction(cpu, 0xA9, 0x7E);  // LDA.
+  runInstruction(cpu, 0x48, 0x00);  // PHA.
+  runInstruction(cpu, 0xAB, 0x00);  // PLB.
+  REQUIRE(cpu.dataBank == 0x7E);
+
+  runInstruction(cpu, 0xA5, 0x00);  // LDA.
+  runInstruction(cpu, 0x48, 0x00);  // PHA.
+  runInstruction(cpu, 0xAB, 0x00);  // PLB.
+  REQUIRE_FALSE(cpu.dataBank.has_value());
+
+  // Block moves record both banks, and leave the destination in DBR.
+  auto pc = cpu.pc;
+  runInstruction(cpu, 0x54, 0x7E00);  // MVN $7E,$00.
+  REQUIRE(cpu.dataBank == 0x00);
+  REQUIRE(analysis->subroutines.at(0x8000).blockMoves.at(pc) ==
+          pair<u8, u8>(0x7E, 0x00));
+
+  delete analysis;
+}
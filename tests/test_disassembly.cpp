@@ -0,0 +1,512 @@
+#include <algorithm>
+#include <catch2/catch.hpp>
+#include <cstdlib>
+#include <filesystem>
+#include <fstream>
+
+#include "asar.hpp"
+
+#include "analysis.hpp"
+#include "disassembly.hpp"
+
+using namespace std;
+
+TEST_CASE("Disassembly is split into line items", "[disassembly]") {
+  Analysis analysis(*assemble("state_change"));
+  analysis.settings.emulationReset = false;
+  analysis.run();
+
+  Disassembly disassembly(&analysis);
+  auto items = disassembly.subroutineItems(0x8000);
+
+  // Label + 5 instructions + local label + blank line.
+  REQUIRE(items.size() == 8);
+
+  // Subroutine label.
+  auto& label = get<LabelLine>(items[0]);
+  REQUIRE(label.label.combinedLabel() == "reset");
+  REQUIRE(label.pc == PCPair{0x8000, 0x8000});
+  REQUIRE(label.isEntryPoint);
+
+  // Instruction with an automatic comment.
+  auto& sep = get<InstructionLine>(items[1]);
+  REQUIRE(sep.pc == 0x8000);
+  REQUIRE(sep.mnemonic == "sep");
+  REQUIRE(sep.operand == "#$30");
+  REQUIRE(!sep.operandLabel.has_value());
+  REQUIRE(sep.comment == "A: 8-bits, X: 8-bits");
+  REQUIRE(sep.status == InstructionStatus::None);
+
+  // Instruction referring to a subroutine.
+  auto& jsr = get<InstructionLine>(items[2]);
+  REQUIRE(jsr.operand == "sub_00800E");
+  REQUIRE(jsr.operandLabel->combinedLabel() == "sub_00800E");
+
+  // Local label.
+  auto& localLabel = get<LabelLine>(items[5]);
+  REQUIRE(localLabel.label.combinedLabel() == "reset.loc_00800B");
+  REQUIRE(!localLabel.isEntryPoint);
+
+  REQUIRE(holds_alternative<BlankLine>(items.back()));
+}
+
+TEST_CASE("Line items are rendered as plain text", "[disassembly]") {
+  Analysis analysis(*assemble("state_change"));
+  analysis.run();
+
+  Disassembly disassembly(&analysis);
+  REQUIRE(Disassembly::render(disassembly.subroutineItems(0x800E)) ==
+          "sub_00800E:\n"
+          "  rep #$30                      ; $00800E | A: 16-bits, X: 16-bits\n"
+          "  rts                           ; $008010 |\n"
+          "\n");
+}
+
+// The expected texts were captured from the renderer of the disassembly view
+// as it was before line items were introduced. That analysis also listed an
+// empty `nmi` subroutine for the unset NMI vector, left out here.
+TEST_CASE("The disassembly of a ROM matches its expected text",
+          "[disassembly]") {
+  auto disassemble = [](const string& name) {
+    Analysis analysis(*assemble(name));
+    analysis.settings.emulationReset = false;
+    analysis.run();
+    return Disassembly(&analysis).all();
+  };
+
+  REQUIRE(disassemble("php_plp") ==
+          "reset:\n"
+          "  sep #$20                      ; $008000 | A: 8-bits\n"
+          "  jsr sub_00800A                ; $008002 |\n"
+          "  lda #$12                      ; $008005 |\n"
+          ".loc_008007:\n"
+          "  jmp .loc_008007               ; $008007 |\n"
+          "\n"
+          "sub_00800A:\n"
+          "  php                           ; $00800A |\n"
+          "  rep #$20                      ; $00800B | A: 16-bits\n"
+          "  lda #$3456                    ; $00800D |\n"
+          "  plp                           ; $008010 |\n"
+          "  rts                           ; $008011 |\n"
+          "\n");
+
+  REQUIRE(disassemble("elidable_state_change") ==
+          "reset:\n"
+          "  sep #$20                      ; $008000 | A: 8-bits\n"
+          "  jsr sub_00800A                ; $008002 |\n"
+          "  lda #$78                      ; $008005 |\n"
+          ".loc_008007:\n"
+          "  jmp .loc_008007               ; $008007 |\n"
+          "\n"
+          "sub_00800A:\n"
+          "  bcs .loc_00800E               ; $00800A |\n"
+          "  lda #$12                      ; $00800C |\n"
+          ".loc_00800E:\n"
+          "  rep #$20                      ; $00800E | A: 16-bits\n"
+          "  lda #$3456                    ; $008010 |\n"
+          "  sep #$20                      ; $008013 | A: 8-bits\n"
+          "  rts                           ; $008015 |\n"
+          "\n");
+
+  REQUIRE(disassemble("state_change") ==
+          "reset:\n"
+          "  sep #$30                      ; $008000 | A: 8-bits, X: 8-bits\n"
+          "  jsr sub_00800E                ; $008002 |\n"
+          "  lda #$1234                    ; $008005 |\n"
+          "  ldx #$1234                    ; $008008 |\n"
+          ".loc_00800B:\n"
+          "  jmp .loc_00800B               ; $00800B |\n"
+          "\n"
+          "sub_00800E:\n"
+          "  rep #$30                      ; $00800E | A: 16-bits, X: 16-bits\n"
+          "  rts                           ; $008010 |\n"
+          "\n");
+}
+
+TEST_CASE("All the subroutines are rendered in order", "[disassembly]") {
+  Analysis analysis(*assemble("state_change"));
+  analysis.run();
+
+  Disassembly disassembly(&analysis);
+  auto text = disassembly.all();
+
+  // Each subroutine is followed by a blank line.
+  REQUIRE(text == Disassembly::render(disassembly.subroutineItems(0x8000)) +
+                      Disassembly::render(disassembly.subroutineItems(0x800E)));
+  REQUIRE(text.find("reset:\n") == 0);
+  REQUIRE(text.find("\n\nsub_00800E:\n") != string::npos);
+}
+
+TEST_CASE("Unlabeled targets are shown as relative offsets",
+          "[disassembly]") {
+  Analysis analysis(*assemble("unlabeled_target"));
+  analysis.addDataBlock(0x8002, 0x8012, DataType::Bytes);
+  analysis.addDataBlock(0x9000, 0x9001, DataType::Bytes);
+  analysis.run();
+
+  // The branch into the data block has no label to point to. The jump out
+  // of the subroutine keeps its address.
+  Disassembly disassembly(&analysis);
+  REQUIRE(Disassembly::render(disassembly.subroutineItems(0x8000)) ==
+          "reset:\n"
+          "  bra .loc_008012               ; $008000 |\n"
+          ".loc_008012:\n"
+          "  lda $10                       ; $008012 | D not set yet\n"
+          "  beq $-12                      ; $008014 |\n"
+          "  jmp $9000                     ; $008016 |\n"
+          "  ; Data block:\n"
+          "  db  $FF,$FF,$FF,$FF,$FF,$FF,$FF,$FF; $008002 |\n"
+          "  db  $FF,$FF,$FF,$FF,$FF,$FF,$FF,$FF; $00800A |\n"
+          "  ; Data block:\n"
+          "  db  $FF                       ; $009000 |\n"
+          "\n");
+}
+
+TEST_CASE("Multi-line comments span multiple lines", "[disassembly]") {
+  Analysis analysis(*assemble("call_chain"));
+  analysis.comments[0x8009] = "Call the innermost routine.\nNothing else.";
+  analysis.run();
+
+  Disassembly disassembly(&analysis);
+  REQUIRE(Disassembly::render(disassembly.subroutineItems(0x8009)) ==
+          "sub_008009:\n"
+          "  jsr sub_00800D                ; $008009 |"
+          " Call the innermost routine.\n"
+          "                                ;         | Nothing else.\n"
+          "  rts                           ; $00800C |\n"
+          "\n");
+}
+
+TEST_CASE("Local labels can be summarized", "[disassembly]") {
+  Analysis analysis(*assemble("preservation"));
+  analysis.settings.localLabelTable = true;
+  analysis.run();
+
+  Disassembly disassembly(&analysis);
+  REQUIRE(Disassembly::render(disassembly.subroutineItems(0x800E)) ==
+          "sub_00800E:\n"
+          "  ; Local labels:\n"
+          "  ;   $008013 .loc_008013\n"
+          "  phx                           ; $00800E |\n"
+          "  beq .loc_008013               ; $00800F |\n"
+          "  ldx #$00                      ; $008011 |\n"
+          ".loc_008013:\n"
+          "  plx                           ; $008013 |\n"
+          "  rts                           ; $008014 |\n"
+          "\n");
+
+  // Subroutines without local labels have no summary.
+  REQUIRE(Disassembly::render(disassembly.subroutineItems(0x8015)) ==
+          "sub_008015:\n"
+          "  ldx #$00                      ; $008015 |\n"
+          "  rts                           ; $008017 |\n"
+          "\n");
+}
+
+TEST_CASE("Pushed effective addresses are annotated", "[disassembly]") {
+  Analysis analysis(*assemble("push_effective_address"));
+  analysis.run();
+
+  Disassembly disassembly(&analysis);
+  REQUIRE(Disassembly::render(disassembly.subroutineItems(0x8000)) ==
+          "reset:\n"
+          "  jsr sub_008014                ; $008000 |\n"
+          "  pea $8014                     ; $008003 | Pushes sub_008014\n"
+          "  pea $8013                     ; $008006 | Pushes sub_008014-1\n"
+          "  pea $2100                     ; $008009 | Pushes !INIDISP\n"
+          "  pea $1234                     ; $00800C |\n"
+          "  pei ($10)                     ; $00800F | Pushes the word at $10 "
+          "(D not set yet)\n"
+          ".loc_008011:\n"
+          "  jmp .loc_008011               ; $008011 |\n"
+          "\n");
+}
+
+TEST_CASE("Accesses through another data bank are annotated",
+          "[disassembly]") {
+  Analysis analysis(*assemble("data_bank"));
+  analysis.run();
+
+  auto& instructions = analysis.subroutines.at(0x8000).instructions;
+  REQUIRE(Disassembly::instructionComment(instructions.at(0x8006)) ==
+          "Data at $7E2000");
+  // Same bank as the code, and unknown bank.
+  REQUIRE(Disassembly::instructionComment(instructions.at(0x800B)).empty());
+  REQUIRE(Disassembly::instructionComment(instructions.at(0x8011)).empty());
+}
+
+TEST_CASE("Dangling assertions show their address", "[disassembly]") {
+  Analysis analysis(*assemble("assertions"));
+  analysis.addAssertion(Assertion(AssertionType::Instruction), 0x8000, 0x8000);
+  analysis.addAssertion(Assertion(AssertionType::Subroutine), 0x9000, 0x9000);
+  analysis.addAssertion(Assertion(AssertionType::Instruction), 0x9000, 0x8000);
+  analysis.run();
+
+  // Neither the instruction nor the subroutine at $009000 were reached.
+  REQUIRE_FALSE(analysis.isDanglingAssertion({0x8000, 0x8000}));
+  REQUIRE(analysis.isDanglingAssertion({0x9000, 0x9000}));
+  REQUIRE(analysis.isDanglingAssertion({0x9000, 0x8000}));
+
+  Disassembly disassembly(&analysis);
+  REQUIRE(disassembly.subroutineLabel(0x8000) == "reset");
+  REQUIRE(disassembly.subroutineLabel(0x9000) == "unknown @ $009000");
+}
+
+TEST_CASE("The call graph is exported for Graphviz", "[disassembly]") {
+  Analysis analysis(*assemble("recursion"));
+  analysis.run();
+
+  // The recursive call leaves the state of `sub_008006` unknown.
+  REQUIRE(Disassembly(&analysis).callGraph() ==
+          "digraph calls {\n"
+          "  \"reset\" [shape=box];\n"
+          "  \"sub_008006\" [color=red];\n"
+          "  \"reset\" -> \"sub_008006\" [label=\"$008000\"];\n"
+          "  \"sub_008006\" -> \"sub_008006\" [label=\"$008009\"];\n"
+          "}\n");
+}
+
+TEST_CASE("The call graph is exported as JSON", "[disassembly]") {
+  Analysis analysis(*assemble("call_sites"));
+  analysis.run();
+  analysis.defineJumpTable(0x8003, {0, 0});
+  analysis.run();
+
+  // The tail jump from `first` and the call through the table are edges.
+  REQUIRE(Disassembly(&analysis).callGraphJSON() ==
+          "{\n"
+          "  \"nodes\": [\n"
+          "    {\"label\": \"reset\", \"pc\": 32768, "
+          "\"has_unknown_state_change\": false, \"is_entry_point\": true},\n"
+          "    {\"label\": \"sub_00800B\", \"pc\": 32779, "
+          "\"has_unknown_state_change\": false, \"is_entry_point\": false},\n"
+          "    {\"label\": \"sub_00800E\", \"pc\": 32782, "
+          "\"has_unknown_state_change\": false, \"is_entry_point\": false}\n"
+          "  ],\n"
+          "  \"edges\": [\n"
+          "    {\"source\": 32768, \"target\": 32779, \"call_site\": 32768, "
+          "\"indirect\": false},\n"
+          "    {\"source\": 32768, \"target\": 32782, \"call_site\": 32771, "
+          "\"indirect\": true},\n"
+          "    {\"source\": 32779, \"target\": 32782, \"call_site\": 32779, "
+          "\"indirect\": false}\n"
+          "  ]\n"
+          "}\n");
+}
+
+TEST_CASE("Hardware registers are named", "[disassembly]") {
+  Analysis analysis(*assemble("hardware_registers"));
+  analysis.run();
+
+  // Through bank $7E, $2100 is RAM.
+  Disassembly disassembly(&analysis);
+  REQUIRE(Disassembly::render(disassembly.subroutineItems(0x8000)) ==
+          "reset:\n"
+          "  sep #$20                      ; $008000 | A: 8-bits\n"
+          "  lda #$0F                      ; $008002 |\n"
+          "  sta !INIDISP                  ; $008004 |\n"
+          "  lda #$7E                      ; $008007 |\n"
+          "  pha                           ; $008009 |\n"
+          "  plb                           ; $00800A |\n"
+          "  sta $2100                     ; $00800B | Data at $7E2100\n"
+          ".loc_00800E:\n"
+          "  jmp .loc_00800E               ; $00800E |\n"
+          "\n");
+}
+
+TEST_CASE("Subroutine bytes are dumped", "[disassembly]") {
+  Analysis analysis(*assemble("state_change"));
+  analysis.run();
+
+  Disassembly disassembly(&analysis);
+  REQUIRE(disassembly.subroutineHexDump(0x800E) == "$00800E: C2 30 60\n");
+  REQUIRE(disassembly.subroutineHexDump(0x8000) ==
+          "$008000: E2 30 20 0E 80 A9 34 12 A2 34 12 4C 0B 80\n");
+}
+
+TEST_CASE("Hex dumps are clamped to the ROM", "[disassembly]") {
+  Analysis analysis(*assemble("state_change"));
+
+  Disassembly disassembly(&analysis);
+  REQUIRE(disassembly.hexDump(0x800E, 3) == "$00800E: C2 30 60\n");
+
+  // The dump stops where the ROM ends.
+  auto dump = disassembly.hexDump(0xFFF8, 0x100);
+  REQUIRE(dump.has_value());
+  REQUIRE(dump->find("$00FFF8:") == 0);
+  REQUIRE(count(dump->begin(), dump->end(), ' ') == 8);
+
+  // Unreasonable requests are refused.
+  REQUIRE_FALSE(disassembly.hexDump(0x8000, 0).has_value());
+  REQUIRE_FALSE(disassembly.hexDump(0x8000, 0xFFFFFFFF).has_value());
+  REQUIRE_FALSE(disassembly.hexDump(0x7E0000, 0x10).has_value());
+  REQUIRE_FALSE(disassembly.hexDump(0x8000, 0x10, 0).has_value());
+}
+
+TEST_CASE("Hex dumps can skip bytes", "[disassembly]") {
+  Analysis analysis(*assemble("state_change"));
+
+  Disassembly disassembly(&analysis);
+  REQUIRE(disassembly.hexDump(0x8000, 6, 2) == "$008000: E2 20 80\n");
+}
+
+TEST_CASE("The whole ROM is exported for asar", "[disassembly]") {
+  auto rom = assemble("overlapping_code");
+  Analysis analysis(*rom);
+  analysis.settings.emulationReset = false;
+  analysis.addEntryPoint("handler_8bit", 0x8008, State(true, true));
+  analysis.run();
+
+  auto text = Disassembly(&analysis).rom();
+  REQUIRE(text.find("org $008000\nreset:\n") != string::npos);
+  REQUIRE(text.find("  ; Warning: also decoded as `lda #$60`") !=
+          string::npos);
+  REQUIRE(text.find("  ; Warning: skipped `rts` at $00800A") !=
+          string::npos);
+  REQUIRE(text.find("  db $00,$00,$00,$00") != string::npos);
+
+  // Reassembling the export gives back the same ROM.
+  auto path = filesystem::temp_directory_path() / "gilgamesh_export.asm";
+  ofstream(path) << text;
+  auto sfcPath = path;
+  sfcPath.replace_extension(".sfc");
+  filesystem::remove(sfcPath);
+  REQUIRE(system(("asar " + path.string()).c_str()) == 0);
+  REQUIRE(ROM(sfcPath.string()).data == rom->data);
+}
+
+TEST_CASE("Operand sizes can be forced in the asar export",
+          "[disassembly]") {
+  auto rom = assemble("data_references");
+  Analysis analysis(*rom);
+  analysis.settings.sizeSuffixes = true;
+  analysis.run();
+
+  auto text = Disassembly(&analysis).rom();
+  REQUIRE(text.find("  sep.b #$30") != string::npos);
+  REQUIRE(text.find("  lda.w $9000") != string::npos);
+  REQUIRE(text.find("  sta.b $10") != string::npos);
+  REQUIRE(text.find("  sta.l $7E0010") != string::npos);
+  REQUIRE(text.find("  rts ") != string::npos);
+
+  // Reassembling the export gives back the same ROM.
+  auto path = filesystem::temp_directory_path() / "gilgamesh_suffixes.asm";
+  ofstream(path) << text;
+  auto sfcPath = path;
+  sfcPath.replace_extension(".sfc");
+  filesystem::remove(sfcPath);
+  REQUIRE(system(("asar " + path.string()).c_str()) == 0);
+  REQUIRE(ROM(sfcPath.string()).data == rom->data);
+}
+
+TEST_CASE("Data blocks are rendered as directives", "[disassembly]") {
+  Analysis analysis(*assemble("data_blocks"));
+  analysis.addDataBlock(0x8004, 0x8008, DataType::Text);
+  analysis.addDataBlock(0x8008, 0x800C, DataType::Pointers);
+  analysis.addDataBlock(0x800C, 0x800F, DataType::Bytes);
+  analysis.run();
+
+  Disassembly disassembly(&analysis);
+  auto text = Disassembly::render(disassembly.subroutineItems(0x8000));
+  REQUIRE(text ==
+          "reset:\n"
+          "  jsr sub_00800F                ; $008000 |\n"
+          "  nop                           ; $008003 |\n"
+          "  ; Data block:\n"
+          "  db  \"HI!\",$00                 ; $008004 |\n"
+          "\n");
+
+  auto pointers = Disassembly::render(
+      disassembly.dataBlockItems(analysis.dataBlocks.at(0x8008)));
+  REQUIRE(pointers ==
+          "  ; Data block:\n"
+          "  dw  $800F                     ; $008008 | -> sub_00800F\n"
+          "  dw  $1234                     ; $00800A |\n");
+
+  auto bytes = Disassembly::render(
+      disassembly.dataBlockItems(analysis.dataBlocks.at(0x800C)));
+  REQUIRE(bytes ==
+          "  ; Data block:\n"
+          "  db  $01,$02,$03               ; $00800C |\n");
+}
+
+TEST_CASE("Unasserted jump tables are previewed", "[disassembly]") {
+  Analysis analysis(*assemble("jump_tables"));
+  analysis.run();
+
+  Disassembly disassembly(&analysis);
+  auto text = Disassembly::render(disassembly.subroutineItems(0x8000));
+  REQUIRE(text ==
+          "reset:\n"
+          "  jsr ($8006,x)                 ; $008000 |\n"
+          "  ; Jump table preview (unasserted):\n"
+          "  ;   [$00] $008006 -> $008100\n"
+          "  ;   [$02] $008008 -> $008200\n"
+          "  ;   [$04] $00800A -> $000000 (outside ROM)\n"
+          "  ;   [$06] $00800C -> $000000 (outside ROM)\n"
+          "  ;   [$08] $00800E -> $000000 (outside ROM)\n"
+          "  ;   [$0A] $008010 -> $000000 (outside ROM)\n"
+          "  ;   [$0C] $008012 -> $000000 (outside ROM)\n"
+          "  ;   [$0E] $008014 -> $000000 (outside ROM)\n"
+          "\n");
+
+  // Once the jump table is asserted, the preview is no longer shown.
+  analysis.defineJumpTable(0x8000, {0, 2});
+  analysis.run();
+  text = Disassembly::render(disassembly.subroutineItems(0x8000));
+  REQUIRE(text ==
+          "reset:\n"
+          "  jsr ($8006,x)                 ; $008000 |\n"
+          ".loc_008003:\n"
+          "  jmp .loc_008003               ; $008003 |\n"
+          "\n");
+}
+
+TEST_CASE("Subroutines are documented in Markdown", "[disassembly]") {
+  Analysis analysis(*assemble("state_change"));
+  analysis.settings.emulationReset = false;
+  analysis.run();
+
+  Disassembly disassembly(&analysis);
+  REQUIRE(disassembly.subroutineMarkdown(0x800E) ==
+          "## sub_00800E\n"
+          "\n"
+          "- **Entry state:** A: 8-bits, X: 8-bits\n"
+          "- **Return states:** A: 16-bits, X: 16-bits\n"
+          "- **Callers:** `reset`\n"
+          "- **Callees:** none\n"
+          "\n"
+          "```asm\n"
+          "sub_00800E:\n"
+          "  rep #$30                      ; $00800E | A: 16-bits, X: 16-bits\n"
+          "  rts                           ; $008010 |\n"
+          "```\n");
+
+  auto markdown = disassembly.markdown();
+  REQUIRE(markdown.find("# TEST\n") == 0);
+  REQUIRE(markdown.find("\n## reset\n") != string::npos);
+  REQUIRE(markdown.find("\n## sub_00800E\n") != string::npos);
+  REQUIRE(markdown.find("- **Callees:** `sub_00800E`\n") != string::npos);
+}
+
+TEST_CASE("Labels and comments are exported as symbols", "[disassembly]") {
+  Analysis analysis(*assemble("migration_v1"));
+  analysis.run();
+  REQUIRE(analysis.renameLabel("print", 0x8011));
+  REQUIRE(analysis.labelRAM("player_x", 0x0020));
+  analysis.comments[0x8002] = "Initialize.\nThen print.";
+  analysis.run();
+
+  REQUIRE(Disassembly(&analysis).symbols() ==
+          "[labels]\n"
+          "00:8000 reset\n"
+          "00:8008 reset.loc_008008\n"
+          "00:800B sub_00800B\n"
+          "00:8011 print\n"
+          "00:8013 print.loc_008013\n"
+          "00:801E print.loc_00801E\n"
+          "7E:0020 player_x\n"
+          "\n"
+          "[comments]\n"
+          "00:8002 Initialize. Then print.\n");
+}
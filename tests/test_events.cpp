@@ -0,0 +1,83 @@
+#include <catch2/catch.hpp>
+#include <algorithm>
+#include <string>
+#include <vector>
+
+#include "asar.hpp"
+
+#include "analysis.hpp"
+#include "events.hpp"
+#include "utils.hpp"
+
+using namespace std;
+
+// Describe an event as a string, for easy comparison.
+static string describe(const AnalysisEvent& event) {
+  if (auto e = get_if<SubroutineDiscovered>(&event)) {
+    return format("subroutine $%06X %s", e->pc, e->label.c_str());
+  } else if (auto e = get_if<InstructionAdded>(&event)) {
+    return format("instruction $%06X $%06X", e->pc, e->subroutinePC);
+  } else if (auto e = get_if<UnknownStateChange>(&event)) {
+    return format("unknown $%06X $%06X %d", e->subroutinePC, e->pc,
+                  (int)e->reason);
+  } else if (auto e = get_if<AssertionConsumed>(&event)) {
+    return format("assertion $%06X %d", e->pc, (int)e->type);
+  }
+  return "";
+}
+
+// Run an analysis, collecting the events it emits.
+static vector<string> collectEvents(Analysis& analysis) {
+  vector<string> events;
+  analysis.eventSink = [&](auto& event) { events.push_back(describe(event)); };
+  analysis.run();
+  analysis.eventSink = nullptr;
+  return events;
+}
+
+TEST_CASE("Discoveries are reported in order", "[events]") {
+  Analysis analysis(*assemble("state_change"));
+  analysis.settings.emulationReset = false;
+  auto events = collectEvents(analysis);
+
+  auto reset = find(events.begin(), events.end(), "subroutine $008000 reset");
+  REQUIRE(reset + 9 <= events.end());
+  REQUIRE(vector<string>(reset, reset + 9) ==
+          vector<string>{
+              "subroutine $008000 reset",
+              "instruction $008000 $008000",
+              "instruction $008002 $008000",
+              "subroutine $00800E sub_00800E",
+              "instruction $00800E $00800E",
+              "instruction $008010 $00800E",
+              "instruction $008005 $008000",
+              "instruction $008008 $008000",
+              "instruction $00800B $008000",
+          });
+
+  // NMI points to RAM, so it's not analyzed.
+  auto nmi = find(events.begin(), events.end(), "subroutine $000000 nmi");
+  REQUIRE(nmi == events.end());
+}
+
+TEST_CASE("Unknown state changes and assertions are reported", "[events]") {
+  Analysis analysis(*assemble("assertions"));
+  auto events = collectEvents(analysis);
+
+  auto indirectJump = format("unknown $008005 $008005 %d",
+                             (int)UnknownReason::IndirectJump);
+  auto unknownCall =
+      format("unknown $008000 $008000 %d", (int)UnknownReason::Unknown);
+  auto callee = find(events.begin(), events.end(), indirectJump);
+  auto caller = find(events.begin(), events.end(), unknownCall);
+  REQUIRE(callee != events.end());
+  REQUIRE(caller != events.end());
+  REQUIRE(callee < caller);
+
+  analysis.addAssertion(Assertion(AssertionType::Instruction), 0x8000, 0x8000);
+  events = collectEvents(analysis);
+  auto assertion =
+      format("assertion $008000 %d", (int)AssertionType::Instruction);
+  REQUIRE(count(events.begin(), events.end(), assertion) == 1);
+  REQUIRE(count(events.begin(), events.end(), unknownCall) == 0);
+}
@@ -0,0 +1,82 @@
+#include <catch2/catch.hpp>
+
+#include "history.hpp"
+
+TEST_CASE("Navigation history moves back and forward", "[history]") {
+  NavigationHistory history;
+  REQUIRE(!history.current().has_value());
+  REQUIRE(!history.back().has_value());
+
+  history.push({0x8000, 0x8000});
+  history.push({0x9000, 0x9000});
+  history.push({0xA000, 0xA000});
+
+  REQUIRE(history.back() == PCPair{0x9000, 0x9000});
+  REQUIRE(history.back() == PCPair{0x8000, 0x8000});
+  REQUIRE(!history.back().has_value());
+  REQUIRE(history.current() == PCPair{0x8000, 0x8000});
+
+  REQUIRE(history.forward() == PCPair{0x9000, 0x9000});
+
+  // Visiting a new location discards the forward history.
+  history.push({0xB000, 0xB000});
+  REQUIRE(!history.forward().has_value());
+  REQUIRE(history.back() == PCPair{0x9000, 0x9000});
+}
+
+TEST_CASE("Navigation history returns to where a move started",
+          "[history]") {
+  NavigationHistory history;
+
+  // The first jump records both its origin and its destination.
+  history.visit(PCPair{0x8000, 0x8000}, {0x9000, 0x9000});
+  REQUIRE(history.current() == PCPair{0x9000, 0x9000});
+  history.visit(PCPair{0x9010, 0x9000}, {0xA000, 0xA000});
+
+  REQUIRE(history.back() == PCPair{0x9010, 0x9000});
+  REQUIRE(history.back() == PCPair{0x9000, 0x9000});
+  REQUIRE(history.back() == PCPair{0x8000, 0x8000});
+  REQUIRE(!history.back().has_value());
+  REQUIRE(history.forward() == PCPair{0x9000, 0x9000});
+
+  // Moving from the current location doesn't record it twice.
+  history.visit(PCPair{0x9000, 0x9000}, {0xB000, 0xB000});
+  REQUIRE(history.back() == PCPair{0x9000, 0x9000});
+  REQUIRE(history.back() == PCPair{0x8000, 0x8000});
+
+  // Without an origin, only the destination is recorded.
+  history.clear();
+  history.visit(std::nullopt, {0x8000, 0x8000});
+  REQUIRE(!history.back().has_value());
+}
+
+TEST_CASE("Edit history undoes and redoes edits", "[history]") {
+  EditHistory history;
+  REQUIRE(!history.undo().has_value());
+
+  history.push("initial");
+  history.push("first");
+  history.push("first");  // Nothing changed.
+  history.push("second");
+
+  REQUIRE(history.undo() == "first");
+  REQUIRE(history.undo() == "initial");
+  REQUIRE(!history.undo().has_value());
+  REQUIRE(history.redo() == "first");
+
+  // A new edit discards the redo history.
+  history.push("third");
+  REQUIRE(!history.redo().has_value());
+  REQUIRE(history.undo() == "first");
+
+  // Only the last edits can be undone.
+  history.clear();
+  for (size_t i = 0; i <= EditHistory::MAX_UNDO + 10; i++) {
+    history.push(std::to_string(i));
+  }
+  size_t undone = 0;
+  while (history.undo().has_value()) {
+    undone++;
+  }
+  REQUIRE(undone == EditHistory::MAX_UNDO);
+}
@@ -1,5 +1,8 @@
 #include <catch2/catch.hpp>
 
+#include "asar.hpp"
+
+#include "analysis.hpp"
 #include "instruction.hpp"
 
 TEST_CASE("LDA instruction is parsed correctly", "[instruction]") {
@@ -17,6 +20,46 @@ TEST_CASE("LDA instruction is parsed correctly", "[instruction]") {
   REQUIRE(!instruction.isControl());
 }
 
+TEST_CASE("Instructions know the flags they affect", "[instruction]") {
+  REQUIRE(std::size(AFFECTED_FLAGS) == Op::XCE + 1);
+  REQUIRE(AFFECTED_FLAGS[Op::CLC] == FLAG_C);
+  REQUIRE(AFFECTED_FLAGS[Op::LDA] == (FLAG_N | FLAG_Z));
+  REQUIRE(AFFECTED_FLAGS[Op::STA] == 0);
+
+  // SEP and REP affect the flags in their argument.
+  State state(false, false);
+  REQUIRE(Instruction(0x8000, 0x8000, 0xE2, 0x30, state).affectedFlags() ==
+          (FLAG_M | FLAG_X));
+  REQUIRE(Instruction(0x8000, 0x8000, 0xC2, 0x01, state).affectedFlags() ==
+          FLAG_C);
+  // BIT only affects N and V when reading from memory.
+  REQUIRE(Instruction(0x8000, 0x8000, 0x89, 0x80, state).affectedFlags() ==
+          FLAG_Z);
+  REQUIRE(Instruction(0x8000, 0x8000, 0x2C, 0x2100, state).affectedFlags() ==
+          (FLAG_N | FLAG_V | FLAG_Z));
+}
+
+TEST_CASE("Instruction cycles are estimated", "[instruction]") {
+  auto cycles = [](u8 opcode, u24 argument, State state) {
+    return Instruction(0x8000, 0x8000, opcode, argument, state).cycles();
+  };
+  State wide(false, false), narrow(true, true);
+
+  // LDA #, STA abs,X and INC abs with 8 and 16-bits A.
+  REQUIRE(cycles(0xA9, 0x12, narrow) == 2);
+  REQUIRE(cycles(0xA9, 0x1234, wide) == 3);
+  REQUIRE(cycles(0x9D, 0x2000, narrow) == 5);
+  REQUIRE(cycles(0x9D, 0x2000, wide) == 6);
+  REQUIRE(cycles(0xEE, 0x2000, narrow) == 6);
+  REQUIRE(cycles(0xEE, 0x2000, wide) == 8);
+  // ASL A doesn't access memory.
+  REQUIRE(cycles(0x0A, 0, wide) == 2);
+  // LDA abs,Y with 16-bits indexes always crosses pages.
+  REQUIRE(cycles(0xB9, 0x2000, State(true, false)) == 5);
+  // BRK in native mode.
+  REQUIRE(cycles(0x00, 0x00, narrow) == 8);
+}
+
 TEST_CASE("BRL instruction is parsed correctly", "[instruction]") {
   Instruction instruction(0x8000, 0x8000, 0x82, 0xFFFD, State(false, false));
 
@@ -31,3 +74,106 @@ TEST_CASE("BRL instruction is parsed correctly", "[instruction]") {
   REQUIRE(instruction.argumentString() == "$FFFD");
   REQUIRE(instruction.isControl());
 }
+
+TEST_CASE("Block move banks are decoded correctly", "[instruction]") {
+  // MVN $7E,$00 is encoded as 54 00 7E.
+  Instruction mvn(0x8000, 0x8000, 0x54, 0x7E00, State());
+  REQUIRE(mvn.argumentString() == "$7E,$00");
+  REQUIRE(mvn.moveBanks() == std::pair<u8, u8>(0x7E, 0x00));
+
+  Instruction mvp(0x8000, 0x8000, 0x44, 0x007F, State());
+  REQUIRE(mvp.argumentString() == "$00,$7F");
+  REQUIRE(mvp.moveBanks() == std::pair<u8, u8>(0x00, 0x7F));
+
+  Instruction lda(0x8000, 0x8000, 0xA9, 0x12, State(true, true));
+  REQUIRE_FALSE(lda.moveBanks().has_value());
+}
+
+TEST_CASE("Absolute data accesses are aliased", "[instruction]") {
+  SECTION("Hardware register") {
+    Instruction instruction(0x8000, 0x8000, 0x8D, 0x2100, State());
+    REQUIRE(instruction.argumentString() == "!INIDISP");
+    REQUIRE(instruction.argumentString(false) == "$2100");
+  }
+
+  SECTION("Indexed hardware register") {
+    Instruction instruction(0x8000, 0x8000, 0x99, 0x4302, State());
+    REQUIRE(instruction.argumentString() == "!A1T0L,y");
+  }
+
+  SECTION("Not a hardware register") {
+    Instruction instruction(0x8000, 0x8000, 0xAD, 0x2000, State());
+    REQUIRE(instruction.argumentString() == "$2000");
+  }
+}
+
+TEST_CASE("Long data accesses are aliased", "[instruction]") {
+  SECTION("Hardware register") {
+    Instruction instruction(0x8000, 0x8000, 0x8F, 0x002100, State());
+    REQUIRE(instruction.argumentString() == "!INIDISP");
+    REQUIRE(instruction.argumentString(false) == "$002100");
+  }
+
+  SECTION("Hardware register in a mirrored bank") {
+    Instruction instruction(0x8000, 0x8000, 0x8F, 0x802100, State());
+    REQUIRE(instruction.argumentString() == "!INIDISP|$800000");
+  }
+
+  SECTION("Indexed hardware register") {
+    Instruction instruction(0x8000, 0x8000, 0x9F, 0x002118, State());
+    REQUIRE(instruction.argumentString() == "!VMDATAL,x");
+  }
+
+  SECTION("Not a hardware register") {
+    Instruction instruction(0x8000, 0x8000, 0xAF, 0x7E2100, State());
+    REQUIRE(instruction.argumentString() == "$7E2100");
+  }
+
+  SECTION("Labeled address") {
+    Analysis analysis(*assemble("state_change"));
+    analysis.run();
+
+    Instruction instruction(0x8000, 0x8000, 0xBF, 0x00800E, State(),
+                            &analysis);
+    REQUIRE(instruction.argumentString() == "sub_00800E,x");
+  }
+}
+
+TEST_CASE("Accesses to named RAM variables are aliased", "[instruction]") {
+  Analysis analysis(*assemble("state_change"));
+  analysis.run();
+  REQUIRE(analysis.labelRAM("player_x", 0x7E0020));
+  REQUIRE(analysis.labelRAM("buffer", 0x7F1000));
+
+  SECTION("Direct page") {
+    Instruction instruction(0x8000, 0x8000, 0x85, 0x20, State(), &analysis);
+    REQUIRE(instruction.argumentString() == "player_x");
+    REQUIRE(instruction.argumentString(false) == "$20");
+  }
+
+  SECTION("Direct page indirect") {
+    Instruction instruction(0x8000, 0x8000, 0xB1, 0x20, State(), &analysis);
+    REQUIRE(instruction.argumentString() == "(player_x),y");
+  }
+
+  SECTION("Absolute, through the mirror of the low WRAM") {
+    Instruction instruction(0x8000, 0x8000, 0x8D, 0x0020, State(), &analysis);
+    REQUIRE(instruction.argumentString() == "player_x");
+  }
+
+  SECTION("Long") {
+    Instruction instruction(0x8000, 0x8000, 0x9F, 0x7F1000, State(),
+                            &analysis);
+    REQUIRE(instruction.argumentString() == "buffer,x");
+  }
+
+  SECTION("Unnamed variable") {
+    Instruction instruction(0x8000, 0x8000, 0x8D, 0x0021, State(), &analysis);
+    REQUIRE(instruction.argumentString() == "$0021");
+  }
+
+  SECTION("Immediate value") {
+    Instruction instruction(0x8000, 0x8000, 0xA9, 0x0020, State(), &analysis);
+    REQUIRE(instruction.argumentString() == "#$0020");
+  }
+}
@@ -0,0 +1,25 @@
+#include <catch2/catch.hpp>
+
+#include "jumptable.hpp"
+
+using namespace std;
+
+TEST_CASE("Jump table ranges are parsed", "[jumptable]") {
+  typedef optional<pair<u16, u16>> Range;
+
+  // A single offset.
+  REQUIRE(JumpTable::parseRange("6") == Range({6, 6}));
+  // Exclusive and inclusive ranges, with optional `$` prefixes.
+  REQUIRE(JumpTable::parseRange("0..8") == Range({0, 7}));
+  REQUIRE(JumpTable::parseRange("$0..=$A") == Range({0, 0xA}));
+
+  // Malformed or empty ranges.
+  REQUIRE(JumpTable::parseRange("") == nullopt);
+  REQUIRE(JumpTable::parseRange("0..") == nullopt);
+  REQUIRE(JumpTable::parseRange("..4") == nullopt);
+  REQUIRE(JumpTable::parseRange("0...4") == nullopt);
+  REQUIRE(JumpTable::parseRange("4..4") == nullopt);
+  REQUIRE(JumpTable::parseRange("6..=2") == nullopt);
+  REQUIRE(JumpTable::parseRange("10000") == nullopt);
+  REQUIRE(JumpTable::parseRange("zz") == nullopt);
+}
@@ -0,0 +1,81 @@
+#include <catch2/catch.hpp>
+
+#include "asar.hpp"
+
+#include "analysis.hpp"
+#include "migration.hpp"
+
+TEST_CASE("Annotations are migrated to a new ROM version", "[migration]") {
+  Analysis oldAnalysis(*assemble("migration_v1"));
+  oldAnalysis.run();
+  oldAnalysis.renameLabel("init", 0x800B);
+  oldAnalysis.renameLabel("print", 0x8011);
+  oldAnalysis.renameLabel("next", 0x8013, 0x8011);
+  oldAnalysis.comments[0x8018] = "Write to VRAM.";
+  oldAnalysis.comments[0x8002] = "Initialize.";
+  oldAnalysis.run();
+
+  // The new version has an extra instruction at the beginning.
+  Analysis newAnalysis(*assemble("migration_v2"));
+  Migration migration(&oldAnalysis, &newAnalysis);
+  migration.run();
+  newAnalysis.run();
+
+  SECTION("Subroutines are relocated") {
+    REQUIRE(migration.relocate(0x800B) == 0x800C);
+    REQUIRE(migration.relocate(0x8011) == 0x8012);
+    REQUIRE(migration.relocate(0x801E) == 0x801F);
+  }
+
+  SECTION("Labels and comments follow the code") {
+    REQUIRE(newAnalysis.subroutines.at(0x800C).label == "init");
+    REQUIRE(newAnalysis.subroutines.at(0x8012).label == "print");
+    REQUIRE(newAnalysis.getLabel(0x8014, 0x8012)->combinedLabel() ==
+            "print.next");
+    REQUIRE(newAnalysis.comments.at(0x8019) == "Write to VRAM.");
+  }
+
+  SECTION("Items that can't be matched are reported") {
+    // The reset subroutine has changed.
+    REQUIRE(migration.relocate(0x8000) == std::nullopt);
+    REQUIRE(newAnalysis.comments.count(0x8002) == 0);
+
+    auto& issues = migration.issues;
+    REQUIRE(issues.size() == 2);
+    REQUIRE(issues[0].problem == MigrationProblem::Unmatched);
+    REQUIRE(issues[0].item == "subroutine");
+    REQUIRE(issues[0].pc == 0x8000);
+    REQUIRE(issues[1].item == "comment");
+    REQUIRE(issues[1].pc == 0x8002);
+  }
+}
+
+TEST_CASE("Bookmarks and data blocks are migrated", "[migration]") {
+  Analysis oldAnalysis(*assemble("migration_v1"));
+  oldAnalysis.run();
+  oldAnalysis.addBookmark(0x8013, "Print loop.");
+  oldAnalysis.addBookmark(0x8002, "Initialize.");
+  oldAnalysis.addDataBlock(0x8018, 0x801B, DataType::Bytes);
+  oldAnalysis.addDataBlock(0x8000, 0x8002, DataType::Words);
+
+  Analysis newAnalysis(*assemble("migration_v2"));
+  Migration migration(&oldAnalysis, &newAnalysis);
+  migration.run();
+
+  // Items in the changed reset subroutine can't be matched.
+  REQUIRE(newAnalysis.bookmarks.size() == 1);
+  REQUIRE(newAnalysis.bookmarks.at(0x8014) == "Print loop.");
+  REQUIRE(newAnalysis.dataBlocks.size() == 1);
+  auto& block = newAnalysis.dataBlocks.at(0x8019);
+  REQUIRE(block.end == 0x801C);
+  REQUIRE(block.type == DataType::Bytes);
+
+  auto& issues = migration.issues;
+  REQUIRE(issues.size() == 3);
+  REQUIRE(issues[1].problem == MigrationProblem::Unmatched);
+  REQUIRE(issues[1].item == "bookmark");
+  REQUIRE(issues[1].pc == 0x8002);
+  REQUIRE(issues[2].problem == MigrationProblem::Unmatched);
+  REQUIRE(issues[2].item == "data block");
+  REQUIRE(issues[2].pc == 0x8000);
+}
@@ -0,0 +1,47 @@
+#include <catch2/catch.hpp>
+
+#include "asar.hpp"
+
+#include "analysis.hpp"
+#include "preservation.hpp"
+
+TEST_CASE("Preservation of index registers is detected", "[preservation]") {
+  Analysis analysis(*assemble("preservation"));
+  analysis.run();
+
+  SECTION("Saving and restoring a register preserves it") {
+    auto& subroutine = analysis.subroutines.at(0x800E);
+    REQUIRE(subroutine.xPreservation == Preservation::Preserved);
+    REQUIRE(subroutine.yPreservation == Preservation::Preserved);
+  }
+
+  SECTION("Modifying a register clobbers it") {
+    auto& subroutine = analysis.subroutines.at(0x8015);
+    REQUIRE(subroutine.xPreservation == Preservation::Clobbered);
+    REQUIRE(subroutine.yPreservation == Preservation::Preserved);
+  }
+
+  SECTION("Modifying a register on some paths clobbers it") {
+    auto& subroutine = analysis.subroutines.at(0x8018);
+    REQUIRE(subroutine.xPreservation == Preservation::Preserved);
+    REQUIRE(subroutine.yPreservation == Preservation::Clobbered);
+  }
+
+  SECTION("Callers inherit the clobbers of their callees") {
+    auto& subroutine = analysis.subroutines.at(0x801D);
+    REQUIRE(subroutine.preservation(IndexRegister::X) ==
+            Preservation::Clobbered);
+    REQUIRE(subroutine.preservation(IndexRegister::Y) ==
+            Preservation::Preserved);
+  }
+}
+
+TEST_CASE("Preservation is unknown for incomplete subroutines",
+          "[preservation]") {
+  Analysis analysis(*assemble("jump_tables"));
+  analysis.run();
+
+  auto& subroutine = analysis.subroutines.at(0x8000);
+  REQUIRE(subroutine.xPreservation == Preservation::Unknown);
+  REQUIRE(subroutine.yPreservation == Preservation::Unknown);
+}
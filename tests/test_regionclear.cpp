@@ -0,0 +1,38 @@
+#include <catch2/catch.hpp>
+
+#include "asar.hpp"
+
+#include "analysis.hpp"
+#include "disassembly.hpp"
+#include "regionclear.hpp"
+
+TEST_CASE("Loops clearing memory regions are recognized", "[regionclear]") {
+  Analysis analysis(*assemble("region_clears"));
+  analysis.run();
+
+  auto& regionClears = analysis.subroutines.at(0x8000).regionClears;
+  REQUIRE(regionClears.size() == 2);
+
+  SECTION("Counting down with STZ clears the region") {
+    auto& clear = regionClears.at(0x8004);
+    REQUIRE(clear.start == 0x0200);
+    REQUIRE(clear.end == 0x021F);
+    REQUIRE_FALSE(clear.value.has_value());
+    REQUIRE(regionClearDescription(clear) == "Clears $0200..$021F");
+  }
+
+  SECTION("Counting up with STA fills the region with a constant") {
+    auto& clear = regionClears.at(0x800E);
+    REQUIRE(clear.start == 0x7E2000);
+    REQUIRE(clear.end == 0x7E200F);
+    REQUIRE(clear.value == 0xFF);
+    REQUIRE(regionClearDescription(clear) ==
+            "Fills $7E2000..$7E200F with #$FF");
+  }
+
+  SECTION("Region clears are described in the disassembly") {
+    auto instruction = analysis.anyInstruction(0x8004);
+    REQUIRE(Disassembly::instructionComment(instruction) ==
+            "Clears $0200..$021F");
+  }
+}
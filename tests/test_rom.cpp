@@ -1,8 +1,12 @@
+#include <algorithm>
 #include <catch2/catch.hpp>
+#include <filesystem>
+#include <fstream>
 
 #include "asar.hpp"
 
 #include "rom.hpp"
+#include "utils.hpp"
 
 TEST_CASE("ROM class can recognize areas of RAM", "[rom]") {
   SECTION("Bank $00") {
@@ -32,6 +36,11 @@ TEST_CASE("ROM types are correctly recognized", "[rom]") {
     auto hirom = assemble("hirom");
     REQUIRE(hirom->romType == ROMType::HiROM);
   }
+
+  SECTION("SA-1") {
+    auto sa1 = assemble("sa1");
+    REQUIRE(sa1->romType == ROMType::SA1);
+  }
 }
 
 TEST_CASE("ROM size is correctly calculated", "[rom]") {
@@ -73,6 +82,27 @@ TEST_CASE("ROM address translation works correctly", "[rom]") {
     REQUIRE(hirom->translate(0xC08000) == 0x008000);
     REQUIRE(hirom->translate(0x400000) == 0x000000);
   }
+
+  SECTION("SA-1") {
+    // Banks $80-$BF don't mirror banks $00-$3F.
+    auto sa1 = assemble("sa1");
+    REQUIRE(sa1->translate(0x008000) == 0x000000);
+    REQUIRE(sa1->translate(0x208000) == 0x100000);
+    REQUIRE(sa1->translate(0x808000) == 0x200000);
+    REQUIRE(sa1->translate(0xA08000) == 0x300000);
+    REQUIRE(sa1->translate(0xC00000) == 0x000000);
+    REQUIRE(sa1->address(0x300000) == 0xA08000);
+
+    // The reset vector points to the code in bank $00.
+    REQUIRE(sa1->resetVector() == 0x8000);
+    REQUIRE(sa1->readByte(sa1->resetVector()) == 0x78);  // SEI
+  }
+
+  SECTION("Back to SNES addresses") {
+    REQUIRE(assemble("lorom")->address(0x000000) == 0x008000);
+    REQUIRE(assemble("lorom")->address(0x00FFFF) == 0x01FFFF);
+    REQUIRE(assemble("hirom")->address(0x008000) == 0xC08000);
+  }
 }
 
 TEST_CASE("ROM reads bytes correctly", "[rom]") {
@@ -85,6 +115,14 @@ TEST_CASE("ROM reads bytes correctly", "[rom]") {
   }
 }
 
+TEST_CASE("ROM reads sequences of bytes correctly", "[rom]") {
+  auto roms = {assemble("lorom"), assemble("hirom")};
+  for (auto rom : roms) {
+    REQUIRE(rom->read(Header::TITLE, 4) ==
+            std::vector<u8>{0x54, 0x45, 0x53, 0x54});  // TEST
+  }
+}
+
 TEST_CASE("ROM reads words correctly", "[rom]") {
   auto roms = {assemble("lorom"), assemble("hirom")};
   for (auto rom : roms) {
@@ -114,3 +152,120 @@ TEST_CASE("ROM's NMI vector is extracted correctly", "[rom]") {
     REQUIRE(rom->nmiVector() == 0x0000);
   }
 }
+
+TEST_CASE("Reading stops at the end of the ROM", "[rom]") {
+  auto lorom = assemble("lorom");
+  REQUIRE(lorom->read(0xFFF0, 0x100).size() == 0x10);
+  REQUIRE(lorom->read(0x7E0000, 0x10).empty());
+}
+
+TEST_CASE("Copier headers are stripped", "[rom]") {
+  auto lorom = assemble("lorom");
+  REQUIRE_FALSE(lorom->hasCopierHeader());
+
+  auto path = std::filesystem::temp_directory_path() / "gilgamesh_smc.sfc";
+  std::ofstream file(path, std::ios::binary);
+  file << std::string(ROM::COPIER_HEADER_SIZE, '\0');
+  file.write((const char*)lorom->data.data(), lorom->data.size());
+  file.close();
+
+  ROM headered(path.string());
+  std::filesystem::remove(path);
+
+  REQUIRE(headered.hasCopierHeader());
+  REQUIRE(headered.realSize() == lorom->realSize());
+  REQUIRE(headered.romType == lorom->romType);
+  REQUIRE(headered.resetVector() == lorom->resetVector());
+  REQUIRE(headered.checksum() == lorom->checksum());
+}
+
+TEST_CASE("Truncated ROMs without a header can be inspected", "[rom]") {
+  auto path = std::filesystem::temp_directory_path() / "gilgamesh_small.sfc";
+  std::ofstream file(path, std::ios::binary);
+  file << std::string(0x1000, '\xEA');
+  file.close();
+
+  ROM rom(path.string());
+  std::filesystem::remove(path);
+
+  REQUIRE(rom.title().empty());
+  REQUIRE(rom.resetVector() == 0);
+  REQUIRE(rom.nmiVector() == 0);
+  REQUIRE(rom.realSize() == 0x1000);
+}
+
+TEST_CASE("ExHiROM ROMs larger than 4MB are mapped correctly", "[rom]") {
+  std::vector<u8> data(0x600000, 0x00);
+  std::copy_n("TEST", 4, data.begin() + 0x40FFC0);
+  data[0x40FFD5] = 0x35;  // ExHiROM, FastROM.
+  data[0x40FFFC] = 0x00;  // Reset vector ($8000).
+  data[0x40FFFD] = 0x80;
+  data[0x100000] = 0x11;
+  data[0x108000] = 0x22;
+  data[0x500000] = 0x33;
+  data[0x508000] = 0x44;
+
+  auto path = std::filesystem::temp_directory_path() / "gilgamesh_exhirom.sfc";
+  std::ofstream file(path, std::ios::binary);
+  file.write((const char*)data.data(), data.size());
+  file.close();
+
+  ROM rom(path.string());
+  std::filesystem::remove(path);
+
+  REQUIRE(rom.romType == ROMType::ExHiROM);
+  REQUIRE(rom.title() == "TEST");
+  REQUIRE(rom.resetVector() == 0x8000);
+
+  // Banks $80-$FF map the first 4MB, banks $00-$7D the rest.
+  REQUIRE(rom.readByte(0xD00000) == 0x11);
+  REQUIRE(rom.readByte(0x908000) == 0x22);
+  REQUIRE(rom.readByte(0x500000) == 0x33);
+  REQUIRE(rom.readByte(0x108000) == 0x44);
+  REQUIRE(rom.address(0x500000) == 0x500000);
+  REQUIRE(rom.address(0x100000) == 0xD00000);
+
+  // Past the end of the ROM.
+  REQUIRE(rom.isROM(0x5FFFFF));
+  REQUIRE_FALSE(rom.isROM(0x600000));
+}
+
+TEST_CASE("The checksum in the header can be fixed", "[rom]") {
+  ROM rom = *assemble("lorom");
+  // The fixture holds neither the checksum nor its complement.
+  REQUIRE_FALSE(rom.hasValidChecksum());
+
+  REQUIRE(rom.fixChecksum());
+  REQUIRE(rom.hasValidChecksum());
+  REQUIRE(rom.readWord(Header::COMPLEMENT) == (u16)~rom.headerChecksum());
+
+  // Patching a byte invalidates the checksum.
+  rom.data[0x0100] = 0x42;
+  REQUIRE_FALSE(rom.hasValidChecksum());
+  REQUIRE(rom.computeChecksum() == (u16)(rom.headerChecksum() + 0x42));
+  REQUIRE(rom.fixChecksum());
+  REQUIRE(rom.hasValidChecksum());
+}
+
+TEST_CASE("CRC-32 is computed correctly", "[rom]") {
+  REQUIRE(crc32({'1', '2', '3', '4', '5', '6', '7', '8', '9'}) == 0xCBF43926);
+}
+
+TEST_CASE("Byte patterns are found in the ROM", "[rom]") {
+  auto rom = assemble("state_change");
+
+  REQUIRE(ROM::parsePattern("A9 ?? 12") ==
+          BytePattern{0xA9, std::nullopt, 0x12});
+  REQUIRE(ROM::parsePattern("A9??12") == ROM::parsePattern("A9 ?? 12"));
+  REQUIRE_FALSE(ROM::parsePattern("A9 1").has_value());
+  REQUIRE_FALSE(ROM::parsePattern("A9 G1").has_value());
+  REQUIRE_FALSE(ROM::parsePattern("").has_value());
+
+  // LDA #$1234 and LDX #$1234.
+  REQUIRE(rom->find(*ROM::parsePattern("A9 34 12")) ==
+          std::vector<u24>{0x8005});
+  REQUIRE(rom->find(*ROM::parsePattern("?? 34 12")) ==
+          std::vector<u24>{0x8005, 0x8008});
+  REQUIRE(rom->find(*ROM::parsePattern("?? 34 12"), 1) ==
+          std::vector<u24>{0x8005});
+}
@@ -0,0 +1,97 @@
+#include <catch2/catch.hpp>
+#include <cstdio>
+#include <cstdlib>
+#include <filesystem>
+
+#include "asar.hpp"
+
+#include "analysis.hpp"
+#include "settings.hpp"
+
+using namespace std;
+namespace fs = std::filesystem;
+
+// Save profiles in a temporary directory.
+static void useTemporaryProfiles() {
+  auto path = fs::temp_directory_path() / "gilgamesh_tests";
+  fs::remove_all(path);
+  setenv("XDG_DATA_HOME", path.c_str(), true);
+}
+
+TEST_CASE("Profiles can be saved, listed and loaded", "[settings]") {
+  useTemporaryProfiles();
+  REQUIRE(Settings::profiles().empty());
+
+  Settings settings;
+  settings.subroutinePrefix = "func_";
+  settings.stateInference = false;
+  REQUIRE(settings.saveProfile("game"));
+  REQUIRE(Settings().saveProfile("default"));
+
+  REQUIRE(Settings::profiles() == vector<string>{"default", "game"});
+
+  Settings loaded;
+  REQUIRE(loaded.loadProfile("game"));
+  REQUIRE(loaded == settings);
+  REQUIRE(!loaded.loadProfile("missing"));
+}
+
+TEST_CASE("Label prefixes are configurable", "[settings]") {
+  Analysis analysis(*assemble("state_change"));
+  analysis.settings.subroutinePrefix = "func_";
+  analysis.settings.localLabelPrefix = "label_";
+  analysis.run();
+
+  REQUIRE(analysis.subroutines.at(0x800E).label == "func_00800E");
+  auto label = analysis.getLabel(0x800B, 0x8000);
+  REQUIRE(label->combinedLabel() == "reset.label_00800B");
+}
+
+TEST_CASE("Per-ROM settings win over profiles", "[settings]") {
+  useTemporaryProfiles();
+  Settings profile;
+  profile.subroutinePrefix = "profile_";
+  profile.saveProfile("game");
+
+  // Save an analysis that disagrees with the profile.
+  Analysis saved(*assemble("state_change"));
+  saved.settings.subroutinePrefix = "saved_";
+  saved.save();
+
+  // Apply the profile first, then the per-ROM save.
+  Analysis analysis(*assemble("state_change"));
+  analysis.settings.loadProfile("game");
+  REQUIRE(analysis.settings.subroutinePrefix == "profile_");
+  REQUIRE(analysis.load());
+  REQUIRE(analysis.settings.subroutinePrefix == "saved_");
+
+  analysis.run();
+  REQUIRE(analysis.subroutines.at(0x800E).label == "saved_00800E");
+
+  remove(analysis.rom.savePath().c_str());
+}
+
+TEST_CASE("Strict mode stops at opcodes that never appear in code",
+          "[settings]") {
+  Analysis analysis(*assemble("strict_mode"));
+
+  // By default, everything gets decoded.
+  analysis.run();
+  auto& subroutine = analysis.subroutines.at(0x8000);
+  REQUIRE(subroutine.instructions.size() == 4);
+  REQUIRE(subroutine.instructions.at(0x8004)->operation() == Op::WDM);
+
+  // In strict mode, the analysis stops at the WDM.
+  analysis.settings.strictMode = true;
+  analysis.run();
+  auto& strictSubroutine = analysis.subroutines.at(0x8000);
+  REQUIRE(strictSubroutine.instructions.size() == 2);
+  REQUIRE(strictSubroutine.unknownStateChanges.count(0x8004) == 1);
+  REQUIRE(strictSubroutine.isUnknownBecauseOf(
+      UnknownReason::SuspectInstruction));
+
+  // The set of opcodes is configurable.
+  analysis.settings.dataOpcodes.erase(0x42);
+  analysis.run();
+  REQUIRE(analysis.subroutines.at(0x8000).instructions.size() == 4);
+}
@@ -0,0 +1,96 @@
+#include <catch2/catch.hpp>
+#include <cstdio>
+
+#include "asar.hpp"
+
+#include "analysis.hpp"
+#include "sweep.hpp"
+
+using namespace std;
+
+TEST_CASE("Sweep candidates are checked for sanity", "[sweep]") {
+  Analysis analysis(*assemble("sweep"));
+  analysis.run();
+  Sweep sweep(&analysis);
+
+  // A subroutine that nothing calls.
+  auto orphan = sweep.evaluate(0x8010);
+  REQUIRE(orphan.has_value());
+  REQUIRE(orphan->end == 0x8018);
+  REQUIRE(orphan->instructions == 4);
+  REQUIRE(orphan->suspicion == 0);
+
+  // Too short to be trusted.
+  REQUIRE(!sweep.evaluate(0x8020).has_value());
+  // Data containing a BRK.
+  REQUIRE(!sweep.evaluate(0x8030).has_value());
+  // Code already reached by the analysis.
+  REQUIRE(!sweep.evaluate(0x8002).has_value());
+  // Code running out of the ROM.
+  REQUIRE(!sweep.evaluate(0xFFFF).has_value());
+}
+
+TEST_CASE("Sweeps skip data blocks", "[sweep]") {
+  Analysis analysis(*assemble("sweep"));
+  analysis.run();
+  analysis.addDataBlock(0x8014, 0x8016, DataType::Bytes);
+
+  // The orphan subroutine runs into the data block.
+  REQUIRE(!Sweep(&analysis).evaluate(0x8010).has_value());
+  REQUIRE(Sweep(&analysis).run(Sweep::END));
+  REQUIRE(analysis.sweepQuarantine.empty());
+}
+
+TEST_CASE("Sweeps can be interrupted and resumed", "[sweep]") {
+  Analysis analysis(*assemble("sweep"));
+  analysis.run();
+
+  // The first start after `reset` is tried, and the cursor moves past it.
+  REQUIRE(!Sweep(&analysis).run(1));
+  REQUIRE(analysis.sweepCursor == 0x8006);
+  REQUIRE(analysis.sweepQuarantine.empty());
+
+  // The cursor is saved with the analysis.
+  analysis.save();
+  Analysis loaded(*assemble("sweep"));
+  REQUIRE(loaded.load());
+  REQUIRE(loaded.sweepCursor == 0x8006);
+  remove(loaded.rom.savePath().c_str());
+
+  // Resume and complete the sweep.
+  loaded.run();
+  REQUIRE(Sweep(&loaded).run(Sweep::END));
+  REQUIRE(loaded.sweepQuarantine.size() == 1);
+  REQUIRE(loaded.sweepQuarantine.count(0x8010) == 1);
+}
+
+TEST_CASE("Sweep results are quarantined until accepted", "[sweep]") {
+  Analysis analysis(*assemble("sweep"));
+  analysis.run();
+  REQUIRE(Sweep(&analysis).run(Sweep::END));
+
+  // The analysis itself is untouched.
+  analysis.run();
+  REQUIRE(analysis.subroutines.count(0x8010) == 0);
+  REQUIRE(analysis.sweepQuarantine.count(0x8010) == 1);
+
+  SECTION("Accepted code becomes a subroutine") {
+    REQUIRE(analysis.acceptSweepCandidate(0x8010));
+    REQUIRE(!analysis.acceptSweepCandidate(0x8010));
+    REQUIRE(analysis.sweepQuarantine.empty());
+
+    analysis.run();
+    auto& subroutine = analysis.subroutines.at(0x8010);
+    REQUIRE(subroutine.label == "sweep_008010");
+    REQUIRE(subroutine.instructions.size() == 4);
+  }
+
+  SECTION("Rejected code is not proposed again") {
+    REQUIRE(analysis.rejectSweepCandidate(0x8010));
+    REQUIRE(analysis.sweepQuarantine.empty());
+
+    analysis.sweepCursor = 0;
+    REQUIRE(Sweep(&analysis).run(Sweep::END));
+    REQUIRE(analysis.sweepQuarantine.empty());
+  }
+}
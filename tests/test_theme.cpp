@@ -0,0 +1,29 @@
+#include <catch2/catch.hpp>
+
+#include "theme.hpp"
+
+using namespace std;
+
+TEST_CASE("Built-in themes can be selected", "[theme]") {
+  REQUIRE(Theme::names() == vector<string>{"default", "mono", "colorblind"});
+  REQUIRE(Theme::current().name == "default");
+
+  REQUIRE_FALSE(Theme::select("neon"));
+  REQUIRE(Theme::current().name == "default");
+
+  REQUIRE(Theme::select("colorblind"));
+  REQUIRE(Theme::current().unknown == "#D55E00");
+  REQUIRE(Theme::select("default"));
+}
+
+TEST_CASE("The mono theme has no colors", "[theme]") {
+  auto theme = Theme::find("mono");
+  REQUIRE(theme != nullptr);
+  for (auto role :
+       {&Theme::assertion, &Theme::verifiedAssertion, &Theme::guessAssertion,
+        &Theme::entryPoint, &Theme::jumpTable, &Theme::partialJumpTable,
+        &Theme::unknown, &Theme::highlightedText, &Theme::opcode, &Theme::label,
+        &Theme::comment, &Theme::currentLine}) {
+    REQUIRE((theme->*role).empty());
+  }
+}